@@ -0,0 +1,211 @@
+use elements::hashes::{sha256, Hash, HashEngine};
+use elements::secp256k1_zkp as secp256k1;
+use secp256k1::{schnorr, Keypair, Message, Parity, PublicKey, Scalar, SecretKey, XOnlyPublicKey};
+use simfony::elements;
+
+/// BIP-340 adaptor signature (pre-signature).
+///
+/// The pre-signature becomes a valid BIP-340 signature once it is completed
+/// with the discrete logarithm of the adaptor point.
+/// Conversely, anyone who knows the pre-signature and the completed signature
+/// can extract the discrete logarithm of the adaptor point.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AdaptorSignature {
+    /// Public nonce `R = k * G + T`. Its y-coordinate is always even.
+    nonce: PublicKey,
+    /// Scalar `s' = k + e * x`.
+    s: SecretKey,
+}
+
+fn tagged_hash(tag: &str, chunks: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = sha256::Hash::hash(tag.as_bytes());
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag_hash.as_ref());
+    engine.input(tag_hash.as_ref());
+    for chunk in chunks {
+        engine.input(chunk);
+    }
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// Compute the BIP-340 challenge `e = hash(R || P || m)`.
+///
+/// Return `None` if the hash overflows the curve order (negligible probability).
+fn challenge(nonce: &XOnlyPublicKey, public_key: &XOnlyPublicKey, msg: &Message) -> Option<Scalar> {
+    let hash = tagged_hash(
+        "BIP0340/challenge",
+        &[&nonce.serialize(), &public_key.serialize(), msg.as_ref()],
+    );
+    Scalar::from_be_bytes(hash).ok()
+}
+
+impl AdaptorSignature {
+    /// Sign the message `msg` with the key pair `keypair`,
+    /// encrypted under the adaptor point `adaptor_point`.
+    ///
+    /// The nonce is derived deterministically from the secret key, the adaptor point and the message.
+    pub fn sign(keypair: &Keypair, adaptor_point: &PublicKey, msg: &Message) -> Self {
+        let (public_key, parity) = keypair.x_only_public_key();
+        let secret_key = match parity {
+            Parity::Even => keypair.secret_key(),
+            Parity::Odd => keypair.secret_key().negate(),
+        };
+
+        // Retry with a different nonce until the combined nonce has an even y-coordinate.
+        // This way, the completed signature needs no adjustment of the adaptor secret.
+        for counter in 0u32.. {
+            let nonce_hash = tagged_hash(
+                "simfony-webide/adaptor-nonce",
+                &[
+                    &secret_key.secret_bytes(),
+                    &adaptor_point.serialize(),
+                    msg.as_ref(),
+                    &counter.to_be_bytes(),
+                ],
+            );
+            let k = match SecretKey::from_slice(&nonce_hash) {
+                Ok(k) => k,
+                Err(..) => continue,
+            };
+            let nonce =
+                match PublicKey::from_secret_key(secp256k1::SECP256K1, &k).combine(adaptor_point) {
+                    Ok(nonce) => nonce,
+                    Err(..) => continue,
+                };
+            let (nonce_x, nonce_parity) = nonce.x_only_public_key();
+            if nonce_parity == Parity::Odd {
+                continue;
+            }
+            let e = match challenge(&nonce_x, &public_key, msg) {
+                Some(e) => e,
+                None => continue,
+            };
+            let s = match secret_key
+                .mul_tweak(&e)
+                .and_then(|ex| ex.add_tweak(&Scalar::from(k)))
+            {
+                Ok(s) => s,
+                Err(..) => continue,
+            };
+            return Self { nonce, s };
+        }
+        unreachable!("nonce search should terminate")
+    }
+
+    /// Check that the pre-signature is valid for the given public key, adaptor point and message.
+    ///
+    /// This checks `s' * G == R - T + e * P`.
+    pub fn verify(
+        &self,
+        public_key: &XOnlyPublicKey,
+        adaptor_point: &PublicKey,
+        msg: &Message,
+    ) -> bool {
+        let secp = secp256k1::SECP256K1;
+        let (nonce_x, nonce_parity) = self.nonce.x_only_public_key();
+        if nonce_parity == Parity::Odd {
+            return false;
+        }
+        let e = match challenge(&nonce_x, public_key, msg) {
+            Some(e) => e,
+            None => return false,
+        };
+        let lhs = PublicKey::from_secret_key(secp, &self.s);
+        let rhs = public_key
+            .public_key(Parity::Even)
+            .mul_tweak(secp, &e)
+            .and_then(|ep| ep.combine(&self.nonce))
+            .and_then(|rhs| rhs.combine(&adaptor_point.negate(secp)));
+        rhs.map(|rhs| lhs == rhs).unwrap_or(false)
+    }
+
+    /// Complete the pre-signature with the discrete logarithm of the adaptor point.
+    ///
+    /// The result is a BIP-340 signature `(R, s' + t)`.
+    pub fn complete(&self, adaptor_secret: &SecretKey) -> Option<schnorr::Signature> {
+        let s = self.s.add_tweak(&Scalar::from(*adaptor_secret)).ok()?;
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(&self.nonce.x_only_public_key().0.serialize());
+        bytes[32..].copy_from_slice(&s.secret_bytes());
+        schnorr::Signature::from_slice(&bytes).ok()
+    }
+
+    /// Extract the discrete logarithm of the adaptor point from a completed signature.
+    ///
+    /// Return `None` if the signature is not a completion of this pre-signature.
+    pub fn extract_secret(
+        &self,
+        signature: &schnorr::Signature,
+        adaptor_point: &PublicKey,
+    ) -> Option<SecretKey> {
+        let bytes = signature.serialize();
+        if bytes[..32] != self.nonce.x_only_public_key().0.serialize() {
+            return None;
+        }
+        let s = SecretKey::from_slice(&bytes[32..]).ok()?;
+        let adaptor_secret = s.add_tweak(&Scalar::from(self.s.negate())).ok()?;
+        match PublicKey::from_secret_key(secp256k1::SECP256K1, &adaptor_secret) == *adaptor_point {
+            true => Some(adaptor_secret),
+            false => None,
+        }
+    }
+
+    /// Serialize the pre-signature as 33 bytes of compressed nonce followed by 32 bytes of scalar.
+    pub fn serialize(&self) -> [u8; 65] {
+        let mut bytes = [0; 65];
+        bytes[..33].copy_from_slice(&self.nonce.serialize());
+        bytes[33..].copy_from_slice(&self.s.secret_bytes());
+        bytes
+    }
+
+    /// Deserialize a pre-signature from 65 bytes.
+    ///
+    /// Return `None` if the bytes are ill-formatted.
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 65 {
+            return None;
+        }
+        let nonce = PublicKey::from_slice(&bytes[..33]).ok()?;
+        let s = SecretKey::from_slice(&bytes[33..]).ok()?;
+        Some(Self { nonce, s })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::SigningKeys;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn adaptor_roundtrip() {
+        let signing_keys = SigningKeys::default();
+        let msg = Message::from_digest([0xab; 32]);
+
+        for index in 0..4 {
+            let keypair = &signing_keys.secret_keys[index];
+            let public_key = signing_keys.public_keys[index];
+            let adaptor_secret = signing_keys.secret_keys[25 - index].secret_key();
+            let adaptor_point = PublicKey::from_secret_key(secp256k1::SECP256K1, &adaptor_secret);
+
+            let pre_signature = AdaptorSignature::sign(keypair, &adaptor_point, &msg);
+            assert!(pre_signature.verify(&public_key, &adaptor_point, &msg));
+            assert_eq!(
+                Some(pre_signature),
+                AdaptorSignature::from_slice(&pre_signature.serialize())
+            );
+
+            let signature = pre_signature.complete(&adaptor_secret).unwrap();
+            secp256k1::SECP256K1
+                .verify_schnorr(&signature, &msg, &public_key)
+                .expect("completed signature should be valid");
+            assert_eq!(
+                Some(adaptor_secret),
+                pre_signature.extract_secret(&signature, &adaptor_point)
+            );
+
+            let other_msg = Message::from_digest([0xcd; 32]);
+            assert!(!pre_signature.verify(&public_key, &adaptor_point, &other_msg));
+        }
+    }
+}
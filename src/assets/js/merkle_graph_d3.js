@@ -1,7 +1,9 @@
 
 let svg, zoom, zoomInitialTransform;
 
-export function load_merkle_graph_js(tree_data){
+// Nodes are given as a list where children are referenced by their index.
+// Each shared node is drawn once, and further occurrences become references to it.
+export function load_merkle_graph_js(nodes, root, selected, on_select){
 
     let horizontal = true;
     let nodeSize = [162, 40] // x, y
@@ -12,7 +14,7 @@ export function load_merkle_graph_js(tree_data){
     let svg_holder = document.getElementById("merkle_graph_holder")
     svg_holder.innerHTML = "";
 
-    let nodeCount = countNodes(tree_data)
+    let nodeCount = nodes.length
     if (nodeCount > 1200){
         let div = document.createElement("div");
         div.innerText = `Too many nodes to display graph. Node count: ${nodeCount}`;
@@ -47,8 +49,9 @@ export function load_merkle_graph_js(tree_data){
     let nodePositions = horizontal ? [nodeSize[1] + nodeGap[1], nodeSize[0] + nodeGap[0]] : [nodeSize[0] + nodeGap[0], nodeSize[1] + nodeGap[1]]
     let tree = d3.tree()
         .nodeSize(nodePositions)
-    let root = d3.hierarchy(tree_data)
-    let links = tree(root).links()
+    let tree_data = expandDag(nodes, root)
+    let hierarchy = d3.hierarchy(tree_data)
+    let links = tree(hierarchy).links()
 
     zoom = d3.zoom()
         .scaleExtent([.2, 4])
@@ -63,6 +66,7 @@ export function load_merkle_graph_js(tree_data){
         .data(links)
         .enter()
         .append('path')
+            .classed('link-taken', d => d.target.data.taken)
             .attr('d', d => {
                 let halfway_y = (d.target.y + d.source.y) / 2
                 return horizontal
@@ -71,9 +75,14 @@ export function load_merkle_graph_js(tree_data){
             })
 
     let node_groups = svg_g.selectAll('g')
-        .data(root.descendants())
+        .data(hierarchy.descendants())
         .join("g")
-        
+        .on('click', (e, d) => {
+            node_groups.classed('node-selected', n => n.data.id == d.data.id)
+            on_select(d.data.id)
+        })
+        .classed('node-selected', d => d.data.id == selected)
+
     node_groups.append("rect")
         .attr('x', d => horizontal ? d.y : d.x)
         .attr('y', d => horizontal ? d.x : d.y)
@@ -82,7 +91,7 @@ export function load_merkle_graph_js(tree_data){
         .attr('width', nodeSize[0])
         .attr('height', nodeSize[1])
         .attr('transform', `translate(-${nodeSize[0]/2}, -${nodeSize[1]/2})`)
-        .attr('class', 'node-rect')
+        .attr('class', d => `node-rect ${d.data.classes}`)
 
     node_groups.append('text')
         .attr('x', d => horizontal ? d.y : d.x)
@@ -104,7 +113,16 @@ export function load_merkle_graph_js(tree_data){
         .attr('text-anchor', 'middle')
         .attr('dominant-baseline', 'middle')
         .attr('class', 'node-full-text')
-        .text(d => d.data.text)
+        .text(d => `#${d.data.id} ${d.data.text}`)
+}
+
+// Highlight the nodes with the given ids, such as the nodes of the code that is selected in the editor.
+export function highlightMerkleNodes(ids){
+    if (!svg)
+        return
+    let highlighted = new Set(ids)
+    svg.selectAll('g')
+        .classed('node-highlighted', d => d !== undefined && highlighted.has(d.data.id))
 }
 
 export function manualZoom(mode){
@@ -116,10 +134,20 @@ export function manualZoom(mode){
         svg.transition().call(zoom.transform, zoomInitialTransform)
 }
 
-function countNodes(node, count = 0){
-    count++
-    for (let n of node.children){
-        count += countNodes(n)
+function expandDag(nodes, root){
+    let visited = new Set()
+    let expand = (id) => {
+        let node = nodes[id]
+        if (visited.has(id)){
+            return {id, text: `↪ #${id}`, classes: `${node.classes} node-shared`, children: []}
+        }
+        visited.add(id)
+        let children = node.children.map((child, index) => {
+            let expanded = expand(child)
+            expanded.taken = node.taken[index]
+            return expanded
+        })
+        return {id, text: node.text, classes: node.classes, children}
     }
-    return count
-}
\ No newline at end of file
+    return expand(root)
+}
@@ -0,0 +1,34 @@
+//! Web worker that checks Simfony programs off the main thread.
+//!
+//! The worker receives a JSON [`check::Request`] per message
+//! and answers with a JSON [`check::Response`].
+
+#[path = "../check.rs"]
+mod check;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent};
+
+fn main() {
+    console_error_panic_hook::set_once();
+
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let response_scope = scope.clone();
+    let on_message = Closure::<dyn Fn(MessageEvent)>::new(move |event: MessageEvent| {
+        let request = match event
+            .data()
+            .as_string()
+            .and_then(|data| serde_json::from_str::<check::Request>(&data).ok())
+        {
+            Some(request) => request,
+            None => return,
+        };
+        let response =
+            serde_json::to_string(&check::check(&request)).expect("response should serialize");
+        let _result = response_scope.post_message(&JsValue::from_str(&response));
+    });
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // The worker handles messages until it is terminated
+    on_message.forget();
+}
@@ -0,0 +1,302 @@
+use std::ops::{Add, AddAssign};
+
+use crate::dag::Dag;
+use crate::source_map::SourceMap;
+
+/// Static cost bound of a part of a Simplicity program in milliweight units.
+///
+/// Costs saturate instead of overflowing.
+#[derive(Copy, Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Cost(pub u64);
+
+impl Add for Cost {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl AddAssign for Cost {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+/// Encoded size and static cost bound of a part of a Simplicity program.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Usage {
+    /// Bits of the encoded program and witness data that only this part uses.
+    pub bits: u64,
+    pub cost: Cost,
+}
+
+impl Add for Usage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            bits: self.bits + rhs.bits,
+            cost: self.cost + rhs.cost,
+        }
+    }
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+/// Return the part of the cost bound of the program that each node of the `dag` is responsible for.
+///
+/// The cost bound of the root sums the cost of each node as often as the node is executed.
+/// Cases count only their more expensive child, like the cost bound does.
+/// The costs of all nodes add up to the cost bound of the root.
+pub fn node_costs(dag: &Dag) -> Vec<Cost> {
+    let nodes = dag.nodes();
+
+    // Children that count towards the cost bound of each node
+    let counted_children: Vec<&[usize]> = nodes
+        .iter()
+        .map(|node| match node.is_case {
+            true => node
+                .children
+                .iter()
+                .max_by_key(|&&child| nodes[child].milli_weight)
+                .map_or(&[][..], std::slice::from_ref),
+            false => &node.children[..],
+        })
+        .collect();
+    // How often each node is counted by the cost bound of the root
+    let mut multiplicities = vec![0u64; nodes.len()];
+    if let Some(root) = multiplicities.last_mut() {
+        *root = 1;
+    }
+    // Parents come after their children, so parents are visited first in reverse order
+    for id in (0..nodes.len()).rev() {
+        for &child in counted_children[id] {
+            multiplicities[child] = multiplicities[child].saturating_add(multiplicities[id]);
+        }
+    }
+
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(id, node)| {
+            let children_weight: u64 = counted_children[id]
+                .iter()
+                .map(|&child| nodes[child].milli_weight)
+                .sum();
+            let own_weight = node.milli_weight.saturating_sub(children_weight);
+            Cost(own_weight.saturating_mul(multiplicities[id]))
+        })
+        .collect()
+}
+
+/// Part of the program that the encoded bits of a node belong to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum BitOwner {
+    Statement(usize),
+    /// The node belongs to no statement.
+    Unmapped,
+    /// Several statements use the node, which is encoded once for all of them.
+    Shared,
+}
+
+impl From<Option<usize>> for BitOwner {
+    fn from(statement: Option<usize>) -> Self {
+        match statement {
+            Some(index) => Self::Statement(index),
+            None => Self::Unmapped,
+        }
+    }
+}
+
+/// Return the part of the program that the encoded bits of each node of the `dag` belong to.
+///
+/// A node belongs to the statement that produces it, given by `owners`.
+/// Other nodes, such as constants, belong to the statement of their parents in `node_statements`
+/// if all parents agree, and they are shared otherwise.
+fn bit_owners(
+    dag: &Dag,
+    owners: &[Option<usize>],
+    node_statements: &[Option<usize>],
+) -> Vec<BitOwner> {
+    let nodes = dag.nodes();
+    let mut parents: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+    for (id, node) in nodes.iter().enumerate() {
+        for &child in &node.children {
+            parents[child].push(id);
+        }
+    }
+
+    (0..nodes.len())
+        .map(|id| {
+            if let Some(index) = owners[id] {
+                return BitOwner::Statement(index);
+            }
+            let mut statements = parents[id].iter().map(|&parent| node_statements[parent]);
+            match statements.next() {
+                // The root
+                None => BitOwner::from(node_statements[id]),
+                Some(first) => match statements.all(|statement| statement == first) {
+                    true => BitOwner::from(first),
+                    false => BitOwner::Shared,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Breakdown of the encoded size and the cost bound of a program by source code.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Breakdown {
+    pub total: Usage,
+    /// Usage of each statement of the source map.
+    pub statements: Vec<Usage>,
+    /// Usage of each function, in the order in which the functions appear in the source.
+    pub functions: Vec<(String, Usage)>,
+    /// Usage of the nodes that belong to no statement.
+    ///
+    /// The bits include the headers and the padding of the encoding.
+    pub unmapped: Usage,
+    /// Bits of the nodes that several statements use.
+    pub shared_bits: u64,
+}
+
+impl Breakdown {
+    /// Break down the program of the `dag`, whose encoding is `encoded_bits` long,
+    /// by the statements of the `source_map`.
+    pub fn new(dag: &Dag, encoded_bits: u64, source_map: &SourceMap) -> Self {
+        let statements = source_map.statements();
+        let mut breakdown = Self {
+            statements: vec![Usage::default(); statements.len()],
+            ..Self::default()
+        };
+        for statement in statements {
+            if !breakdown
+                .functions
+                .iter()
+                .any(|(function, _)| function == &statement.function)
+            {
+                breakdown
+                    .functions
+                    .push((statement.function.clone(), Usage::default()));
+            }
+        }
+        let mut add = |owner: Option<usize>, usage: Usage| match owner {
+            Some(index) => {
+                breakdown.statements[index] += usage;
+                let function = &statements[index].function;
+                if let Some((_, function_usage)) = breakdown
+                    .functions
+                    .iter_mut()
+                    .find(|(name, _)| name == function)
+                {
+                    *function_usage += usage;
+                }
+            }
+            None => breakdown.unmapped += usage,
+        };
+
+        let node_statements = source_map.node_statements(dag);
+        let bit_owners = bit_owners(dag, &source_map.node_owners(dag), &node_statements);
+        let (mut node_bits, mut shared_bits) = (0, 0);
+        for (id, cost) in node_costs(dag).into_iter().enumerate() {
+            add(node_statements[id], Usage { bits: 0, cost });
+            let bits = dag.nodes()[id].bits;
+            node_bits += bits;
+            let owner = match bit_owners[id] {
+                BitOwner::Statement(index) => Some(index),
+                BitOwner::Unmapped => None,
+                BitOwner::Shared => {
+                    shared_bits += bits;
+                    continue;
+                }
+            };
+            add(
+                owner,
+                Usage {
+                    bits,
+                    cost: Cost::default(),
+                },
+            );
+        }
+        // Headers and padding
+        add(
+            None,
+            Usage {
+                bits: encoded_bits.saturating_sub(node_bits),
+                cost: Cost::default(),
+            },
+        );
+
+        breakdown.shared_bits = shared_bits;
+        breakdown.total = breakdown
+            .statements
+            .iter()
+            .fold(breakdown.unmapped, |sum, &usage| sum + usage);
+        breakdown.total.bits += breakdown.shared_bits;
+        breakdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simfony::CompiledProgram;
+
+    use super::*;
+    use crate::check;
+    use crate::examples;
+    use crate::test_util::satisfied_and_tx_env;
+    use crate::util::{HashedData, SigningKeys};
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn break_down_examples() {
+        let signing_keys = SigningKeys::default();
+        let hashed_data = HashedData::default();
+
+        for name in examples::keys() {
+            let example = examples::get(name).unwrap();
+            let (satisfied, _) = satisfied_and_tx_env(example, &signing_keys, &hashed_data);
+            let program = satisfied.redeem().clone();
+            let dag = Dag::new(&program, None);
+
+            let costs = node_costs(&dag);
+            let total = costs.iter().fold(Cost::default(), |sum, &cost| sum + cost);
+            assert_eq!(dag.get(dag.root()).unwrap().milli_weight, total.0, "{name}");
+            let (program_bytes, witness_bytes) = program.encode_to_vec();
+            let encoded_bits = 8 * (program_bytes.len() + witness_bytes.len()) as u64;
+
+            // Each variant of the template is compiled with the parameters of the example
+            let text = example.template_text();
+            let source_map = SourceMap::new(text, |text| {
+                let arguments = example.arguments(&signing_keys.public_keys, &hashed_data.hashes);
+                let compiled = CompiledProgram::new(text, arguments)?;
+                Ok(check::commit_cmrs(&compiled.commit()))
+            })
+            .unwrap();
+            let breakdown = Breakdown::new(&dag, encoded_bits, &source_map);
+            assert_eq!(total, breakdown.total.cost, "{name}");
+            assert_eq!(encoded_bits, breakdown.total.bits, "{name}");
+            let statements_total = breakdown
+                .statements
+                .iter()
+                .fold(breakdown.unmapped, |sum, &usage| sum + usage);
+            let functions_total = breakdown
+                .functions
+                .iter()
+                .fold(breakdown.unmapped, |sum, (_, usage)| sum + *usage);
+            for usage in [statements_total, functions_total] {
+                assert_eq!(total, usage.cost, "{name}");
+                assert_eq!(encoded_bits, usage.bits + breakdown.shared_bits, "{name}");
+            }
+            assert!(breakdown
+                .functions
+                .iter()
+                .any(|(function, _)| function == "main"));
+        }
+    }
+}
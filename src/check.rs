@@ -0,0 +1,167 @@
+//! Check of Simfony programs that runs in a web worker,
+//! so long compilations do not block the editor.
+//!
+//! The worker binary includes this module on its own,
+//! so it must not depend on other modules of the crate.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use simfony::parse::ParseFromStr;
+use simfony::simplicity;
+use simplicity::dag::{DagLike, MaxSharing};
+use simplicity::jet::Elements;
+use simplicity::node::{self, CommitNode};
+use simplicity::Cmr;
+
+/// Request to check the program `text`.
+///
+/// Symbols must already be replaced by values.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    /// Number that identifies the request, so outdated responses can be dropped.
+    pub generation: u64,
+    pub text: String,
+    /// Variants of the program text where one statement is marked.
+    ///
+    /// The nodes of each variant are compared with the nodes of the program,
+    /// which builds the source map of the program off the main thread.
+    #[serde(default)]
+    pub variants: Vec<String>,
+}
+
+/// Result of checking a program.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    /// Number of the request that this response answers.
+    pub generation: u64,
+    /// CMR of the program as hex, if the program compiles.
+    pub cmr: Option<String>,
+    /// Errors of compiling and satisfying the program.
+    pub errors: Vec<String>,
+    /// CMRs as hex of the nodes of the program that each variant does not compile to,
+    /// if variants were requested and the program compiles.
+    ///
+    /// A variant that does not compile changes no node.
+    #[serde(default)]
+    pub changed: Option<Vec<Vec<String>>>,
+}
+
+/// Return the CMRs of all nodes of the given `program`.
+pub fn commit_cmrs(program: &CommitNode<Elements>) -> HashSet<Cmr> {
+    program
+        .post_order_iter::<MaxSharing<node::Commit<Elements>>>()
+        .map(|data| data.node.cmr())
+        .collect()
+}
+
+fn compile(text: &str) -> Result<simfony::CompiledProgram, String> {
+    simfony::Arguments::parse_from_str(text)
+        .map_err(|error| error.to_string())
+        .and_then(|args| simfony::CompiledProgram::new(text, args))
+}
+
+/// Return the CMRs as hex of the `original` nodes that the program `variant` does not compile to.
+fn changed_cmrs(original: &HashSet<Cmr>, variant: &str) -> Vec<String> {
+    match compile(variant) {
+        Ok(compiled) => original
+            .difference(&commit_cmrs(&compiled.commit()))
+            .map(Cmr::to_string)
+            .collect(),
+        Err(..) => vec![],
+    }
+}
+
+/// Compile and satisfy the program of the given `request`.
+pub fn check(request: &Request) -> Response {
+    let text = request.text.as_str();
+    let compiled = compile(text);
+    let cmr = compiled
+        .as_ref()
+        .ok()
+        .map(|compiled| compiled.commit().cmr().to_string());
+    let changed = match &compiled {
+        Ok(compiled) if !request.variants.is_empty() => {
+            let original = commit_cmrs(&compiled.commit());
+            let changed = request
+                .variants
+                .iter()
+                .map(|variant| changed_cmrs(&original, variant))
+                .collect();
+            Some(changed)
+        }
+        _ => None,
+    };
+    let satisfied = compiled.and_then(|compiled| {
+        let witness = simfony::WitnessValues::parse_from_str(text)?;
+        compiled.satisfy(witness)
+    });
+    Response {
+        generation: request.generation,
+        cmr,
+        errors: satisfied.err().into_iter().collect(),
+        changed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(text: &str) -> Request {
+        Request {
+            generation: 1,
+            text: text.to_string(),
+            variants: vec![],
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn check_programs() {
+        let response = check(&request("fn main() {}"));
+        assert_eq!(1, response.generation);
+        assert!(response.cmr.is_some());
+        assert!(response.errors.is_empty());
+
+        // Missing witness values fail satisfaction, after compilation
+        let response = check(&request("fn main() {\n    let a: u32 = witness::A;\n}"));
+        assert!(response.cmr.is_some());
+        assert_eq!(1, response.errors.len());
+
+        let response = check(&request("fn main() {\n    let a: u32 = ;\n}"));
+        assert_eq!(None, response.cmr);
+        assert_eq!(1, response.errors.len());
+        assert_eq!(None, response.changed);
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn check_variants() {
+        let text = "fn main() {\n    assert!(jet::is_zero_32(0));\n}";
+        let variants = vec![
+            "fn main() {\n    { let webide_mark: u1 = 0; assert!(jet::is_zero_32(0)) };\n}"
+                .to_string(),
+            "fn main() {".to_string(),
+        ];
+        let response = check(&Request {
+            generation: 2,
+            text: text.to_string(),
+            variants: variants.clone(),
+        });
+        let changed = response.changed.unwrap();
+        assert_eq!(2, changed.len());
+        assert!(!changed[0].is_empty());
+        assert!(changed[1].is_empty());
+        // Checks without variants build no source map
+        assert_eq!(None, check(&request(text)).changed);
+
+        // Variants are ignored if the program does not compile
+        let response = check(&Request {
+            generation: 3,
+            text: "fn main() {".to_string(),
+            variants,
+        });
+        assert_eq!(None, response.changed);
+    }
+}
@@ -0,0 +1,493 @@
+use std::fmt;
+use std::ops::Range;
+
+use itertools::Itertools;
+use simfony::simplicity::jet::Elements;
+use simfony::ResolvedType;
+
+use crate::form::FormValue;
+use crate::hash::CustomPreimage;
+use crate::syntax::{self, Token, TokenKind};
+use crate::witness::{self, Module};
+
+/// Kind of a completion item.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CompletionKind {
+    Jet,
+    Function,
+    Type,
+    Witness,
+    Param,
+    /// Namespace such as `jet::`.
+    Namespace,
+}
+
+impl fmt::Display for CompletionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CompletionKind::Jet => "jet",
+            CompletionKind::Function => "fn",
+            CompletionKind::Type => "type",
+            CompletionKind::Witness => "witness",
+            CompletionKind::Param => "param",
+            CompletionKind::Namespace => "mod",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Item that the user can choose to complete the word at the cursor.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Completion {
+    pub kind: CompletionKind,
+    pub label: String,
+    /// Signature or type of the item.
+    pub detail: String,
+    /// Text that replaces the word at the cursor.
+    pub insertion: String,
+    /// Byte range of the insertion that is selected after the insertion.
+    pub selection: Range<usize>,
+}
+
+impl Completion {
+    fn new(kind: CompletionKind, label: String, detail: String) -> Self {
+        let insertion = label.clone();
+        let end = insertion.len();
+        Self {
+            kind,
+            label,
+            detail,
+            insertion,
+            selection: end..end,
+        }
+    }
+}
+
+/// Return the byte range of the word that ends at byte offset `cursor`.
+///
+/// Words are identifiers with an optional namespace, such as `jet::add_32` or `witness::`.
+pub fn word_range(text: &str, cursor: usize) -> Range<usize> {
+    let before = &text[..cursor];
+    let mut start = cursor;
+    loop {
+        let rest = &before[..start];
+        if let Some(c) = rest
+            .chars()
+            .next_back()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            start -= c.len_utf8();
+        } else if rest.ends_with("::") {
+            start -= 2;
+        } else {
+            break;
+        }
+    }
+    start..cursor
+}
+
+/// Return a call of the function `name` with a placeholder value for each parameter,
+/// and the byte range of the first placeholder.
+pub fn call_skeleton(name: &str, params: &[ResolvedType]) -> (String, Range<usize>) {
+    let args = params
+        .iter()
+        .map(|ty| FormValue::placeholder(ty).to_string())
+        .collect::<Vec<_>>();
+    let insertion = format!("{name}({})", args.join(", "));
+    let first_arg_start = name.len() + 1;
+    let first_arg_len = args.first().map_or(0, String::len);
+    (insertion, first_arg_start..first_arg_start + first_arg_len)
+}
+
+/// Signature of a function that the program defines.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionSignature {
+    pub name: String,
+    /// Names and types of the parameters.
+    pub params: Vec<(String, String)>,
+    pub output: Option<String>,
+}
+
+impl fmt::Display for FunctionSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params = self
+            .params
+            .iter()
+            .map(|(name, ty)| format!("{name}: {ty}"))
+            .join(", ");
+        write!(f, "fn {}({params})", self.name)?;
+        if let Some(output) = &self.output {
+            write!(f, " -> {output}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Return the signatures of the functions that the program `text` defines.
+///
+/// Comments are ignored. Definitions that are still being typed are skipped.
+pub fn function_signatures(text: &str) -> Vec<FunctionSignature> {
+    let tokens: Vec<Token> = syntax::tokenize(text)
+        .into_iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+        .collect();
+    let mut signatures = vec![];
+
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Keyword || token.text != "fn" {
+            continue;
+        }
+        let (name, open) = match (tokens.get(index + 1), tokens.get(index + 2)) {
+            (Some(name), Some(open)) if name.kind == TokenKind::Function && open.text == "(" => {
+                (name, open)
+            }
+            _ => continue,
+        };
+        let close = match syntax::matching_bracket(&tokens, open.start) {
+            Some(close) => close,
+            None => continue,
+        };
+        let params = syntax::split_top_level(&text[open.end()..close])
+            .into_iter()
+            .filter_map(|param| param.split_once(':'))
+            .map(|(name, ty)| (name.trim().to_string(), ty.trim().to_string()))
+            .collect();
+        let after_params = &text[close + 1..];
+        let output = after_params
+            .find('{')
+            .map(|index| after_params[..index].trim())
+            .and_then(|arrow| arrow.strip_prefix("->"))
+            .map(|output| output.trim().to_string());
+
+        signatures.push(FunctionSignature {
+            name: name.text.to_string(),
+            params,
+            output,
+        });
+    }
+
+    signatures
+}
+
+/// Return the names and definitions of the type aliases that the program `text` defines.
+pub fn type_alias_definitions(text: &str) -> Vec<(String, String)> {
+    syntax::type_aliases(text)
+        .into_iter()
+        .filter_map(|name| {
+            let (_, after_name) = text.split_once(&format!("type {name}"))?;
+            let definition = after_name.trim_start().strip_prefix('=')?;
+            let definition = definition.split(';').next()?.trim();
+            Some((name.to_string(), definition.to_string()))
+        })
+        .collect()
+}
+
+/// Name that replaces the word that is being completed, so the rest of the program can be parsed.
+const COMPLETED_NAME: &str = "WEBIDE_COMPLETED";
+
+/// Return the names and types of the witness values or parameters of the program `text`,
+/// without the word at byte range `exclude`, which is being completed.
+///
+/// The names come from [`witness::declarations`], followed by the names that the module assigns
+/// but that the program does not use yet.
+/// If the program cannot be parsed, then the names that the program mentions are returned without types.
+pub fn entry_names(
+    text: &str,
+    module: Module,
+    exclude: Range<usize>,
+    custom_preimages: &[CustomPreimage],
+) -> Vec<(String, String)> {
+    let namespace = format!("{}::", module_namespace(module));
+    let mut completed = text.to_string();
+    completed.replace_range(exclude.clone(), &format!("{namespace}{COMPLETED_NAME}"));
+
+    let declarations = match witness::declarations(&completed, custom_preimages) {
+        Ok(declarations) => declarations,
+        Err(..) => {
+            return syntax::tokenize(text)
+                .into_iter()
+                .filter(|token| token.kind == TokenKind::Witness && token.start != exclude.start)
+                .filter_map(|token| token.text.strip_prefix(namespace.as_str()))
+                .filter(|name| !name.is_empty())
+                .unique()
+                .map(|name| (name.to_string(), String::new()))
+                .collect();
+        }
+    };
+    let declared = declarations
+        .into_iter()
+        .filter(|(entry_module, _, _)| *entry_module == module)
+        .map(|(_, name, ty)| (name, ty));
+    let assigned = witness::assignments(&completed, module, custom_preimages).unwrap_or_default();
+    declared
+        .chain(assigned)
+        .filter(|(name, _)| name != COMPLETED_NAME)
+        .unique_by(|(name, _)| name.clone())
+        .map(|(name, ty)| (name, ty.to_string()))
+        .collect()
+}
+
+fn jet_completions() -> Vec<Completion> {
+    Elements::ALL
+        .iter()
+        .map(|&jet| {
+            let params = simfony::jet::source_type(jet);
+            let output = simfony::jet::target_type(jet);
+            let label = format!("jet::{jet}");
+            let detail = format!("fn({}) -> {output}", params.iter().join(", "));
+            let resolved_params = params
+                .iter()
+                .map(|ty| ty.resolve_builtin().ok())
+                .collect::<Option<Vec<ResolvedType>>>()
+                .unwrap_or_default();
+            let (insertion, selection) = call_skeleton(&label, &resolved_params);
+            Completion {
+                kind: CompletionKind::Jet,
+                label,
+                detail,
+                insertion,
+                selection,
+            }
+        })
+        .collect()
+}
+
+fn function_completions(text: &str) -> Vec<Completion> {
+    function_signatures(text)
+        .into_iter()
+        .filter(|signature| signature.name != "main")
+        .map(|signature| {
+            let mut completion = Completion::new(
+                CompletionKind::Function,
+                signature.name.clone(),
+                signature.to_string(),
+            );
+            let (insertion, selection) = match signature.params.first() {
+                Some((first_param, _)) => {
+                    let args = signature.params.iter().map(|(name, _)| name).join(", ");
+                    let start = signature.name.len() + 1;
+                    (
+                        format!("{}({args})", signature.name),
+                        start..start + first_param.len(),
+                    )
+                }
+                None => {
+                    let end = signature.name.len() + 2;
+                    (format!("{}()", signature.name), end..end)
+                }
+            };
+            completion.insertion = insertion;
+            completion.selection = selection;
+            completion
+        })
+        .collect()
+}
+
+fn type_completions(text: &str) -> Vec<Completion> {
+    let user_aliases = type_alias_definitions(text)
+        .into_iter()
+        .map(|(name, definition)| Completion::new(CompletionKind::Type, name, definition));
+    let builtins = syntax::BUILTIN_TYPES
+        .iter()
+        .chain(syntax::BUILTIN_ALIASES.iter())
+        .map(|name| {
+            Completion::new(
+                CompletionKind::Type,
+                name.to_string(),
+                "built-in".to_string(),
+            )
+        });
+    user_aliases.chain(builtins).collect()
+}
+
+fn namespace_completions() -> Vec<Completion> {
+    ["jet::", "witness::", "param::"]
+        .into_iter()
+        .map(|namespace| {
+            Completion::new(
+                CompletionKind::Namespace,
+                namespace.to_string(),
+                "".to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Return the completions of the word that ends at byte offset `cursor`,
+/// and the byte range of that word.
+///
+/// Items whose label starts with the word come first.
+/// Symbols are typed after the `custom_preimages` of the hash store.
+pub fn completions(
+    text: &str,
+    cursor: usize,
+    custom_preimages: &[CustomPreimage],
+) -> (Range<usize>, Vec<Completion>) {
+    let range = word_range(text, cursor);
+    let word = &text[range.clone()];
+    let entry_completions = |module: Module, kind: CompletionKind| {
+        entry_names(text, module, range.clone(), custom_preimages)
+            .into_iter()
+            .map(|(name, ty)| {
+                let label = format!("{}::{name}", module_namespace(module));
+                Completion::new(kind, label, ty)
+            })
+            .collect::<Vec<_>>()
+    };
+    let candidates = match word.split_once("::") {
+        Some(("jet", _)) => jet_completions(),
+        Some(("witness", _)) => entry_completions(Module::Witness, CompletionKind::Witness),
+        Some(("param", _)) => entry_completions(Module::Param, CompletionKind::Param),
+        Some(..) => vec![],
+        None => {
+            let mut candidates = function_completions(text);
+            candidates.extend(type_completions(text));
+            candidates.extend(namespace_completions());
+            candidates
+        }
+    };
+
+    let lowercase_word = word.to_lowercase();
+    let mut completions: Vec<Completion> = candidates
+        .into_iter()
+        .filter(|completion| completion.label != word)
+        .filter(|completion| completion.label.to_lowercase().contains(&lowercase_word))
+        .collect();
+    completions
+        .sort_by_key(|completion| !completion.label.to_lowercase().starts_with(&lowercase_word));
+    (range, completions)
+}
+
+fn module_namespace(module: Module) -> &'static str {
+    match module {
+        Module::Witness => "witness",
+        Module::Param => "param",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: &str = r#"type Pair = (u32, Either<u8, u16>);
+
+fn checksig(pk: Pubkey, sig: Signature) {
+    jet::bip_0340_verify((pk, jet::sig_all_hash()), sig)
+}
+
+// fn commented(a: u8) -> u8 {}
+fn first(pair: Pair, b: (u8, u8)) -> u32 {
+    let (a, _): Pair = pair;
+    a
+}
+
+fn main() {
+    checksig(param::ALICE, witness::SIG);
+    let pair: Pair = witness::PAIR;
+    witness::
+}"#;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn find_word() {
+        let text = "let a = jet::add_";
+        assert_eq!(8..text.len(), word_range(text, text.len()));
+        let text = "let a = witness::";
+        assert_eq!(8..text.len(), word_range(text, text.len()));
+        let text = "(a, b)";
+        assert_eq!(6..6, word_range(text, text.len()));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn parse_definitions() {
+        let signatures = function_signatures(PROGRAM);
+        assert_eq!(
+            vec![
+                "fn checksig(pk: Pubkey, sig: Signature)",
+                "fn first(pair: Pair, b: (u8, u8)) -> u32",
+                "fn main()",
+            ],
+            signatures
+                .iter()
+                .map(FunctionSignature::to_string)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![("Pair".to_string(), "(u32, Either<u8, u16>)".to_string())],
+            type_alias_definitions(PROGRAM)
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn complete_words() {
+        let labels = |cursor: usize| {
+            completions(PROGRAM, cursor, &[])
+                .1
+                .into_iter()
+                .map(|completion| completion.label)
+                .collect::<Vec<_>>()
+        };
+        let witness_cursor = PROGRAM.rfind("witness::").unwrap() + "witness::".len();
+        assert_eq!(
+            vec!["witness::PAIR", "witness::SIG"],
+            labels(witness_cursor)
+        );
+
+        let first_cursor = PROGRAM.find("a\n}").unwrap();
+        let mut text = PROGRAM.to_string();
+        text.insert_str(first_cursor, "fi");
+        let (range, completions) = completions(&text, first_cursor + 2, &[]);
+        assert_eq!(first_cursor..first_cursor + 2, range);
+        let first = completions
+            .iter()
+            .find(|completion| completion.label == "first")
+            .unwrap();
+        assert_eq!("first(pair, b)", first.insertion);
+        assert_eq!("pair", &first.insertion[first.selection.clone()]);
+
+        let jets = completions_of("jet::add_3");
+        let add_32 = jets
+            .iter()
+            .find(|completion| completion.label == "jet::add_32")
+            .unwrap();
+        assert_eq!("jet::add_32(0, 0)", add_32.insertion);
+        assert_eq!("0", &add_32.insertion[add_32.selection.clone()]);
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn complete_declared_entries() {
+        let text = r#"mod witness {
+    const UNUSED: u16 = 1;
+}
+
+fn main() {
+    let a: u32 = witness::A;
+    let b: u8 = witness::B;
+}"#;
+        let cursor = text.find("witness::B").unwrap() + "witness::B".len();
+        let entries = entry_names(
+            text,
+            Module::Witness,
+            cursor - "witness::B".len()..cursor,
+            &[],
+        );
+        assert_eq!(
+            vec![
+                ("A".to_string(), "u32".to_string()),
+                ("UNUSED".to_string(), "u16".to_string()),
+            ],
+            entries
+        );
+        // Names are taken from the program text if the program cannot be parsed
+        let text = "fn main() {\n    let a: u32 = witness::A;\n    let b = witness::";
+        let entries = entry_names(text, Module::Witness, text.len() - 9..text.len(), &[]);
+        assert_eq!(vec![("A".to_string(), String::new())], entries);
+    }
+
+    fn completions_of(text: &str) -> Vec<Completion> {
+        completions(text, text.len(), &[]).1
+    }
+}
@@ -1,28 +1,36 @@
 use leptos::*;
 use std::str::FromStr;
-use std::sync::Arc;
 
+use crate::components::cost_breakdown::CostBreakdown;
+use crate::components::program_window::{Program, RunProgram, Runtime};
+use crate::components::run_window::TxEnv;
+use crate::dag::Dag;
 use crate::util;
 use crate::util::Expression;
 
 #[component]
-pub fn Analysis(
-    program: Signal<Option<Arc<Expression>>>,
-    run_result: ReadSignal<Option<Result<String, String>>>,
-) -> impl IntoView {
-    let maybe_input = move || match (program.get(), run_result.get()) {
-        (Some(program), Some(run_result)) => Some((program, run_result)),
-        _ => None,
-    };
+pub fn Analysis() -> impl IntoView {
+    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
+    let tx_env = use_context::<TxEnv>().expect("transaction environment should exist in context");
 
     view! {
         {
-            move || maybe_input().map(|(program, run_result)| view! {
-                <div>
-                    <AnalysisInner
-                        expression=program
-                        run_result=run_result/>
-                </div>
+            move || runtime.last_run.get().map(|run| {
+                let run_result = runtime.error_output.with_untracked(|error| match error.is_empty() {
+                    true => Ok(()),
+                    false => Err(error.clone()),
+                });
+                let witness_weight = run.pruned.as_ref().map(|pruned| {
+                    tx_env.params.with_untracked(|params| params.witness_weight(pruned))
+                });
+                view! {
+                    <div>
+                        <AnalysisInner
+                            run=run
+                            run_result=run_result
+                            witness_weight=witness_weight/>
+                    </div>
+                }
             })
         }
     }
@@ -30,17 +38,87 @@ pub fn Analysis(
 
 const MILLISECONDS_PER_WU: f64 = 0.5 / 1000.0;
 
+/// Encoded size and static bounds of a Simplicity program.
+struct ProgramStats {
+    /// Size of the encoded program and witness data in bytes.
+    size: usize,
+    /// Upper bound on the cost in weight units.
+    weight: u32,
+    /// Upper bound on the memory in bytes.
+    max_bytes: usize,
+}
+
+impl ProgramStats {
+    fn new(expression: &Expression) -> Self {
+        let (program_bytes, witness_bytes) = expression.encode_to_vec();
+        let bounds = expression.bounds();
+        // FIXME: Add conversion method to simplicity::Cost
+        let milli_weight = u32::from_str(&bounds.cost.to_string()).unwrap();
+        Self {
+            size: program_bytes.len() + witness_bytes.len(),
+            weight: milli_weight.saturating_add(999) / 1000,
+            max_bytes: bounds.extra_cells.div_ceil(8),
+        }
+    }
+}
+
+/// Display a value of the unpruned program next to the value of the pruned program.
+fn unpruned_and_pruned<T, F>(unpruned: &ProgramStats, pruned: Option<&ProgramStats>, f: F) -> String
+where
+    T: std::fmt::Display,
+    F: Fn(&ProgramStats) -> T,
+{
+    match pruned {
+        Some(pruned) => format!("{} ({} pruned)", f(unpruned), f(pruned)),
+        None => f(unpruned).to_string(),
+    }
+}
+
 #[component]
-fn AnalysisInner(expression: Arc<Expression>, run_result: Result<String, String>) -> impl IntoView {
-    let bounds = expression.bounds();
-    // FIXME: Add conversion method to simplicity::Cost
-    let milli_weight = u32::from_str(&bounds.cost.to_string()).unwrap();
-    let weight = milli_weight.saturating_add(999) / 1000;
-    let virtual_size = weight.div_ceil(4);
-    let size = weight; // Simplicity programs are Taproot witness data
-    let max_milliseconds = format!("{:.3}", f64::from(weight) * MILLISECONDS_PER_WU);
-    let max_bytes = bounds.extra_cells.div_ceil(8);
-    let compression = util::get_compression_factor(&expression);
+fn AnalysisInner(
+    run: RunProgram,
+    run_result: Result<(), String>,
+    witness_weight: Option<usize>,
+) -> impl IntoView {
+    let unpruned = ProgramStats::new(&run.program);
+    let pruned = run.pruned.as_deref().map(ProgramStats::new);
+    let pruned = pruned.as_ref();
+    // Simplicity programs are Taproot witness data
+    let size = unpruned_and_pruned(&unpruned, pruned, |stats| format!("{}B", stats.size));
+    let virtual_size = unpruned_and_pruned(&unpruned, pruned, |stats| {
+        format!("{}vB", stats.size.div_ceil(4))
+    });
+    let weight = unpruned_and_pruned(&unpruned, pruned, |stats| format!("{}WU", stats.weight));
+    let max_bytes = unpruned_and_pruned(&unpruned, pruned, |stats| format!("{}B", stats.max_bytes));
+    let max_milliseconds = unpruned_and_pruned(&unpruned, pruned, |stats| {
+        format!("{:.3}ms", f64::from(stats.weight) * MILLISECONDS_PER_WU)
+    });
+    let compression = util::get_compression_factor(&run.program);
+    let program = use_context::<Program>().expect("program should exist in context");
+    let breakdown_run = run.clone();
+    let node_statements = run.source_map.as_ref().map(|source_map| {
+        source_map.node_statements(&Dag::new(&run.program, run.pruned.as_deref()))
+    });
+    // Number of nodes that the code that is selected in the editor compiles to
+    let selected_code = move || {
+        let node_statements = match &node_statements {
+            Some(node_statements) => node_statements,
+            None => return "Source map not available".to_string(),
+        };
+        let statements = run.selected_statements(program);
+        if statements.is_empty() {
+            return "Select code in the editor".to_string();
+        }
+        let node_count = node_statements
+            .iter()
+            .filter(|statement| statement.is_some_and(|s| statements.contains(&s)))
+            .count();
+        format!("{node_count} nodes")
+    };
+    let witness_weight = match witness_weight {
+        Some(weight) => format!("{weight}WU"),
+        None => "Execution fails".to_string(),
+    };
 
     view! {
         <div class="analysis">
@@ -51,31 +129,40 @@ fn AnalysisInner(expression: Arc<Expression>, run_result: Result<String, String>
             <div class="analysis-body">
                 <div class="analysis-item">
                     <div class="analysis-item-label">Size:</div>
-                    <div class="analysis-item-data">{size}B</div>
+                    <div class="analysis-item-data">{size}</div>
                 </div>
                 <div class="analysis-item">
                     <div class="analysis-item-label">Virtual size:</div>
-                    <div class="analysis-item-data">{virtual_size}vB</div>
+                    <div class="analysis-item-data">{virtual_size}</div>
                 </div>
                 <div class="analysis-item">
                     <div class="analysis-item-label">Maximum memory:</div>
-                    <div class="analysis-item-data">{max_bytes}B</div>
+                    <div class="analysis-item-data">{max_bytes}</div>
                 </div>
                 <div class="analysis-item">
                     <div class="analysis-item-label">Weight:</div>
-                    <div class="analysis-item-data">{weight}WU</div>
+                    <div class="analysis-item-data">{weight}</div>
                 </div>
                 <div class="analysis-item">
                     <div class="analysis-item-label">Maximum runtime:</div>
-                    <div class="analysis-item-data">{max_milliseconds}ms</div>
+                    <div class="analysis-item-data">{max_milliseconds}</div>
                 </div>
                 <div class="analysis-item">
                     <div class="analysis-item-label">Program compression:</div>
                     <div class="analysis-item-data">{compression}x</div>
                 </div>
+                <div class="analysis-item">
+                    <div class="analysis-item-label">Witness stack weight:</div>
+                    <div class="analysis-item-data">{witness_weight}</div>
+                </div>
+                <div class="analysis-item">
+                    <div class="analysis-item-label">Selected code:</div>
+                    <div class="analysis-item-data">{selected_code}</div>
+                </div>
             </div>
+            <CostBreakdown run=breakdown_run/>
 
-            <RunResultMessage run_result=run_result.clone()/>
+            <RunResultMessage run_result=run_result/>
         </div>
     }
 }
@@ -99,7 +186,7 @@ fn RunSuccess(run_success: bool) -> impl IntoView {
 }
 
 #[component]
-fn RunResultMessage(run_result: Result<String, String>) -> impl IntoView {
+fn RunResultMessage(run_result: Result<(), String>) -> impl IntoView {
     match run_result {
         Ok(_) => view! {
             <div></div>
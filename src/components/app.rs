@@ -1,9 +1,19 @@
-use leptos::{component, provide_context, view, IntoView, RwSignal};
+use leptos::{
+    component, create_rw_signal, on_cleanup, provide_context, view, IntoView, RwSignal, Show,
+    SignalGet, SignalTrack,
+};
 
 use super::program_window::{select_example, Program, ProgramWindow, Runtime};
+use crate::components::analysis::Analysis;
 use crate::components::footer::Footer;
-use crate::components::run_window::{HashCount, KeyCount, RunWindow, SignedData, TxEnv};
-use crate::components::state::LocalStorage;
+use crate::components::merkle::MerkleExplorer;
+use crate::components::program_export::ProgramExport;
+use crate::components::run_window::{
+    CustomPreimages, HashCount, KeyCount, RunWindow, SignedData, SignedDataParams, TxEnv,
+};
+use crate::components::shared_state::SharedState;
+use crate::components::state::{self, IdeState, LocalStorage};
+use crate::components::storage_encryption::UnlockStorage;
 use crate::examples;
 use crate::transaction::TxParams;
 use crate::util::{HashedData, SigningKeys};
@@ -11,21 +21,70 @@ use crate::util::{HashedData, SigningKeys};
 #[derive(Copy, Clone, Debug, Default)]
 pub struct ActiveRunTab(pub RwSignal<&'static str>);
 
+/// Name of the open workspace.
+///
+/// The IDE is rebuilt from the local storage when another workspace is opened.
+#[derive(Copy, Clone, Debug)]
+pub struct ActiveWorkspace(pub RwSignal<String>);
+
 #[component]
 pub fn App() -> impl IntoView {
+    let unlocked = create_rw_signal(!state::is_locked());
+    let active_workspace =
+        ActiveWorkspace(create_rw_signal(state::workspaces().active().to_string()));
+    provide_context(active_workspace);
+
+    view! {
+        <Show
+            when=move || unlocked.get()
+            fallback=move || view! { <UnlockStorage unlocked=unlocked /> }
+        >
+            <SharedState />
+            {move || {
+                active_workspace.0.track();
+                view! { <Ide /> }
+            }}
+        </Show>
+    }
+}
+
+#[component]
+fn Ide() -> impl IntoView {
     let program = Program::load_from_storage().unwrap_or_default();
     provide_context(program);
     let tx_params = TxParams::load_from_storage().unwrap_or_default();
     let tx_env = TxEnv::new(program, tx_params);
     provide_context(tx_env);
-    provide_context(SigningKeys::load_from_storage().unwrap_or_default());
-    provide_context(SignedData::new(tx_env.lazy_env));
-    provide_context(HashedData::load_from_storage().unwrap_or_default());
+    let signing_keys = SigningKeys::load_from_storage().unwrap_or_default();
+    provide_context(signing_keys.clone());
+    let hashed_data = HashedData::load_from_storage().unwrap_or_default();
+    provide_context(hashed_data.clone());
+    let custom_preimages = CustomPreimages::load_from_storage().unwrap_or_default();
+    provide_context(custom_preimages);
+    let signed_data_params = SignedDataParams::load_from_storage().unwrap_or_default();
+    let signed_data = SignedData::new(
+        program,
+        tx_env.lazy_env,
+        custom_preimages,
+        signed_data_params,
+    );
+    provide_context(signed_data);
+    program.resolve_symbols_with(
+        signing_keys,
+        hashed_data,
+        custom_preimages,
+        signed_data.message,
+    );
     provide_context(KeyCount::load_from_storage().unwrap_or_default());
     provide_context(HashCount::load_from_storage().unwrap_or_default());
     provide_context(Runtime::new(program, tx_env.lazy_env));
     provide_context(ActiveRunTab::default());
 
+    // Store the state of this workspace when the IDE is rebuilt for another workspace
+    let workspace = state::workspaces().active().to_string();
+    let ide_state = IdeState::from_context();
+    on_cleanup(move || ide_state.store_in_workspace(&workspace));
+
     if program.is_empty() {
         select_example(examples::get("✍️️ P2PK").expect("P2PK example should exist"))
     }
@@ -33,6 +92,9 @@ pub fn App() -> impl IntoView {
     view! {
         <ProgramWindow />
         <RunWindow />
+        <Analysis />
+        <MerkleExplorer />
+        <ProgramExport />
         <Footer />
     }
 }
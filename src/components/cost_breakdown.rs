@@ -0,0 +1,260 @@
+use leptos::*;
+
+use crate::breakdown::{Breakdown, Usage};
+use crate::components::program_window::{Program, RunProgram};
+use crate::dag::Dag;
+use crate::problem::Span;
+
+/// Maximum number of characters of a statement that are shown in its row.
+const MAX_LABEL_LEN: usize = 48;
+
+/// Column by which the breakdown is sorted.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Column {
+    Name,
+    Size,
+    Weight,
+}
+
+/// Row of the breakdown, which is a function or a statement.
+#[derive(Clone, Debug)]
+struct Row {
+    name: String,
+    /// Function of the row, which is used to highlight the selected code.
+    function: Option<String>,
+    /// Statement of the row, if the row is a statement.
+    statement: Option<usize>,
+    /// Index of the file and span of the code of the row.
+    location: Option<(usize, Span)>,
+    usage: Usage,
+}
+
+/// Return the first line of the statement, shortened to fit into a row.
+fn statement_label(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().trim();
+    match line.chars().count() <= MAX_LABEL_LEN && !text.contains('\n') {
+        true => line.to_string(),
+        false => format!("{}…", line.chars().take(MAX_LABEL_LEN).collect::<String>()),
+    }
+}
+
+fn rows(run: &RunProgram, breakdown: &Breakdown) -> (Vec<Row>, Vec<Row>) {
+    let statements = run
+        .source_map
+        .as_ref()
+        .map(|source_map| source_map.statements())
+        .unwrap_or_default();
+    let other = (breakdown.unmapped != Usage::default()).then(|| Row {
+        name: "(other)".to_string(),
+        function: None,
+        statement: None,
+        location: None,
+        usage: breakdown.unmapped,
+    });
+    // Nodes that several statements use are encoded once and have no weight of their own
+    let shared = (breakdown.shared_bits != 0).then(|| Row {
+        name: "(shared)".to_string(),
+        function: None,
+        statement: None,
+        location: None,
+        usage: Usage {
+            bits: breakdown.shared_bits,
+            ..Usage::default()
+        },
+    });
+    let other: Vec<Row> = other.into_iter().chain(shared).collect();
+
+    let function_rows = breakdown
+        .functions
+        .iter()
+        .map(|(function, usage)| Row {
+            name: format!("fn {function}"),
+            function: Some(function.clone()),
+            statement: None,
+            location: statements
+                .iter()
+                .position(|statement| &statement.function == function)
+                .and_then(|index| run.statement_location(index)),
+            usage: *usage,
+        })
+        .chain(other.clone())
+        .collect();
+    let statement_rows = statements
+        .iter()
+        .zip(&breakdown.statements)
+        .enumerate()
+        .map(|(index, (statement, usage))| Row {
+            name: format!(
+                "fn {}: {}",
+                statement.function,
+                statement_label(&run.combined.text[statement.span.clone()])
+            ),
+            function: Some(statement.function.clone()),
+            statement: Some(index),
+            location: run.statement_location(index),
+            usage: *usage,
+        })
+        .chain(other)
+        .collect();
+
+    (function_rows, statement_rows)
+}
+
+/// Return the share of `part` in `total` as a percentage.
+fn share(part: u64, total: u64) -> f64 {
+    match total {
+        0 => 0.0,
+        _ => part as f64 * 100.0 / total as f64,
+    }
+}
+
+/// Table of the encoded size and the cost bound of each function or statement of a run,
+/// with their shares of the total.
+///
+/// Nodes that several statements use count towards a separate row of shared bits.
+///
+/// Clicking a row selects its code in the editor.
+/// The rows of the code that is selected in the editor are highlighted.
+#[component]
+pub fn CostBreakdown(run: RunProgram) -> impl IntoView {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let breakdown = run.source_map.as_ref().map(|source_map| {
+        let dag = Dag::new(&run.program, None);
+        let (program_bytes, witness_bytes) = run.program.encode_to_vec();
+        let encoded_bits = 8 * (program_bytes.len() + witness_bytes.len()) as u64;
+        Breakdown::new(&dag, encoded_bits, source_map)
+    });
+    let breakdown = match breakdown {
+        Some(breakdown) => breakdown,
+        None => {
+            let hint = view! {
+                <p class="breakdown-hint">The breakdown needs the Simfony source code of the program.</p>
+            };
+            return hint.into_view();
+        }
+    };
+    let total = breakdown.total;
+    let (function_rows, statement_rows) = rows(&run, &breakdown);
+    let by_function = create_rw_signal(true);
+    // Column and whether the order is descending
+    let sort = create_rw_signal((Column::Weight, true));
+    let selected = create_memo(move |_| {
+        let statements = run.selected_statements(program);
+        let functions: Vec<String> = run
+            .source_map
+            .as_ref()
+            .map(|source_map| {
+                statements
+                    .iter()
+                    .map(|&index| source_map.statements()[index].function.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        (statements, functions)
+    });
+
+    let sort_by = move |column: Column| {
+        sort.update(|(sorted_column, descending)| {
+            *descending = match *sorted_column == column {
+                true => !*descending,
+                false => column != Column::Name,
+            };
+            *sorted_column = column;
+        })
+    };
+    let header = move |column: Column, label: &'static str| {
+        let arrow = move || match sort.get() {
+            (sorted_column, true) if sorted_column == column => " ▼",
+            (sorted_column, false) if sorted_column == column => " ▲",
+            _ => "",
+        };
+        view! {
+            <th class="breakdown-sortable" on:click=move |_| sort_by(column)>
+                {label}{arrow}
+            </th>
+        }
+    };
+    let table_rows = move || {
+        let mut rows = match by_function.get() {
+            true => function_rows.clone(),
+            false => statement_rows.clone(),
+        };
+        let (column, descending) = sort.get();
+        rows.sort_by(|a, b| {
+            let ordering = match column {
+                Column::Name => a.name.cmp(&b.name),
+                Column::Size => a.usage.bits.cmp(&b.usage.bits),
+                Column::Weight => a.usage.cost.cmp(&b.usage.cost),
+            };
+            match descending {
+                true => ordering.reverse(),
+                false => ordering,
+            }
+        });
+        let is_function = by_function.get();
+        rows.into_iter()
+            .map(|row| {
+                let (function, statement) = (row.function, row.statement);
+                let is_selected = move || {
+                    selected.with(|(statements, functions)| match is_function {
+                        true => function.as_ref().is_some_and(|f| functions.contains(f)),
+                        false => statement.is_some_and(|s| statements.contains(&s)),
+                    })
+                };
+                let location = row.location;
+                let weight_share = share(row.usage.cost.0, total.cost.0);
+                view! {
+                    <tr
+                        class:breakdown-selected=is_selected
+                        class:breakdown-link=location.is_some()
+                        on:click=move |_| {
+                            if let Some((file, span)) = location {
+                                program.reveal(file, span);
+                            }
+                        }
+                    >
+                        <td class="breakdown-name">{row.name}</td>
+                        <td>{format!("{:.1}B", row.usage.bits as f64 / 8.0)}</td>
+                        <td>{format!("{:.1}%", share(row.usage.bits, total.bits))}</td>
+                        <td>{format!("{:.3}WU", row.usage.cost.0 as f64 / 1000.0)}</td>
+                        <td class="breakdown-share">
+                            <div class="breakdown-bar" style=format!("width: {weight_share:.1}%")></div>
+                            <span>{format!("{weight_share:.1}%")}</span>
+                        </td>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="breakdown">
+            <div class="breakdown-header">
+                <h3>Cost breakdown</h3>
+                <div class="breakdown-toggle">
+                    <button class:active=move || by_function.get() on:click=move |_| by_function.set(true)>
+                        By function
+                    </button>
+                    <button class:active=move || !by_function.get() on:click=move |_| by_function.set(false)>
+                        By statement
+                    </button>
+                </div>
+            </div>
+            <table class="breakdown-table">
+                <thead>
+                    <tr>
+                        {header(Column::Name, "Code")}
+                        {header(Column::Size, "Size")}
+                        <th>Size share</th>
+                        {header(Column::Weight, "Weight")}
+                        <th>Weight share</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {table_rows}
+                </tbody>
+            </table>
+        </div>
+    }
+    .into_view()
+}
@@ -1,94 +1,345 @@
+use std::rc::Rc;
 use std::sync::Arc;
 
 use js_sys::{Array, Object};
 use leptos::*;
 use simfony::simplicity;
-use simplicity::dag::DagLike;
-use simplicity::dag::NoSharing;
-use simplicity::node;
 use wasm_bindgen::prelude::*;
 
-use crate::util::{DisplayInner, Expression};
+use crate::components::program_window::{Program, RunProgram, Runtime};
+use crate::dag::{Dag, DagNode, Pruning};
+use crate::function::Trace;
+
+/// Program of the last run, with the nodes that the run executed.
+#[derive(Clone)]
+struct ExploredRun {
+    dag: Rc<Dag>,
+    trace: Arc<Trace>,
+    /// Index of the source statement of each node, if the source map is known.
+    statements: Rc<Vec<Option<usize>>>,
+}
+
+impl ExploredRun {
+    fn new(run: &RunProgram) -> Self {
+        let dag = Dag::new(&run.program, run.pruned.as_deref());
+        let statements = match &run.source_map {
+            Some(source_map) => source_map.node_statements(&dag),
+            None => vec![None; dag.nodes().len()],
+        };
+        Self {
+            dag: Rc::new(dag),
+            trace: Arc::clone(&run.trace),
+            statements: Rc::new(statements),
+        }
+    }
+}
 
 #[component]
-pub fn MerkleExplorer(
-    run_result: ReadSignal<Option<Result<String, String>>>,
-    graph_toggle: ReadSignal<bool>,
-    set_graph_toggle: WriteSignal<bool>,
-) -> impl IntoView {
-    move || match run_result.get() {
-        Some(Ok(_)) => view! {
-            <div id="merkle-container" class="analysis">
-                <div class="flex analysis-header">
-                    <div
-                        on:click=move |_| set_graph_toggle.set(!graph_toggle.get())
-                        class="graph-toggle-holder"
+pub fn MerkleExplorer() -> impl IntoView {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
+    let (graph_toggle, set_graph_toggle) = create_signal(true);
+    let explored = create_rw_signal(None::<ExploredRun>);
+    let selected = create_rw_signal(None::<usize>);
+    // Select the source code of the node in the editor
+    let reveal_source = move |id: usize| {
+        let statement = explored.with_untracked(|run| run.as_ref()?.statements.get(id).copied().flatten());
+        let location = statement.and_then(|statement| {
+            runtime
+                .last_run
+                .with_untracked(|run| run.as_ref()?.statement_location(statement))
+        });
+        if let Some((file, span)) = location {
+            program.reveal(file, span);
+        }
+    };
+    let on_select = Closure::<dyn Fn(usize)>::new(move |id| {
+        selected.set(Some(id));
+        reveal_source(id);
+    })
+    .into_js_value()
+    .unchecked_into::<js_sys::Function>();
+    // Nodes of the code that is selected in the editor
+    let highlighted = create_memo(move |_| {
+        let statements = runtime.last_run.with(|run| {
+            run.as_ref()
+                .map(|run| run.selected_statements(program))
+                .unwrap_or_default()
+        });
+        explored.with(|run| {
+            run.as_ref().map_or(vec![], |run| {
+                (0..run.statements.len())
+                    .filter(|&id| run.statements[id].is_some_and(|s| statements.contains(&s)))
+                    .collect::<Vec<usize>>()
+            })
+        })
+    });
+
+    create_effect(move |_| {
+        let new_run = runtime
+            .last_run
+            .with(|run| run.as_ref().map(ExploredRun::new));
+        selected.set(None);
+        explored.set(new_run);
+    });
+    create_effect(move |_| {
+        if !graph_toggle.get() {
+            return;
+        }
+        if let Some(run) = explored.get() {
+            let on_select = on_select.clone();
+            // Wait until the graph holder is visible, so the graph gets its full width
+            request_animation_frame(move || {
+                reload_graph(&run, selected.get_untracked(), &on_select);
+                highlight_nodes(&highlighted.get_untracked());
+            });
+        }
+    });
+    create_effect(move |_| highlighted.with(|ids| highlight_nodes(ids)));
+
+    view! {
+        <div id="merkle-container" class="analysis" class:hidden=move || explored.with(Option::is_none)>
+            <div class="flex analysis-header">
+                <div
+                    on:click=move |_| set_graph_toggle.set(!graph_toggle.get())
+                    class="graph-toggle-holder"
+                >
+                    <h2 class="analysis-title">Merkle Explorer</h2>
+
+                    <svg width="46" height="24" viewBox="0 0 46 24" fill="none" xmlns="http://www.w3.org/2000/svg"
+                        id="graph-toggle-icon"
+                        class:toggle-on=move || graph_toggle.get()
                     >
-                        <h2 class="analysis-title">Merkle Explorer</h2>
-
-                        <svg width="46" height="24" viewBox="0 0 46 24" fill="none" xmlns="http://www.w3.org/2000/svg"
-                            id="graph-toggle-icon"
-                            class:toggle-on=move || graph_toggle.get()
-                        >
-                            <rect x="0.5" y="0.5" width="45" height="22.7931" rx="11.3966" />
-                            <circle cx="11.897" cy="11.8965" r="8.72414" />
-                        </svg>
-                    </div>
+                        <rect x="0.5" y="0.5" width="45" height="22.7931" rx="11.3966" />
+                        <circle cx="11.897" cy="11.8965" r="8.72414" />
+                    </svg>
                 </div>
-                <div class="merkle_graph" class:hidden=move || !graph_toggle.get() >
-                    <div id="merkle_graph_holder"></div>
-
-                    <div class="graph-button-holder">
-                        <svg viewBox="0 0 100 100" xmlns="http://www.w3.org/2000/svg" class="graph-button" on:click=move |_| manualZoom("zoom_in")>
-                            <line x1="5" y1="50" x2="95" y2="50" />
-                            <line x1="50" y1="5" x2="50" y2="95" />
-                        </svg>
-
-                        <svg viewBox="0 0 100 100" xmlns="http://www.w3.org/2000/svg" class="graph-button" on:click=move |_| manualZoom("zoom_out")>
-                            <line x1="5" y1="50" x2="95" y2="50" />
-                        </svg>
-
-                        <svg viewBox="0 0 512 512" xmlns="http://www.w3.org/2000/svg" class="graph-button" on:click=move |_| manualZoom("zoom_reset")>
-                            <path d="M64,256H34A222,222,0,0,1,430,118.15V85h30V190H355V160h67.27A192.21,192.21,0,0,0,256,64C150.13,64,64,150.13,64,256Zm384,0c0,105.87-86.13,192-192,192A192.21,192.21,0,0,1,89.73,352H157V322H52V427H82V393.85A222,222,0,0,0,478,256Z"/>
-                        </svg>
-                    </div>
+            </div>
+            <div class="merkle_graph" class:hidden=move || !graph_toggle.get() >
+                <div id="merkle_graph_holder"></div>
+
+                <div class="graph-button-holder">
+                    <svg viewBox="0 0 100 100" xmlns="http://www.w3.org/2000/svg" class="graph-button" on:click=move |_| manualZoom("zoom_in")>
+                        <line x1="5" y1="50" x2="95" y2="50" />
+                        <line x1="50" y1="5" x2="50" y2="95" />
+                    </svg>
+
+                    <svg viewBox="0 0 100 100" xmlns="http://www.w3.org/2000/svg" class="graph-button" on:click=move |_| manualZoom("zoom_out")>
+                        <line x1="5" y1="50" x2="95" y2="50" />
+                    </svg>
+
+                    <svg viewBox="0 0 512 512" xmlns="http://www.w3.org/2000/svg" class="graph-button" on:click=move |_| manualZoom("zoom_reset")>
+                        <path d="M64,256H34A222,222,0,0,1,430,118.15V85h30V190H355V160h67.27A192.21,192.21,0,0,0,256,64C150.13,64,64,150.13,64,256Zm384,0c0,105.87-86.13,192-192,192A192.21,192.21,0,0,1,89.73,352H157V322H52V427H82V393.85A222,222,0,0,0,478,256Z"/>
+                    </svg>
                 </div>
+
+                <NodeDetails explored=explored selected=selected />
             </div>
-        },
-        _ => view! {
-            <div></div>
-        },
+        </div>
+    }
+}
+
+#[component]
+fn NodeDetails(
+    explored: RwSignal<Option<ExploredRun>>,
+    selected: RwSignal<Option<usize>>,
+) -> impl IntoView {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
+    let node = move || {
+        let id = selected.get()?;
+        explored.with(|run| {
+            let run = run.as_ref()?;
+            let node = run.dag.get(id)?.clone();
+            let execution = match run.trace.get(&node.imr) {
+                Some(trace) => {
+                    let value = |value: &Option<simplicity::Value>| {
+                        value
+                            .as_ref()
+                            .map_or("none".to_string(), ToString::to_string)
+                    };
+                    let failed = run.trace.failed() == Some(node.imr);
+                    view! {
+                        <div class="merkle-detail">
+                            <span class="merkle-detail-label">Executed</span>
+                            <span>{trace.count}" times"{failed.then_some(", failed here")}</span>
+                        </div>
+                        <div class="merkle-detail">
+                            <span class="merkle-detail-label">Input</span>
+                            <code>{value(&trace.input)}</code>
+                        </div>
+                        <div class="merkle-detail">
+                            <span class="merkle-detail-label">Output</span>
+                            <code>{value(&trace.output)}</code>
+                        </div>
+                    }
+                    .into_view()
+                }
+                None => view! {
+                    <div class="merkle-detail">
+                        <span class="merkle-detail-label">Executed</span>
+                        <span>never</span>
+                    </div>
+                }
+                .into_view(),
+            };
+            let statement = run.statements.get(id).copied().flatten();
+            Some((id, node, execution, statement))
+        })
+    };
+
+    move || match node() {
+        Some((id, node, execution, statement)) => {
+            let location = statement.and_then(|statement| {
+                runtime.last_run.with_untracked(|run| {
+                    let run = run.as_ref()?;
+                    let function = run.source_map.as_ref()?.statements()[statement]
+                        .function
+                        .clone();
+                    let (file, span) = run.statement_location(statement)?;
+                    Some((function, file, span))
+                })
+            });
+            let source = match location {
+                Some((function, file, span)) => {
+                    let name = program
+                        .files
+                        .with_untracked(|files| files.get(file).map(|file| file.name.clone()))
+                        .unwrap_or_default();
+                    view! {
+                        <button class="merkle-node-link" on:click=move |_| program.reveal(file, span)>
+                            {name}":"{span.start.to_string()}" in fn "{function}
+                        </button>
+                    }
+                    .into_view()
+                }
+                None => "unknown".into_view(),
+            };
+            let children = node
+                .children
+                .iter()
+                .map(|child| {
+                    let child = *child;
+                    view! {
+                        <button class="merkle-node-link" on:click=move |_| selected.set(Some(child))>
+                            "#"{child}
+                        </button>
+                    }
+                })
+                .collect_view();
+            view! {
+                <div class="merkle-details">
+                    <h3>"#"{id}" "{node.name}</h3>
+                    <div class="merkle-detail">
+                        <span class="merkle-detail-label">CMR</span>
+                        <code>{node.cmr.to_string()}</code>
+                    </div>
+                    <div class="merkle-detail">
+                        <span class="merkle-detail-label">IMR</span>
+                        <code>{node.imr.to_string()}</code>
+                    </div>
+                    <div class="merkle-detail">
+                        <span class="merkle-detail-label">AMR</span>
+                        <code>{node.amr.to_string()}</code>
+                    </div>
+                    <div class="merkle-detail">
+                        <span class="merkle-detail-label">Type</span>
+                        <code>{node.source_type}" → "{node.target_type}</code>
+                    </div>
+                    <div class="merkle-detail">
+                        <span class="merkle-detail-label">Pruning</span>
+                        <span>{node.pruning.to_string()}</span>
+                    </div>
+                    <div class="merkle-detail">
+                        <span class="merkle-detail-label">Source</span>
+                        <span>{source}</span>
+                    </div>
+                    <div class="merkle-detail">
+                        <span class="merkle-detail-label">Children</span>
+                        <span>{children}</span>
+                    </div>
+                    {execution}
+                </div>
+            }
+            .into_view()
+        }
+        None => view! {
+            <p class="merkle-hint">Click a node to see its details.</p>
+        }
+        .into_view(),
     }
 }
 
 #[wasm_bindgen(module = "/src/assets/js/merkle_graph_d3.js")]
 extern "C" {
-    fn load_merkle_graph_js(dat: JsValue);
+    fn load_merkle_graph_js(
+        nodes: JsValue,
+        root: usize,
+        selected: Option<usize>,
+        on_select: &js_sys::Function,
+    );
     fn manualZoom(mode: &str);
+    fn highlightMerkleNodes(ids: JsValue);
 }
 
-fn marshal_merkle_data<M: node::Marker>(expression: &node::Node<M>) -> JsValue {
-    let mut output = vec![];
-    for data in expression.post_order_iter::<NoSharing>() {
-        let text = JsValue::from(DisplayInner::from(data.node).to_string());
-        let children = Array::new();
-        if data.left_index.is_some() {
-            children.push(&output.pop().unwrap());
-        }
-        if data.right_index.is_some() {
-            children.push(&output.pop().unwrap());
-        }
+fn node_classes(node: &DagNode, trace: &Trace) -> String {
+    let pruning = match node.pruning {
+        Pruning::Removed => "node-pruned",
+        Pruning::KeptLeft | Pruning::KeptRight => "node-asserted",
+        Pruning::Unknown | Pruning::Kept => "",
+    };
+    let execution = match trace.get(&node.imr) {
+        _ if trace.failed() == Some(node.imr) => "node-failed",
+        Some(..) => "node-executed",
+        None => "",
+    };
+    format!("{pruning} {execution}")
+}
+
+fn marshal_merkle_data(run: &ExploredRun) -> JsValue {
+    let nodes = Array::new();
+    for node in run.dag.nodes() {
+        let children = node
+            .children
+            .iter()
+            .map(|&child| JsValue::from(child))
+            .collect::<Array>();
+        // An edge is taken if the node executed the child, such as the branch of a case
+        let taken = run
+            .trace
+            .get(&node.imr)
+            .map(|trace| trace.children)
+            .unwrap_or_default();
+        let taken = taken[..node.children.len()]
+            .iter()
+            .map(|&taken| JsValue::from_bool(taken))
+            .collect::<Array>();
         let node_obj = Object::new();
-        js_sys::Reflect::set(&node_obj, &JsValue::from_str("text"), &text).unwrap();
+        js_sys::Reflect::set(
+            &node_obj,
+            &JsValue::from_str("text"),
+            &JsValue::from_str(&node.name),
+        )
+        .unwrap();
         js_sys::Reflect::set(&node_obj, &JsValue::from_str("children"), &children).unwrap();
-
-        output.push(JsValue::from(node_obj))
+        js_sys::Reflect::set(
+            &node_obj,
+            &JsValue::from_str("classes"),
+            &JsValue::from_str(&node_classes(node, &run.trace)),
+        )
+        .unwrap();
+        js_sys::Reflect::set(&node_obj, &JsValue::from_str("taken"), &taken).unwrap();
+        nodes.push(&node_obj);
     }
-    debug_assert!(output.len() == 1);
-    output.pop().unwrap()
+    JsValue::from(nodes)
+}
+
+fn highlight_nodes(ids: &[usize]) {
+    let ids = ids.iter().map(|&id| JsValue::from(id)).collect::<Array>();
+    highlightMerkleNodes(JsValue::from(ids));
 }
 
-pub fn reload_graph(expression: Arc<Expression>) {
-    let data = marshal_merkle_data(&expression);
-    load_merkle_graph_js(data);
+fn reload_graph(run: &ExploredRun, selected: Option<usize>, on_select: &js_sys::Function) {
+    let data = marshal_merkle_data(run);
+    load_merkle_graph_js(data, run.dag.root(), selected, on_select);
 }
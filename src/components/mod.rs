@@ -1,12 +1,18 @@
 mod analysis;
 mod app;
 mod copy_to_clipboard;
+mod cost_breakdown;
+mod download;
 mod dropdown;
 mod footer;
+mod merkle;
 mod navbar;
+mod program_export;
 mod program_window;
 mod run_window;
+mod shared_state;
 mod state;
+mod storage_encryption;
 mod string_box;
 mod toolbar;
 
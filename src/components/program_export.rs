@@ -0,0 +1,155 @@
+use hex_conservative::DisplayHex;
+use leptos::*;
+
+use crate::components::copy_to_clipboard::CopyToClipboard;
+use crate::components::download::download_file;
+use crate::components::program_window::Runtime;
+use crate::dag::Dag;
+use crate::export;
+use crate::util::Expression;
+
+/// Exported forms of a Simplicity program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Exported {
+    listing: String,
+    dot: String,
+    program_base64: String,
+    program_hex: String,
+    witness_base64: String,
+    witness_hex: String,
+}
+
+impl Exported {
+    fn new(program: &Expression) -> Self {
+        let dag = Dag::new(program, None);
+        let (program_bytes, witness_bytes) = program.encode_to_vec();
+        Self {
+            listing: export::listing(&dag),
+            dot: export::dot(&dag),
+            program_base64: export::base64_encode(&program_bytes),
+            program_hex: program_bytes.to_lower_hex_string(),
+            witness_base64: export::base64_encode(&witness_bytes),
+            witness_hex: witness_bytes.to_lower_hex_string(),
+        }
+    }
+}
+
+/// Let the browser download a text file, and show the error to the user if there is one.
+fn download(name: &str, content: &str) {
+    if let (Err(error), Some(window)) = (
+        download_file(name, "text/plain", content),
+        web_sys::window(),
+    ) {
+        let _result = window.alert_with_message(&error);
+    }
+}
+
+/// Export of the Simplicity program of the last run, as it is compiled or as it is pruned,
+/// for audits and for other Simplicity tools.
+#[component]
+pub fn ProgramExport() -> impl IntoView {
+    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
+    let show_pruned = create_rw_signal(false);
+    let has_pruned = move || {
+        runtime
+            .last_run
+            .with(|run| run.as_ref().is_some_and(|run| run.pruned.is_some()))
+    };
+    let exported = create_memo(move |_| {
+        runtime.last_run.with(|run| {
+            let run = run.as_ref()?;
+            let program = match (show_pruned.get(), &run.pruned) {
+                (true, Some(pruned)) => pruned,
+                _ => &run.program,
+            };
+            Some(Exported::new(program))
+        })
+    });
+    let field = move |f: fn(&Exported) -> String| {
+        Signal::derive(move || {
+            exported.with(|exported| exported.as_ref().map(f).unwrap_or_default())
+        })
+    };
+    let listing = field(|exported| exported.listing.clone());
+    let dot = field(|exported| exported.dot.clone());
+    let suffix = move || match show_pruned.get() && has_pruned() {
+        true => "_pruned",
+        false => "",
+    };
+    let byte_fields = [
+        (
+            "Program (base64)",
+            field(|exported| exported.program_base64.clone()),
+        ),
+        (
+            "Program (hex)",
+            field(|exported| exported.program_hex.clone()),
+        ),
+        (
+            "Witness (base64)",
+            field(|exported| exported.witness_base64.clone()),
+        ),
+        (
+            "Witness (hex)",
+            field(|exported| exported.witness_hex.clone()),
+        ),
+    ]
+    .into_iter()
+    .map(|(label, content)| {
+        view! {
+            <div class="export-bytes">
+                <span class="export-label">{label}</span>
+                <code>{content}</code>
+                <CopyToClipboard content=content class="copy-button">
+                    <i class="far fa-copy"></i>
+                </CopyToClipboard>
+            </div>
+        }
+    })
+    .collect_view();
+
+    view! {
+        <div class="analysis program-export" class:hidden=move || exported.with(Option::is_none)>
+            <div class="flex analysis-header">
+                <h2 class="analysis-title">Export</h2>
+                <div class="breakdown-toggle">
+                    <button class:active=move || !show_pruned.get() on:click=move |_| show_pruned.set(false)>
+                        Compiled
+                    </button>
+                    <button
+                        class:active=move || show_pruned.get()
+                        disabled=move || !has_pruned()
+                        on:click=move |_| show_pruned.set(true)
+                    >
+                        Pruned
+                    </button>
+                </div>
+            </div>
+            {byte_fields}
+            <div class="export-text">
+                <div class="export-text-header">
+                    <span class="export-label">Simplicity listing</span>
+                    <CopyToClipboard content=listing class="copy-button">
+                        <i class="far fa-copy"></i>
+                    </CopyToClipboard>
+                    <button class="copy-button" on:click=move |_| listing.with(|listing| download(&format!("program{}.simpl", suffix()), listing))>
+                        <i class="fas fa-download"></i>
+                    </button>
+                </div>
+                <pre>{listing}</pre>
+            </div>
+            <div class="export-text">
+                <div class="export-text-header">
+                    <span class="export-label">Graphviz DOT</span>
+                    <CopyToClipboard content=dot class="copy-button">
+                        <i class="far fa-copy"></i>
+                    </CopyToClipboard>
+                    <button class="copy-button" on:click=move |_| dot.with(|dot| download(&format!("program{}.dot", suffix()), dot))>
+                        <i class="fas fa-download"></i>
+                    </button>
+                </div>
+                <pre>{dot}</pre>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,84 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{MessageEvent, Worker};
+
+use crate::check::{Request, Response};
+
+/// Script that loads the worker, which trunk builds from `src/bin/check_worker.rs`.
+///
+/// The loader instantiates the WebAssembly module of the worker and runs its `main` function.
+/// The script `./check_worker.js` without the loader contains only the bindings.
+const WORKER_SCRIPT: &str = "./check_worker_loader.js";
+
+/// Web worker that compiles and satisfies programs off the main thread.
+#[derive(Clone, Debug)]
+pub struct CheckWorker {
+    worker: Worker,
+    generation: Rc<Cell<u64>>,
+    /// Number of the latest request that the worker answered.
+    answered: Rc<Cell<u64>>,
+}
+
+impl CheckWorker {
+    /// Spawn a worker that passes the response to the latest request to `on_response`.
+    ///
+    /// Responses to outdated requests are dropped.
+    pub fn spawn<F>(on_response: F) -> Result<Self, String>
+    where
+        F: Fn(Response) + 'static,
+    {
+        let worker = Worker::new(WORKER_SCRIPT).map_err(|error| format!("{error:?}"))?;
+        let generation = Rc::new(Cell::new(0));
+        let latest = Rc::clone(&generation);
+        let answered = Rc::new(Cell::new(0));
+        let latest_answered = Rc::clone(&answered);
+        let on_message = Closure::<dyn Fn(MessageEvent)>::new(move |event: MessageEvent| {
+            let response = event
+                .data()
+                .as_string()
+                .and_then(|data| serde_json::from_str::<Response>(&data).ok());
+            if let Some(response) = response {
+                latest_answered.set(response.generation);
+                if response.generation == latest.get() {
+                    on_response(response);
+                }
+            }
+        })
+        .into_js_value();
+        worker.set_onmessage(Some(on_message.unchecked_ref()));
+        Ok(Self {
+            worker,
+            generation,
+            answered,
+        })
+    }
+
+    /// Check the program `text`, which must not contain symbols.
+    ///
+    /// The `variants` of the text are compared with the program to build its source map.
+    /// Return the number of the request.
+    pub fn check(&self, text: String, variants: Vec<String>) -> u64 {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        let request = Request {
+            generation,
+            text,
+            variants,
+        };
+        let request = serde_json::to_string(&request).expect("request should serialize");
+        let _result = self.worker.post_message(&JsValue::from_str(&request));
+        generation
+    }
+
+    /// Check if the worker answered the request with the given number.
+    pub fn has_answered(&self, generation: u64) -> bool {
+        generation <= self.answered.get()
+    }
+
+    pub fn terminate(&self) {
+        self.worker.terminate();
+    }
+}
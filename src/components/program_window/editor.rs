@@ -0,0 +1,492 @@
+use std::ops::Range;
+
+use leptos::{
+    component, create_memo, create_node_ref, create_rw_signal, ev, event_target_value, html,
+    use_context, view, CollectView, IntoView, NodeRef, Signal, SignalGet, SignalGetUntracked,
+    SignalSet, SignalUpdate, SignalWith, SignalWithUntracked,
+};
+
+use crate::completion::{self, Completion};
+use crate::components::program_window::{Program, Runtime};
+use crate::components::run_window::CustomPreimages;
+use crate::lookup;
+use crate::problem::{self, Position, Problem, Severity};
+use crate::syntax::{self, TokenKind};
+
+const TAB_KEY: u32 = 9;
+const ENTER_KEY: u32 = 13;
+const ESCAPE_KEY: u32 = 27;
+const SPACE_KEY: u32 = 32;
+const ARROW_LEFT_KEY: u32 = 37;
+const ARROW_UP_KEY: u32 = 38;
+const ARROW_RIGHT_KEY: u32 = 39;
+const ARROW_DOWN_KEY: u32 = 40;
+/// Height of a line of the editor in pixels. Must match the stylesheet.
+const LINE_HEIGHT_PX: i32 = 18;
+/// Width of a character of the editor in pixels. Must match the stylesheet.
+const CHAR_WIDTH_PX: f64 = 7.2;
+/// Padding of the editor in pixels. Must match the stylesheet.
+const TEXT_PADDING_PX: i32 = 12;
+/// Distance between the outer edge of the editor and its text in pixels, including the border.
+const TEXT_OFFSET_PX: i32 = TEXT_PADDING_PX + 1;
+/// Maximum number of completions that are shown at once.
+const MAX_COMPLETIONS: usize = 50;
+
+/// Return the number of UTF-16 code units before the given byte `offset` of the `text`.
+///
+/// Selection offsets of text areas count UTF-16 code units.
+pub fn utf16_offset(text: &str, offset: usize) -> u32 {
+    text[..offset.min(text.len())].encode_utf16().count() as u32
+}
+
+/// Return the byte offset of the `text` after the given number of UTF-16 code units.
+pub fn byte_offset(text: &str, utf16_offset: u32) -> usize {
+    let mut units = 0;
+    for (index, c) in text.char_indices() {
+        if utf16_offset as usize <= units {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// Return the CSS style that places a popup below the character at byte offset `offset` of the `text`,
+/// given the scroll offsets of the editor.
+fn popup_style(text: &str, offset: usize, (scroll_top, scroll_left): (i32, i32)) -> String {
+    let position = Position::from_offset(text, offset);
+    format!(
+        "top: {}px; left: {:.1}px;",
+        (position.line as i32 + 1) * LINE_HEIGHT_PX + TEXT_OFFSET_PX - scroll_top,
+        position.col as f64 * CHAR_WIDTH_PX + f64::from(TEXT_OFFSET_PX - scroll_left),
+    )
+}
+
+/// Completions of the word at the cursor.
+#[derive(Clone, Debug)]
+struct CompletionMenu {
+    /// Byte range of the word that is completed.
+    range: Range<usize>,
+    items: Vec<Completion>,
+    selected: usize,
+}
+
+/// Editor of Simfony programs with syntax highlighting, line numbers,
+/// bracket matching, auto-indentation and autocompletion.
+///
+/// Hovering a name shows its type. Ctrl+click jumps to the definition of a name.
+///
+/// The text is edited in a transparent `<textarea>`.
+/// Highlighted tokens and problem marks are rendered in layers behind it.
+#[component]
+pub fn ProgramEditor(
+    textarea_ref: NodeRef<html::Textarea>,
+    #[prop(into)] problems: Signal<Vec<Problem>>,
+) -> impl IntoView {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
+    let custom_preimages =
+        use_context::<CustomPreimages>().expect("custom preimages should exist in context");
+    let gutter_ref = create_node_ref::<html::Div>();
+    let highlight_ref = create_node_ref::<html::Div>();
+    let backdrop_ref = create_node_ref::<html::Div>();
+    // Byte offset of the cursor, if no text is selected
+    let cursor = create_rw_signal(None::<usize>);
+    let scroll = create_rw_signal((0, 0));
+    let completion_menu = create_rw_signal(None::<CompletionMenu>);
+    // Byte offset of the character under the mouse pointer
+    let hover_offset = create_rw_signal(None::<usize>);
+
+    let line_count = create_memo(move |_| program.text.with(|text| text.split('\n').count()));
+    let hover = create_memo(move |_| {
+        let offset = hover_offset.get()?;
+        custom_preimages.0.with(|preimages| {
+            program
+                .text
+                .with(|text| lookup::hover(text, offset, preimages))
+        })
+    });
+    let bracket_pair = create_memo(move |_| {
+        let cursor = cursor.get()?;
+        program
+            .text
+            .with(|text| syntax::bracket_pair_at(&syntax::tokenize(text), cursor))
+    });
+
+    let update_cursor = move || {
+        let element = textarea_ref.get().expect("<textarea> should be mounted");
+        let selection = match (element.selection_start(), element.selection_end()) {
+            (Ok(Some(start)), Ok(Some(end))) => program
+                .text
+                .with_untracked(|text| Some(byte_offset(text, start)..byte_offset(text, end))),
+            _ => None,
+        };
+        cursor.set(
+            selection
+                .as_ref()
+                .filter(|selection| selection.is_empty())
+                .map(|selection| selection.start),
+        );
+        program.selection.set(selection);
+    };
+    // Show the completions of the word at the cursor.
+    // Unless forced, completions are only shown after the user started typing a word.
+    let update_completions = move |force: bool| {
+        let cursor = match cursor.get_untracked() {
+            Some(cursor) => cursor,
+            None => {
+                completion_menu.set(None);
+                return;
+            }
+        };
+        let (range, items) = custom_preimages.0.with_untracked(|preimages| {
+            program
+                .text
+                .with_untracked(|text| completion::completions(text, cursor, preimages))
+        });
+        match items.is_empty() || (!force && range.is_empty()) {
+            true => completion_menu.set(None),
+            false => completion_menu.set(Some(CompletionMenu {
+                range,
+                items,
+                selected: 0,
+            })),
+        }
+    };
+    let accept_completion = move |completion: Completion| {
+        let range = match completion_menu
+            .with_untracked(|menu| menu.as_ref().map(|menu| menu.range.clone()))
+        {
+            Some(range) => range,
+            None => return,
+        };
+        let element = textarea_ref.get().expect("<textarea> should be mounted");
+        let mut text = program.text.get_untracked();
+        text.replace_range(range.clone(), &completion.insertion);
+        let selection_start = utf16_offset(&text, range.start + completion.selection.start);
+        let selection_end = utf16_offset(&text, range.start + completion.selection.end);
+        program.text.set(text);
+        completion_menu.set(None);
+        let _result = element.set_selection_range(selection_start, selection_end);
+        update_cursor();
+    };
+    let update_program_text = move |event: ev::Event| {
+        hover_offset.set(None);
+        program.text.set(event_target_value(&event));
+        update_cursor();
+        update_completions(false);
+    };
+    let insert_4_spaces = move || {
+        let element = textarea_ref.get().expect("<textarea> should be mounted");
+        if let Ok(Some(start)) = element.selection_start() {
+            let start_ = program.text.with_untracked(|text| byte_offset(text, start));
+            program.text.update(|s| s.insert_str(start_, "    "));
+            let _result = element.set_selection_range(start + 4, start + 4);
+        }
+    };
+    let delete_4_spaces = move || {
+        let element = textarea_ref.get().expect("<textarea> should be mounted");
+        if let Ok(Some(start)) = element.selection_start() {
+            let start_ = program.text.with_untracked(|text| byte_offset(text, start));
+            if start_ < 4
+                || program
+                    .text
+                    .with_untracked(|s| s.get(start_ - 4..start_) != Some("    "))
+            {
+                return;
+            }
+            program
+                .text
+                .update(|s| s.replace_range(start_ - 4..start_, ""));
+            let _result = element.set_selection_range(start - 4, start - 4);
+        }
+    };
+    let insert_newline = move || {
+        let element = textarea_ref.get().expect("<textarea> should be mounted");
+        if let (Ok(Some(start)), Ok(Some(end))) =
+            (element.selection_start(), element.selection_end())
+        {
+            let (start, end) = program
+                .text
+                .with_untracked(|text| (byte_offset(text, start), byte_offset(text, end)));
+            let mut text = program.text.get_untracked();
+            text.replace_range(start..end, "");
+            let (insertion, insertion_cursor) = syntax::newline_insertion(&text, start);
+            text.insert_str(start, &insertion);
+            let caret = utf16_offset(&text, start + insertion_cursor);
+            program.text.set(text);
+            let _result = element.set_selection_range(caret, caret);
+            update_cursor();
+        }
+    };
+    let sync_scroll = move |_event: ev::Event| {
+        if let Some(textarea) = textarea_ref.get() {
+            for layer in [highlight_ref.get(), backdrop_ref.get()]
+                .into_iter()
+                .flatten()
+            {
+                layer.set_scroll_top(textarea.scroll_top());
+                layer.set_scroll_left(textarea.scroll_left());
+            }
+            scroll.set((textarea.scroll_top(), textarea.scroll_left()));
+            if let Some(gutter) = gutter_ref.get() {
+                gutter.set_scroll_top(textarea.scroll_top());
+            }
+        }
+    };
+    // Return the byte offset of the character under the mouse pointer
+    let offset_at = move |event: &ev::MouseEvent| -> Option<usize> {
+        let (scroll_top, scroll_left) = scroll.get_untracked();
+        let x = event.offset_x() + scroll_left - TEXT_PADDING_PX;
+        let y = event.offset_y() + scroll_top - TEXT_PADDING_PX;
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let position = Position::new(
+            (y / LINE_HEIGHT_PX) as usize,
+            (f64::from(x) / CHAR_WIDTH_PX) as usize,
+        );
+        program.text.with_untracked(|text| {
+            let offset = position.offset(text);
+            // Positions behind the end of a line are moved to the end of that line
+            (Position::from_offset(text, offset) == position).then_some(offset)
+        })
+    };
+    let go_to_definition = move |event: &ev::MouseEvent| {
+        let element = textarea_ref.get().expect("<textarea> should be mounted");
+        let range = offset_at(event).and_then(|offset| {
+            program
+                .text
+                .with_untracked(|text| lookup::definition(text, offset))
+        });
+        if let Some(range) = range {
+            let (start, end) = program.text.with_untracked(|text| {
+                (
+                    utf16_offset(text, range.start),
+                    utf16_offset(text, range.end),
+                )
+            });
+            // Refocus to scroll the selection into view
+            let _result = element.blur();
+            let _result = element.focus();
+            let _result = element.set_selection_range(start, end);
+            update_cursor();
+        }
+    };
+    let handle_click = move |event: ev::MouseEvent| {
+        completion_menu.set(None);
+        match event.ctrl_key() || event.meta_key() {
+            true => go_to_definition(&event),
+            false => update_cursor(),
+        }
+    };
+    let handle_completion_keydown = move |event: &ev::KeyboardEvent| -> bool {
+        let menu_len = match completion_menu.with_untracked(|menu| {
+            menu.as_ref()
+                .map(|menu| menu.items.len().min(MAX_COMPLETIONS))
+        }) {
+            Some(menu_len) => menu_len,
+            None => return false,
+        };
+        match event.key_code() {
+            ARROW_DOWN_KEY => completion_menu.update(|menu| {
+                if let Some(menu) = menu {
+                    menu.selected = (menu.selected + 1) % menu_len;
+                }
+            }),
+            ARROW_UP_KEY => completion_menu.update(|menu| {
+                if let Some(menu) = menu {
+                    menu.selected = (menu.selected + menu_len - 1) % menu_len;
+                }
+            }),
+            ENTER_KEY | TAB_KEY => {
+                let completion = completion_menu.with_untracked(|menu| {
+                    menu.as_ref().map(|menu| menu.items[menu.selected].clone())
+                });
+                if let Some(completion) = completion {
+                    accept_completion(completion);
+                }
+            }
+            ESCAPE_KEY => completion_menu.set(None),
+            ARROW_LEFT_KEY | ARROW_RIGHT_KEY => {
+                completion_menu.set(None);
+                return false;
+            }
+            _ => return false,
+        }
+        true
+    };
+    let handle_keydown = move |event: ev::KeyboardEvent| {
+        if !event.ctrl_key() && handle_completion_keydown(&event) {
+            event.prevent_default();
+        } else if event.ctrl_key() && event.key_code() == SPACE_KEY {
+            event.prevent_default();
+            update_cursor();
+            update_completions(true);
+        } else if event.ctrl_key() && event.key_code() == ENTER_KEY {
+            runtime.run();
+        } else if event.key_code() == ENTER_KEY {
+            event.prevent_default();
+            insert_newline();
+        } else if event.key_code() == TAB_KEY {
+            event.prevent_default();
+            match event.shift_key() {
+                false => insert_4_spaces(),
+                true => delete_4_spaces(),
+            }
+        }
+    };
+
+    let line_numbers = move || {
+        (1..=line_count.get())
+            .map(|line| view! { <div>{line}</div> })
+            .collect_view()
+    };
+    let highlighted_text = move || {
+        let bracket_pair = bracket_pair.get();
+        let is_matched = |start: usize| {
+            bracket_pair.is_some_and(|(first, second)| start == first || start == second)
+        };
+        program.text.with(|text| {
+            syntax::tokenize(text)
+                .into_iter()
+                .map(|token| match token.kind {
+                    TokenKind::Whitespace => token.text.to_string().into_view(),
+                    kind => {
+                        let class = match is_matched(token.start) {
+                            true => format!("token-{kind} bracket-match"),
+                            false => format!("token-{kind}"),
+                        };
+                        view! { <span class=class>{token.text.to_string()}</span> }.into_view()
+                    }
+                })
+                .collect_view()
+        })
+    };
+    let marked_text = move || {
+        program.text.with(|text| {
+            problems.with(|problems| {
+                problem::segments(text, problems)
+                    .into_iter()
+                    .map(|(segment, severity)| match severity {
+                        None => segment.to_string().into_view(),
+                        Some(Severity::Error) => view! {
+                            <mark class="problem-error">{segment.to_string()}</mark>
+                        }
+                        .into_view(),
+                        Some(Severity::Warning) => view! {
+                            <mark class="problem-warning">{segment.to_string()}</mark>
+                        }
+                        .into_view(),
+                    })
+                    .collect_view()
+            })
+        })
+    };
+
+    let completion_list = move || {
+        completion_menu.with(|menu| {
+            menu.as_ref().map(|menu| {
+                let style = program
+                    .text
+                    .with_untracked(|text| popup_style(text, menu.range.start, scroll.get()));
+                let items = menu
+                    .items
+                    .iter()
+                    .take(MAX_COMPLETIONS)
+                    .enumerate()
+                    .map(|(index, item)| {
+                        let is_selected = index == menu.selected;
+                        let completion = item.clone();
+                        // Prevent the <textarea> from losing focus
+                        let accept = move |event: ev::MouseEvent| {
+                            event.prevent_default();
+                            accept_completion(completion.clone());
+                        };
+                        view! {
+                            <div class="completion" class:selected=is_selected on:mousedown=accept>
+                                <span class="completion-kind">{item.kind.to_string()}</span>
+                                <span class="completion-label">{item.label.clone()}</span>
+                                <span class="completion-detail">{item.detail.clone()}</span>
+                            </div>
+                        }
+                    })
+                    .collect_view();
+
+                view! {
+                    <div class="completions" style=style>
+                        {items}
+                    </div>
+                }
+            })
+        })
+    };
+
+    let hover_info = move || {
+        if completion_menu.with(Option::is_some) {
+            return None;
+        }
+        hover.with(|hover| {
+            hover.as_ref().map(|hover| {
+                let style = program
+                    .text
+                    .with_untracked(|text| popup_style(text, hover.range.start, scroll.get()));
+                let detail = hover
+                    .detail
+                    .clone()
+                    .map(|detail| view! { <div class="hover-detail">{detail}</div> });
+
+                view! {
+                    <div class="hover-info" style=style>
+                        <code class="hover-signature">{hover.signature.clone()}</code>
+                        {detail}
+                    </div>
+                }
+            })
+        })
+    };
+
+    view! {
+        <div class="program-editor">
+            <div class="program-gutter" node_ref=gutter_ref>
+                {line_numbers}
+            </div>
+            <div class="program-editor-area">
+                <div class="program-input-field program-layer program-highlight" node_ref=highlight_ref>
+                    {highlighted_text}
+                    // Keep the height of a trailing empty line
+                    " "
+                </div>
+                <div class="program-input-field program-layer program-backdrop" node_ref=backdrop_ref>
+                    {marked_text}
+                    " "
+                </div>
+                <textarea
+                    class="program-input-field"
+                    placeholder="Enter your program here"
+                    rows="25"
+                    cols="80"
+                    wrap="off"
+                    spellcheck="false"
+                    prop:value=program.text
+                    on:input=update_program_text
+                    on:keydown=handle_keydown
+                    on:keyup=move |_| update_cursor()
+                    on:click=handle_click
+                    on:mousemove=move |event| hover_offset.set(offset_at(&event))
+                    on:mouseleave=move |_| hover_offset.set(None)
+                    on:blur=move |_| {
+                        cursor.set(None);
+                        completion_menu.set(None);
+                    }
+                    on:scroll=sync_scroll
+                    node_ref=textarea_ref
+                >
+                    {program.text.get_untracked()}
+                </textarea>
+                {completion_list}
+                {hover_info}
+            </div>
+        </div>
+    }
+}
@@ -19,7 +19,7 @@ pub fn select_example(example: Example) {
     tx_env.params.set(example.params());
     let arguments = example.arguments(&signing_keys.public_keys, &hashed_data.hashes);
     let program_text = format!("{arguments}\n\n{}", example.template_text());
-    program.text.set(program_text.clone());
+    program.set_single_file(program_text.clone());
     program.update_on_read();
 
     let witness = example.witness(
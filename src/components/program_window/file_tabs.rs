@@ -0,0 +1,109 @@
+use leptos::{
+    component, create_memo, create_rw_signal, ev, event_target_value, use_context, view,
+    CollectView, IntoView, SignalGet, SignalSet, SignalWith, SignalWithUntracked,
+};
+
+use crate::components::program_window::Program;
+use crate::project;
+
+/// Tabs of the source files of the program.
+///
+/// Double-click a tab to rename its file.
+/// The entry point, whose main function is compiled, is marked with a play icon.
+/// An entry point claims library files such as its witness file with `// include:` comments.
+#[component]
+pub fn FileTabs() -> impl IntoView {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let renaming = create_rw_signal(None::<usize>);
+
+    let entry_point = create_memo(move |_| {
+        let active = program.active_file.get();
+        program.files.with(|files| {
+            program.text.with(|text| {
+                let mut files = files.clone();
+                if let Some(file) = files.get_mut(active) {
+                    file.text = text.clone();
+                }
+                project::entry_point(&files, active)
+            })
+        })
+    });
+    let rename = move |index: usize, event: ev::Event| {
+        let name = event_target_value(&event).trim().to_string();
+        let is_valid = program
+            .files
+            .with_untracked(|files| project::is_valid_file_name(files, &name));
+        if is_valid {
+            program.rename_file(index, name);
+        }
+        renaming.set(None);
+    };
+
+    let tabs = move || {
+        let file_count = program.files.with(Vec::len);
+        program
+            .files
+            .with(|files| {
+                files
+                    .iter()
+                    .map(|file| file.name.clone())
+                    .collect::<Vec<_>>()
+            })
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let is_active = move || program.active_file.get() == index;
+                let is_entry = move || entry_point.get() == Some(index);
+                let remove = move |event: ev::MouseEvent| {
+                    event.stop_propagation();
+                    program.remove_file(index);
+                };
+                let label = move || match renaming.get() == Some(index) {
+                    true => view! {
+                        <input
+                            class="file-tab-input"
+                            type="text"
+                            prop:value=name.clone()
+                            on:change=move |event| rename(index, event)
+                            on:blur=move |_| renaming.set(None)
+                        />
+                    }
+                    .into_view(),
+                    false => view! {
+                        <span class="file-tab-name">{name.clone()}</span>
+                    }
+                    .into_view(),
+                };
+                view! {
+                    <div
+                        class="file-tab"
+                        class:active=is_active
+                        on:click=move |_| program.open_file(index)
+                        on:dblclick=move |_| renaming.set(Some(index))
+                    >
+                        <i class="fa-solid fa-play file-tab-entry" class:hidden=move || !is_entry()></i>
+                        {label}
+                        <i
+                            class="fa-solid fa-xmark file-tab-remove"
+                            class:hidden=move || file_count <= 1
+                            on:click=remove
+                        ></i>
+                    </div>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="file-tabs">
+            {tabs}
+            <button
+                class="file-tab-add"
+                title="Add file"
+                on:click=move |_| program.add_file("file.simf".to_string(), String::new())
+            >
+                <i class="fa-solid fa-plus"></i>
+            </button>
+        </div>
+    }
+}
@@ -1,11 +1,16 @@
 mod address_button;
+mod check_worker;
+mod editor;
 mod examples_dropdown;
+mod file_tabs;
 mod help_button;
 mod program_tab;
 mod run_button;
 mod share_button;
+mod tidy_button;
 mod tools_dropdown;
 mod transaction_button;
+mod workspace_dropdown;
 
 use leptos::create_signal;
 use leptos::{component, view, IntoView, SignalGet, SignalSet};
@@ -16,11 +21,14 @@ use self::help_button::HelpButton;
 use self::program_tab::ProgramTab;
 use self::run_button::RunButton;
 use self::share_button::ShareButton;
+use self::tidy_button::TidyButton;
 use self::transaction_button::TransactionButton;
+use self::workspace_dropdown::WorkspaceDropdown;
 use crate::components::toolbar::Toolbar;
 
 pub use self::examples_dropdown::select_example;
-pub use self::program_tab::{Program, Runtime};
+pub use self::program_tab::{Program, RunProgram, Runtime};
+pub use self::share_button::SHARE_FRAGMENT_PREFIX;
 
 #[component]
 pub fn ProgramWindow() -> impl IntoView {
@@ -30,6 +38,8 @@ pub fn ProgramWindow() -> impl IntoView {
         <Toolbar>
             <RunButton />
             <ExamplesDropdown />
+            <TidyButton />
+            <WorkspaceDropdown />
 
             <div class="mobile-hidden"  class:open = move || mobile_open.get() >
                 <AddressButton />
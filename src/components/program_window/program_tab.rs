@@ -1,25 +1,96 @@
+use std::cmp::Ordering;
+use std::ops::Range;
 use std::sync::Arc;
 
+use elements::secp256k1_zkp as secp256k1;
+use hex_conservative::DisplayHex;
 use itertools::Itertools;
 use leptos::{
-    component, create_node_ref, create_rw_signal, ev, event_target_value, html, spawn_local,
-    use_context, view, IntoView, NodeRef, RwSignal, Signal, SignalGetUntracked, SignalSet,
-    SignalUpdate, SignalWith, SignalWithUntracked,
+    component, create_effect, create_node_ref, create_rw_signal, ev, html, on_cleanup,
+    request_animation_frame, spawn_local, store_value, use_context, view, CollectView, IntoView,
+    NodeRef, RwSignal, Signal, SignalGet, SignalGetUntracked, SignalSet, SignalTrack, SignalUpdate,
+    SignalWith, SignalWithUntracked,
 };
 use simfony::parse::ParseFromStr;
 use simfony::simplicity::jet::elements::ElementsEnv;
 use simfony::{elements, simplicity};
 use simfony::{CompiledProgram, SatisfiedProgram, WitnessValues};
 
+use crate::check;
 use crate::components::copy_to_clipboard::CopyToClipboard;
-use crate::function::Runner;
+use crate::components::program_window::check_worker::CheckWorker;
+use crate::components::program_window::editor::{utf16_offset, ProgramEditor};
+use crate::components::program_window::file_tabs::FileTabs;
+use crate::components::run_window::CustomPreimages;
+use crate::function::{Runner, Trace};
+use crate::hash::{self, CustomPreimage};
+use crate::problem::{self, Position, Problem, Severity, Span};
+use crate::project::{self, Combined, SourceFile};
+use crate::source_map::{self, SourceMap};
+use crate::util::{Expression, HashedData, SigningKeys};
+use crate::witness::{self, Module, Resolved, Symbol};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Program {
+    /// Text of the file that is open in the editor.
     pub text: RwSignal<String>,
+    /// Source files of the program.
+    ///
+    /// The text of the active file is outdated while the file is open.
+    /// Use [`Program::text`] instead.
+    pub files: RwSignal<Vec<SourceFile>>,
+    pub active_file: RwSignal<usize>,
+    /// Byte range of the active file that is selected in the editor.
+    ///
+    /// The range is empty if nothing is selected, and it is the position of the cursor.
+    pub selection: RwSignal<Option<Range<usize>>>,
+    /// Span of the active file that the editor is asked to select.
+    pub revealed: RwSignal<Option<Span>>,
+    /// Program text that was compiled, with signatures replaced by placeholders.
     cached_text: RwSignal<String>,
+    /// Program text that was satisfied, with all symbols replaced by their values.
+    cached_witness_text: RwSignal<String>,
     pub lazy_cmr: RwSignal<Result<simplicity::Cmr, String>>,
+    lazy_compiled: RwSignal<Result<CompiledProgram, String>>,
     lazy_satisfied: RwSignal<Result<SatisfiedProgram, String>>,
+    symbol_values: RwSignal<Option<SymbolValues>>,
+    /// Source map with the combined program text that it was built from.
+    cached_source_map: RwSignal<Option<(String, Arc<SourceMap>)>>,
+}
+
+/// Values of the symbols that can be used in place of witness values.
+#[derive(Clone, Debug)]
+struct SymbolValues {
+    signing_keys: SigningKeys,
+    hashed_data: HashedData,
+    custom_preimages: CustomPreimages,
+    message: Signal<secp256k1::Message>,
+}
+
+impl SymbolValues {
+    /// Return the value of the given `symbol`.
+    ///
+    /// Signatures are replaced by placeholders unless `sign` is true.
+    fn get(&self, symbol: Symbol, sign: bool) -> Result<String, String> {
+        match symbol {
+            Symbol::Signature(index) if sign => {
+                let signature = self.signing_keys.sign(index, self.message.get_untracked());
+                Ok(format!("0x{}", signature.serialize().as_hex()))
+            }
+            Symbol::Signature(..) => Ok(symbol.placeholder(&[])),
+            Symbol::Preimage(index) => match self.hashed_data.preimages.get(index) {
+                Some(preimage) => Ok(format!("0x{}", preimage.as_hex())),
+                None => self.custom_preimages.0.with_untracked(|preimages| {
+                    preimages
+                        .get(index - self.hashed_data.preimages.len())
+                        .map(|custom| hash::simfony_bytes(&custom.preimage))
+                        .ok_or(format!(
+                            "`preimage::{index}`: There is no such preimage in the hash store"
+                        ))
+                }),
+            },
+        }
+    }
 }
 
 impl Default for Program {
@@ -28,20 +99,170 @@ impl Default for Program {
     }
 }
 
+/// Combine the program `files` with `text` as the text of the `active` file.
+fn combine_files(files: &[SourceFile], active: usize, text: &str) -> Combined {
+    let mut files = files.to_vec();
+    if let Some(file) = files.get_mut(active) {
+        file.text = text.to_string();
+    }
+    Combined::new(&files, active)
+}
+
 impl Program {
     pub fn new(text: String) -> Self {
+        let files = vec![SourceFile::new(project::DEFAULT_FILE_NAME, text)];
+        Self::with_files(files, 0)
+    }
+
+    /// Create a program that consists of the given `files`,
+    /// where the file at index `active` is open in the editor.
+    pub fn with_files(files: Vec<SourceFile>, active: usize) -> Self {
+        let text = files
+            .get(active)
+            .map(|file| file.text.clone())
+            .unwrap_or_default();
         let program = Self {
             text: create_rw_signal(text),
+            files: create_rw_signal(files),
+            active_file: create_rw_signal(active),
+            selection: create_rw_signal(None),
+            revealed: create_rw_signal(None),
             cached_text: create_rw_signal("".to_string()),
+            cached_witness_text: create_rw_signal("".to_string()),
             lazy_cmr: create_rw_signal(Err("".to_string())),
+            lazy_compiled: create_rw_signal(Err("".to_string())),
             lazy_satisfied: create_rw_signal(Err("".to_string())),
+            symbol_values: create_rw_signal(None),
+            cached_source_map: create_rw_signal(None),
         };
         program.update_on_read();
         program
     }
 
     pub fn is_empty(&self) -> bool {
-        self.text.with_untracked(String::is_empty)
+        self.files.with_untracked(|files| files.len() <= 1)
+            && self.text.with_untracked(String::is_empty)
+    }
+
+    /// Return the source files of the program, including the current text of the active file.
+    pub fn current_files(self) -> Vec<SourceFile> {
+        let mut files = self.files.get_untracked();
+        if let Some(file) = files.get_mut(self.active_file.get_untracked()) {
+            file.text = self.text.get_untracked();
+        }
+        files
+    }
+
+    /// Return the program text that combines the libraries of the entry point with the entry point.
+    ///
+    /// The combined text is what is compiled.
+    pub fn combined(self) -> Combined {
+        self.files.with(|files| {
+            self.text
+                .with(|text| combine_files(files, self.active_file.get(), text))
+        })
+    }
+
+    /// Return the combined program text without tracking changes.
+    pub fn combined_untracked(self) -> Combined {
+        self.files.with_untracked(|files| {
+            self.text
+                .with_untracked(|text| combine_files(files, self.active_file.get_untracked(), text))
+        })
+    }
+
+    /// Open the file at the given `index` in the editor.
+    pub fn open_file(self, index: usize) {
+        let active = self.active_file.get_untracked();
+        if index == active || self.files.with_untracked(|files| files.len() <= index) {
+            return;
+        }
+        let text = self.text.get_untracked();
+        self.files.update(|files| files[active].text = text);
+        self.selection.set(None);
+        self.active_file.set(index);
+        self.text
+            .set(self.files.with_untracked(|files| files[index].text.clone()));
+    }
+
+    /// Open the file at the given `index` and select the given `span` of it in the editor.
+    pub fn reveal(self, index: usize, span: Span) {
+        self.open_file(index);
+        self.revealed.set(Some(span));
+    }
+
+    /// Add a file with the given `name` and `text`, and open it in the editor.
+    ///
+    /// The name is changed if another file has the same name.
+    pub fn add_file(self, name: String, text: String) {
+        self.files.update(|files| {
+            let name = project::unused_file_name(files, &name);
+            files.push(SourceFile::new(name, text));
+        });
+        self.open_file(self.files.with_untracked(Vec::len) - 1);
+    }
+
+    pub fn rename_file(self, index: usize, name: String) {
+        self.files.update(|files| {
+            if let Some(file) = files.get_mut(index) {
+                file.name = name;
+            }
+        });
+    }
+
+    /// Remove the file at the given `index`.
+    ///
+    /// The last remaining file cannot be removed.
+    pub fn remove_file(self, index: usize) {
+        let len = self.files.with_untracked(Vec::len);
+        if len <= 1 || len <= index {
+            return;
+        }
+        let active = self.active_file.get_untracked();
+        let mut files = self.current_files();
+        files.remove(index);
+        let active = match active.cmp(&index) {
+            Ordering::Greater => active - 1,
+            Ordering::Equal => active.min(files.len() - 1),
+            Ordering::Less => active,
+        };
+        let text = files[active].text.clone();
+        self.files.set(files);
+        self.active_file.set(active);
+        self.text.set(text);
+    }
+
+    /// Replace all files by a single file with the given `text`.
+    pub fn set_single_file(self, text: String) {
+        self.files.set(vec![SourceFile::new(
+            project::DEFAULT_FILE_NAME,
+            text.clone(),
+        )]);
+        self.active_file.set(0);
+        self.text.set(text);
+    }
+
+    /// Return the index of the file that contains the given `module`.
+    ///
+    /// Return the index of the active file if no file contains the module.
+    fn module_file(self, module: Module) -> usize {
+        let combined = self.combined_untracked();
+        witness::module_body(&combined.text, module)
+            .map(|body| Position::from_offset(&combined.text, body.start))
+            .and_then(|position| combined.locate(position))
+            .map_or(self.active_file.get_untracked(), |(index, _)| index)
+    }
+
+    /// Edit the text of the file that contains the given `module`.
+    pub fn update_module_file<F>(self, module: Module, f: F)
+    where
+        F: FnOnce(&mut String),
+    {
+        let index = self.module_file(module);
+        match index == self.active_file.get_untracked() {
+            true => self.text.update(f),
+            false => self.files.update(|files| f(&mut files[index].text)),
+        }
     }
 
     pub fn cmr(self) -> Result<simplicity::Cmr, String> {
@@ -54,36 +275,274 @@ impl Program {
         self.lazy_satisfied.get_untracked()
     }
 
-    pub fn update_on_read(self) {
-        let needs_update = self.text.with_untracked(|text| {
-            self.cached_text
-                .with_untracked(|cached_text| text != cached_text)
-        });
-        if !needs_update {
-            return;
+    /// Resolve symbols such as `sig::alice` or `preimage::0` using the given stores.
+    ///
+    /// Signatures are computed over the current value of `message`
+    /// each time the program is read, after the CMR is updated.
+    pub fn resolve_symbols_with(
+        self,
+        signing_keys: SigningKeys,
+        hashed_data: HashedData,
+        custom_preimages: CustomPreimages,
+        message: Signal<secp256k1::Message>,
+    ) {
+        self.symbol_values.set(Some(SymbolValues {
+            signing_keys,
+            hashed_data,
+            custom_preimages,
+            message,
+        }));
+    }
+
+    /// Return the given program `text` with all symbols replaced by their values.
+    ///
+    /// Signatures are computed over the current message if `sign` is true,
+    /// and they are replaced by placeholders otherwise.
+    /// Symbols are replaced by placeholders if their values are not yet available.
+    fn resolve(self, text: &str, sign: bool) -> Result<Resolved, String> {
+        self.symbol_values.with_untracked(|values| match values {
+            Some(values) => witness::resolve_mapped(text, |symbol| values.get(symbol, sign)),
+            None => witness::resolve_mapped(text, |symbol| Ok(symbol.placeholder(&[]))),
+        })
+    }
+
+    /// Return the custom preimages of the hash store, which determine the types of their symbols.
+    ///
+    /// There are none until the symbols can be resolved.
+    fn custom_preimages_untracked(self) -> Vec<CustomPreimage> {
+        self.symbol_values.with_untracked(|values| {
+            values
+                .as_ref()
+                .map(|values| values.custom_preimages.0.get_untracked())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Return the combined program text that the compiler checks,
+    /// with signatures replaced by placeholders of the same length.
+    pub fn check_text(self) -> Result<Resolved, String> {
+        self.resolve(&self.combined_untracked().text, false)
+    }
+
+    /// Return the map from the Simplicity nodes of the program
+    /// to the statements of the combined program text,
+    /// if it was built for the current text.
+    pub fn source_map(self) -> Option<Arc<SourceMap>> {
+        let text = self.combined_untracked().text;
+        self.cached_source_map
+            .with_untracked(|cached| match cached {
+                Some((cached_text, source_map)) if cached_text == &text => {
+                    Some(Arc::clone(source_map))
+                }
+                _ => None,
+            })
+    }
+
+    /// Return the variants of the combined program text that its source map is built from,
+    /// with signatures replaced by placeholders.
+    ///
+    /// A variant whose symbols cannot be resolved is empty.
+    pub fn source_map_variants(self) -> Vec<String> {
+        let text = self.combined_untracked().text;
+        source_map::marked_texts(&text)
+            .iter()
+            .map(|marked| {
+                self.resolve(marked, false)
+                    .map(|resolved| resolved.text)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Store the source map of the combined program `text`,
+    /// given the CMRs of the nodes that each of its variants changes.
+    ///
+    /// The check worker compares the variants of [`Program::source_map_variants`]
+    /// with the program.
+    pub fn set_source_map(self, text: String, changed: &[Vec<String>]) -> Arc<SourceMap> {
+        let changed = changed
+            .iter()
+            .map(|cmrs| cmrs.iter().filter_map(|cmr| cmr.parse().ok()).collect())
+            .collect();
+        let source_map = Arc::new(SourceMap::from_changes(&text, changed));
+        self.cached_source_map
+            .set(Some((text, Arc::clone(&source_map))));
+        source_map
+    }
+
+    /// Build the source map of the current program text on the main thread,
+    /// unless it is already built.
+    ///
+    /// Building the map compiles the program once per statement,
+    /// so it should happen only if the check worker is unavailable.
+    pub fn update_source_map(self) -> Option<Arc<SourceMap>> {
+        if let Some(source_map) = self.source_map() {
+            return Some(source_map);
         }
-        self.text.with_untracked(|text| {
-            self.cached_text.set(text.clone());
-            let compiled = simfony::Arguments::parse_from_str(text)
+        let text = self.combined_untracked().text;
+        let source_map = SourceMap::new(&text, |text| {
+            let text = self.resolve(text, false)?.text;
+            let compiled = simfony::Arguments::parse_from_str(&text)
                 .map_err(|error| error.to_string())
-                .and_then(|args| CompiledProgram::new(text.as_str(), args));
+                .and_then(|args| CompiledProgram::new(text.as_str(), args))?;
+            Ok(check::commit_cmrs(&compiled.commit()))
+        })
+        .ok()
+        .map(Arc::new)?;
+        self.cached_source_map
+            .set(Some((text, Arc::clone(&source_map))));
+        Some(source_map)
+    }
+
+    pub fn update_on_read(self) {
+        let text = self.combined_untracked().text;
+
+        // The signed message may depend on the CMR, which does not depend on signatures.
+        // The program is compiled with placeholders, and signatures are computed afterwards.
+        let commit_text = self.resolve(&text, false).map(|resolved| resolved.text);
+        let cache_key = match &commit_text {
+            Ok(commit_text) => commit_text.clone(),
+            Err(..) => text.clone(),
+        };
+        let needs_compile = self
+            .cached_text
+            .with_untracked(|cached_text| &cache_key != cached_text);
+        if needs_compile {
+            self.cached_text.set(cache_key);
+            let compiled = commit_text.and_then(|commit_text| {
+                simfony::Arguments::parse_from_str(&commit_text)
+                    .map_err(|error| error.to_string())
+                    .and_then(|args| CompiledProgram::new(commit_text.as_str(), args))
+            });
             let cmr = compiled
                 .as_ref()
                 .map(|x| x.commit().cmr())
                 .map_err(Clone::clone);
             self.lazy_cmr.set(cmr);
-            let satisfied = compiled.and_then(|x| {
-                let witness = WitnessValues::parse_from_str(text)?;
-                x.satisfy(witness)
-            });
-            self.lazy_satisfied.set(satisfied);
+            self.lazy_compiled.set(compiled);
+        }
+
+        // Signatures change with the signed data, so the witness is keyed by the resolved text
+        let witness_text = self.resolve(&text, true).map(|resolved| resolved.text);
+        let cache_key = match &witness_text {
+            Ok(witness_text) => witness_text.clone(),
+            Err(error) => error.clone(),
+        };
+        let needs_satisfy = needs_compile
+            || self
+                .cached_witness_text
+                .with_untracked(|cached_text| &cache_key != cached_text);
+        if !needs_satisfy {
+            return;
+        }
+        self.cached_witness_text.set(cache_key);
+        let satisfied = self.lazy_compiled.with_untracked(|compiled| {
+            let compiled = compiled.as_ref().map_err(Clone::clone)?;
+            let witness = WitnessValues::parse_from_str(&witness_text?)?;
+            compiled.satisfy(witness)
         });
+        self.lazy_satisfied.set(satisfied);
+    }
+
+    /// Compile and satisfy the program on the main thread, and return the problems that occur,
+    /// with the spans of program text where they occur.
+    ///
+    /// Spans refer to the text of the active file.
+    /// Problems in other files have no span, and their message starts with the file name and position.
+    pub fn problems(self) -> Vec<Problem> {
+        self.update_on_read();
+        let cmr_error = self.lazy_cmr.with_untracked(|cmr| cmr.clone().err());
+        let satisfied_error = self
+            .lazy_satisfied
+            .with_untracked(|satisfied| satisfied.as_ref().err().cloned());
+        let errors = cmr_error.iter().chain(satisfied_error.iter());
+        let resolved = self
+            .check_text()
+            .unwrap_or_else(|_| Resolved::unchanged(&self.combined_untracked().text));
+        self.locate_problems(&resolved, errors.map(String::as_str))
+    }
+
+    /// Return the problems of the program, given the `response` of the worker
+    /// that checked the `resolved` text of [`Program::check_text`].
+    ///
+    /// The CMR is updated if the program did not change in the meantime.
+    pub fn checked_problems(self, resolved: &Resolved, response: &check::Response) -> Vec<Problem> {
+        let is_current = self
+            .check_text()
+            .is_ok_and(|current| current.text == resolved.text);
+        if is_current {
+            let cmr = match response.cmr.as_deref().map(str::parse::<simplicity::Cmr>) {
+                Some(Ok(cmr)) => Ok(cmr),
+                Some(Err(error)) => Err(error.to_string()),
+                None => Err(response.errors.first().cloned().unwrap_or_default()),
+            };
+            self.lazy_cmr.set(cmr);
+        }
+        self.locate_problems(resolved, response.errors.iter().map(String::as_str))
+    }
+
+    /// Collect the problems of the given compiler `errors` on the `resolved` program text
+    /// and move them to the files where they occur.
+    fn locate_problems<'a, I>(self, resolved: &Resolved, errors: I) -> Vec<Problem>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let combined = self.combined_untracked();
+        let active = self.active_file.get_untracked();
+        let problems = problem::collect_problems(
+            &combined.text,
+            resolved,
+            errors,
+            &self.custom_preimages_untracked(),
+        );
+
+        problems
+            .into_iter()
+            .map(|problem| {
+                let span = match problem.span {
+                    Some(span) => span,
+                    None => return problem,
+                };
+                match (combined.locate(span.start), combined.locate(span.end)) {
+                    (Some((index, start)), Some((_, end))) if index == active => Problem {
+                        span: Some(Span { start, end }),
+                        ..problem
+                    },
+                    (Some((index, start)), _) => {
+                        let name = self.files.with_untracked(|files| files[index].name.clone());
+                        Problem {
+                            message: format!("{name}:{start}: {}", problem.message),
+                            span: None,
+                            ..problem
+                        }
+                    }
+                    (None, _) => problem,
+                }
+            })
+            .collect()
     }
 
+    /// Add typed placeholders for all witness values and parameters that the program uses
+    /// but that are not assigned yet.
+    ///
+    /// If the program cannot be parsed, then only add empty modules.
+    ///
+    /// Placeholders are added to the file that contains the module,
+    /// and missing modules are added to the active file.
     pub fn add_default_modules(self) {
-        let (contains_witness, contains_param) = self
-            .text
-            .with_untracked(|text| (text.contains("mod witness"), text.contains("mod param")));
+        let combined = self.combined_untracked();
+        if let Ok(stubs) = witness::stubs(&combined.text, &self.custom_preimages_untracked()) {
+            for (module, lines) in stubs {
+                self.update_module_file(module, |text| {
+                    witness::insert_into_module(text, module, &lines)
+                });
+            }
+            return;
+        }
+        let (contains_witness, contains_param) = (
+            combined.text.contains("mod witness"),
+            combined.text.contains("mod param"),
+        );
         if !contains_param {
             self.text
                 .update(|text| text.insert_str(0, "mod param {}\n\n"));
@@ -95,11 +554,58 @@ impl Program {
     }
 }
 
+/// Simplicity program of a run.
+#[derive(Clone, Debug)]
+pub struct RunProgram {
+    pub program: Arc<Expression>,
+    /// Pruned program, if the run succeeded.
+    pub pruned: Option<Arc<Expression>>,
+    /// Nodes that the run executed.
+    pub trace: Arc<Trace>,
+    /// Map from the nodes to the statements of the combined program text,
+    /// if the program was compiled from Simfony.
+    pub source_map: Option<Arc<SourceMap>>,
+    /// Combined program text that was run, which is empty if there is no Simfony source.
+    pub combined: Arc<Combined>,
+}
+
+impl RunProgram {
+    /// Return the statements of the source map that overlap the code that is selected in the editor.
+    pub fn selected_statements(&self, program: Program) -> Vec<usize> {
+        let (source_map, selection) = match (&self.source_map, program.selection.get()) {
+            (Some(source_map), Some(selection)) => (source_map, selection),
+            _ => return vec![],
+        };
+        let active = program.active_file.get_untracked();
+        let range = program.text.with_untracked(|text| {
+            let start = Position::from_offset(text, selection.start);
+            let end = Position::from_offset(text, selection.end);
+            let start = self.combined.position_in(active, start)?;
+            let end = self.combined.position_in(active, end)?;
+            Some(start.offset(&self.combined.text)..end.offset(&self.combined.text))
+        });
+        range.map_or(vec![], |range| source_map.statements_at(range))
+    }
+
+    /// Return the index of the file that contains the statement at the given `index`
+    /// of the source map, together with the span of the statement in that file.
+    pub fn statement_location(&self, index: usize) -> Option<(usize, Span)> {
+        let statement = self.source_map.as_ref()?.statements().get(index)?;
+        let start = Position::from_offset(&self.combined.text, statement.span.start);
+        let end = Position::from_offset(&self.combined.text, statement.span.end);
+        let (file, start) = self.combined.locate(start)?;
+        let (_, end) = self.combined.locate(end)?;
+        Some((file, Span { start, end }))
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct Runtime {
     program: Program,
     env: Signal<ElementsEnv<Arc<elements::Transaction>>>,
     pub run_succeeded: RwSignal<Option<bool>>,
+    /// Program of the last run, if it could be satisfied.
+    pub last_run: RwSignal<Option<RunProgram>>,
     pub debug_output: RwSignal<String>,
     pub error_output: RwSignal<String>,
     // This node ref needs to be mounted somewhere in order to work.
@@ -112,6 +618,7 @@ impl Runtime {
             program,
             env,
             run_succeeded: Default::default(),
+            last_run: Default::default(),
             debug_output: Default::default(),
             error_output: Default::default(),
             alarm_audio_ref: Default::default(),
@@ -142,12 +649,41 @@ impl Runtime {
             Ok(x) => x,
             Err(error) => {
                 self.error_output.set(error);
+                self.last_run.set(None);
                 self.set_success(false);
                 return;
             }
         };
-        let mut runner = Runner::for_program(satisfied_program);
-        let success = self.env.with(|env| match runner.run(env) {
+        let program = satisfied_program.redeem().clone();
+        let runner = Runner::for_program(satisfied_program);
+        // The source map is built while the user is idle, and it is added later if it is missing
+        let source_map = self.program.source_map();
+        let combined = Arc::new(self.program.combined_untracked());
+        self.env
+            .with(|env| self.execute(program, runner, env, source_map, combined));
+    }
+
+    /// Run the given Simplicity `program` that has no Simfony source in the environment `env`.
+    pub fn run_redeem(
+        self,
+        program: Arc<Expression>,
+        env: &ElementsEnv<Arc<elements::Transaction>>,
+    ) {
+        self.debug_output.update(String::clear);
+        let runner = Runner::for_redeem(Arc::clone(&program));
+        self.execute(program, runner, env, None, Arc::default());
+    }
+
+    /// Run the `program` with the `runner` in the environment `env`, and show the outcome.
+    fn execute(
+        self,
+        program: Arc<Expression>,
+        mut runner: Runner,
+        env: &ElementsEnv<Arc<elements::Transaction>>,
+        source_map: Option<Arc<SourceMap>>,
+        combined: Arc<Combined>,
+    ) {
+        let success = match runner.run(env) {
             Ok(..) => {
                 self.error_output.update(String::clear);
                 true
@@ -156,56 +692,184 @@ impl Runtime {
                 self.error_output.set(error.to_string());
                 false
             }
-        });
+        };
+        let pruned = match success {
+            true => program.prune(env).ok(),
+            false => None,
+        };
+        let trace = Arc::new(runner.trace().clone());
+        self.last_run.set(Some(RunProgram {
+            program,
+            pruned,
+            trace,
+            source_map,
+            combined,
+        }));
         self.debug_output
             .set(runner.debug_output().into_iter().join("\n"));
         self.set_success(success);
     }
 }
 
-const TAB_KEY: u32 = 9;
-const ENTER_KEY: u32 = 13;
+/// Time in milliseconds after the last keystroke until the program is checked.
+const CHECK_DELAY_MS: u32 = 300;
+/// Time in milliseconds that the worker has to answer a check,
+/// before the program is checked on the main thread instead.
+const CHECK_TIMEOUT_MS: u32 = 10_000;
+/// Time in milliseconds after the program compiled until its source map is built.
+const SOURCE_MAP_DELAY_MS: u32 = 1000;
 
 #[component]
 pub fn ProgramTab() -> impl IntoView {
     let program = use_context::<Program>().expect("program should exist in context");
-    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
     let textarea_ref = create_node_ref::<html::Textarea>();
-
-    let update_program_text = move |event: ev::Event| {
-        program.text.set(event_target_value(&event));
-    };
-    let insert_4_spaces = move || {
-        let element = textarea_ref.get().expect("<textarea> should be mounted");
-        if let Ok(Some(start)) = element.selection_start() {
-            let start_ = start as usize; // safety: 32-bit machine of higher
-            program.text.update(|s| s.insert_str(start_, "    "));
-            let _result = element.set_selection_range(start + 4, start + 4);
+    let problems = create_rw_signal(Vec::<Problem>::new());
+    let check_generation = store_value(0u64);
+    // Text that the worker is checking, whose spans the problems refer to
+    let checked_text = store_value(None::<Resolved>);
+    // Combined text whose source map the worker is building
+    let mapped_text = store_value(None::<String>);
+    // Add the source map to the last run of the same program
+    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
+    let attach_source_map = move |source_map: Arc<SourceMap>| {
+        let is_missing = runtime.last_run.with_untracked(|run| {
+            run.as_ref().is_some_and(|run| {
+                run.source_map.is_none() && run.combined.text == program.combined_untracked().text
+            })
+        });
+        if is_missing {
+            runtime.last_run.update(|run| {
+                if let Some(run) = run {
+                    run.source_map = Some(source_map);
+                }
+            });
         }
     };
-    let delete_4_spaces = move || {
-        let element = textarea_ref.get().expect("<textarea> should be mounted");
-        if let Ok(Some(start)) = element.selection_start() {
-            let start_ = start as usize; // safety: 32-bit machine of higher
-            if start < 4 || program.text.with(|s| &s[start_ - 4..start_] != "    ") {
+    let check_worker = CheckWorker::spawn(move |response| {
+        checked_text.with_value(|resolved| {
+            if let Some(resolved) = resolved {
+                problems.set(program.checked_problems(resolved, &response));
+            }
+        });
+        if let (Some(changed), Some(text)) = (&response.changed, mapped_text.get_value()) {
+            attach_source_map(program.set_source_map(text, changed));
+        }
+    });
+    // Without a working worker, the program is checked on the main thread
+    let check_worker = store_value(check_worker.ok());
+    on_cleanup(move || {
+        check_worker.with_value(|worker| {
+            if let Some(worker) = worker {
+                worker.terminate();
+            }
+        })
+    });
+
+    // Check the program once the user stops typing
+    create_effect(move |_| {
+        program.text.track();
+        program.files.track();
+        check_generation.update_value(|generation| *generation += 1);
+        let generation = check_generation.get_value();
+        spawn_local(async move {
+            gloo_timers::future::TimeoutFuture::new(CHECK_DELAY_MS).await;
+            if check_generation.get_value() != generation {
                 return;
             }
-            program
-                .text
-                .update(|s| s.replace_range(start_ - 4..start_, ""));
-            let _result = element.set_selection_range(start - 4, start - 4);
+            match (check_worker.get_value(), program.check_text()) {
+                (Some(worker), Ok(resolved)) => {
+                    let request = worker.check(resolved.text.clone(), vec![]);
+                    checked_text.set_value(Some(resolved));
+                    gloo_timers::future::TimeoutFuture::new(CHECK_TIMEOUT_MS).await;
+                    // The worker failed to load or crashed, so it is replaced by the main thread
+                    if check_generation.get_value() == generation && !worker.has_answered(request) {
+                        worker.terminate();
+                        check_worker.set_value(None);
+                        problems.set(program.problems());
+                    }
+                }
+                _ => problems.set(program.problems()),
+            }
+        });
+    });
+
+    // Build the source map once the program compiles and the user stops typing
+    create_effect(move |_| {
+        if program.lazy_cmr.with(Result::is_err) {
+            return;
         }
-    };
-    let handle_keydown = move |event: ev::KeyboardEvent| {
-        if event.ctrl_key() && event.key_code() == ENTER_KEY {
-            runtime.run();
-        } else if event.key_code() == TAB_KEY {
-            event.prevent_default();
-            match event.shift_key() {
-                false => insert_4_spaces(),
-                true => delete_4_spaces(),
+        let generation = check_generation.get_value();
+        spawn_local(async move {
+            gloo_timers::future::TimeoutFuture::new(SOURCE_MAP_DELAY_MS).await;
+            if check_generation.get_value() != generation {
+                return;
             }
+            if let Some(source_map) = program.source_map() {
+                attach_source_map(source_map);
+                return;
+            }
+            match (check_worker.get_value(), program.check_text()) {
+                // The worker answers with the changes of the variants
+                (Some(worker), Ok(resolved)) => {
+                    mapped_text.set_value(Some(program.combined_untracked().text));
+                    worker.check(resolved.text.clone(), program.source_map_variants());
+                    checked_text.set_value(Some(resolved));
+                }
+                (Some(..), Err(..)) => {}
+                (None, _) => {
+                    if let Some(source_map) = program.update_source_map() {
+                        attach_source_map(source_map);
+                    }
+                }
+            }
+        });
+    });
+
+    let jump_to = move |span: Span| {
+        let element = textarea_ref.get().expect("<textarea> should be mounted");
+        let (start, end) = program.text.with_untracked(|text| {
+            let range = span.range(text);
+            (
+                utf16_offset(text, range.start),
+                utf16_offset(text, range.end),
+            )
+        });
+        let _result = element.focus();
+        let _result = element.set_selection_range(start, end);
+    };
+    // Select code that other components ask for, once the file is open in the editor
+    create_effect(move |_| {
+        if let Some(span) = program.revealed.get() {
+            request_animation_frame(move || jump_to(span));
         }
+    });
+    let problem_list = move || {
+        problems
+            .get()
+            .into_iter()
+            .map(|problem| {
+                let position = problem
+                    .span
+                    .map(|span| span.start.to_string())
+                    .unwrap_or_default();
+                let icon = match problem.severity {
+                    Severity::Error => "fas fa-circle-xmark",
+                    Severity::Warning => "fas fa-triangle-exclamation",
+                };
+                let jump = move |_event: ev::MouseEvent| {
+                    if let Some(span) = problem.span {
+                        jump_to(span);
+                    }
+                };
+                view! {
+                    <div class="problem" on:click=jump>
+                        <i class=icon></i>
+                        <span class="problem-position">{position}</span>
+                        <pre class="problem-message">{problem.message}</pre>
+                    </div>
+                }
+            })
+            .collect_view()
     };
 
     view! {
@@ -215,19 +879,11 @@ pub fn ProgramTab() -> impl IntoView {
                     <i class="far fa-copy"></i>
                 </CopyToClipboard>
             </div>
-            <textarea
-                class="program-input-field"
-                placeholder="Enter your program here"
-                rows="25"
-                cols="80"
-                spellcheck="false"
-                prop:value=program.text
-                on:input=update_program_text
-                on:keydown=handle_keydown
-                node_ref=textarea_ref
-            >
-                {program.text.get_untracked()}
-            </textarea>
+            <FileTabs />
+            <ProgramEditor textarea_ref=textarea_ref problems=problems />
+            <div class="problems">
+                {problem_list}
+            </div>
         </div>
     }
 }
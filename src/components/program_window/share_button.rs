@@ -1,14 +1,47 @@
-use leptos::{component, view, IntoView};
+use leptos::{component, view, IntoView, Signal, SignalGetUntracked};
+use leptos_router::use_location;
 
 use crate::components::copy_to_clipboard::CopyToClipboard;
+use crate::components::state;
+use crate::share;
+
+/// Prefix of the URL fragment that holds a shared IDE state.
+///
+/// The state is put into the fragment because browsers do not send the fragment to the server,
+/// so shared keys and preimages do not end up in server logs.
+pub const SHARE_FRAGMENT_PREFIX: &str = "state=";
 
 #[component]
 pub fn ShareButton() -> impl IntoView {
-    let url = move || "Sharing is temporarily disabled".to_string();
+    let location = use_location();
+    let share_url = move |include_secrets: bool| {
+        let origin = web_sys::window()
+            .and_then(|window| window.location().origin().ok())
+            .unwrap_or_default();
+        let encoded = share::encode_state(state::shared_values(include_secrets));
+        format!(
+            "{origin}{}#{SHARE_FRAGMENT_PREFIX}{encoded}",
+            location.pathname.get_untracked()
+        )
+    };
+    let url = Signal::derive(move || share_url(false));
+    let url_with_secrets = Signal::derive(move || share_url(true));
+
     view! {
-        <CopyToClipboard content=url class="button" tooltip_below=true>
-            <i class="fa-solid fa-share-nodes"></i>
-            " Share"
-        </CopyToClipboard>
+        <div class="dropdown">
+            <button class="button dropdown-button">
+                <i class="fa-solid fa-share-nodes"></i>
+                " Share "
+                <i class="fa fa-caret-down"></i>
+            </button>
+            <div class="dropdown-content">
+                <CopyToClipboard content=url class="action-button" tooltip_below=true>
+                    "Copy link"
+                </CopyToClipboard>
+                <CopyToClipboard content=url_with_secrets class="action-button" tooltip_below=true>
+                    "Copy link with keys and preimages"
+                </CopyToClipboard>
+            </div>
+        </div>
     }
 }
@@ -0,0 +1,33 @@
+use leptos::{component, ev, use_context, view, IntoView, SignalSet, SignalWithUntracked};
+
+use crate::components::program_window::Program;
+use crate::whitespace;
+
+#[component]
+pub fn TidyButton() -> impl IntoView {
+    let program = use_context::<Program>().expect("program should exist in context");
+
+    // Programs that cannot be parsed stay as they are.
+    // The problems below the editor explain why.
+    let tidy_program = move |_event: ev::MouseEvent| {
+        let normalized = program.text.with_untracked(|text| {
+            whitespace::normalize(text)
+                .ok()
+                .filter(|normalized| normalized != text)
+        });
+        if let Some(normalized) = normalized {
+            program.text.set(normalized);
+        }
+    };
+
+    view! {
+        <button
+            class="button"
+            title="Normalize whitespace and indentation"
+            on:click=tidy_program
+        >
+            <i class="fa-solid fa-align-left"></i>
+            " Tidy"
+        </button>
+    }
+}
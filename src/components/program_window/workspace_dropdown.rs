@@ -0,0 +1,183 @@
+use leptos::{
+    component, create_node_ref, ev, html, spawn_local, use_context, view, CollectView, IntoView,
+    SignalGet, SignalGetUntracked, SignalSet, SignalWith, SignalWithUntracked,
+};
+
+use wasm_bindgen_futures::JsFuture;
+
+use crate::components::app::ActiveWorkspace;
+use crate::components::download::download_file;
+use crate::components::program_window::Program;
+use crate::components::state;
+use crate::document::Document;
+use crate::util::js_error;
+
+/// Show the error of the `result` to the user if there is one.
+fn report(result: Result<(), String>) {
+    if let (Err(error), Some(window)) = (result, web_sys::window()) {
+        let _result = window.alert_with_message(&error);
+    }
+}
+
+#[component]
+pub fn WorkspaceDropdown() -> impl IntoView {
+    let active_workspace =
+        use_context::<ActiveWorkspace>().expect("active workspace should exist in context");
+    let program = use_context::<Program>().expect("program should exist in context");
+    // Opening another workspace rebuilds the IDE from the local storage
+    let reload = move || {
+        let active = state::workspaces().active().to_string();
+        if active_workspace
+            .0
+            .with_untracked(|previous| previous != &active)
+        {
+            active_workspace.0.set(active);
+        }
+    };
+
+    let open_workspace = move |name: String| {
+        report(state::open_workspace(&name));
+        reload();
+    };
+    let create_workspace = move |_event: ev::MouseEvent| {
+        let name = web_sys::window()
+            .and_then(|window| window.prompt_with_message("Name of the new workspace").ok())
+            .flatten();
+        if let Some(name) = name {
+            report(state::create_workspace(name.trim()));
+            reload();
+        }
+    };
+    let duplicate_workspace = move |_event: ev::MouseEvent| {
+        report(state::duplicate_workspace());
+        reload();
+    };
+    let delete_workspace = move |_event: ev::MouseEvent| {
+        let message = format!(
+            "Delete the workspace `{}` with its program, keys and preimages?",
+            active_workspace.0.get()
+        );
+        let confirmed = web_sys::window()
+            .and_then(|window| window.confirm_with_message(&message).ok())
+            .unwrap_or(false);
+        if confirmed {
+            report(state::delete_workspace());
+            reload();
+        }
+    };
+
+    let export_workspace = move |include_secrets: bool| {
+        if include_secrets {
+            let message = "The exported file contains the seeds of your keys and preimages \
+                in plain text. Anyone who gets the file can spend the coins that they protect. \
+                Export anyway?";
+            let confirmed = web_sys::window()
+                .and_then(|window| window.confirm_with_message(message).ok())
+                .unwrap_or(false);
+            if !confirmed {
+                return;
+            }
+        }
+        let name = format!("{}.json", active_workspace.0.get_untracked());
+        report(download_file(
+            &name,
+            "application/json",
+            &state::export_document(include_secrets).to_json(),
+        ));
+    };
+    // Workspace documents are imported into a new workspace.
+    // Other files are added to the program of the open workspace.
+    let file_input_ref = create_node_ref::<html::Input>();
+    let import_file = move |_event: ev::Event| {
+        let input = file_input_ref.get().expect("<input> should be mounted");
+        let file = match input.files().and_then(|files| files.get(0)) {
+            Some(file) => file,
+            None => return,
+        };
+        input.set_value("");
+        spawn_local(async move {
+            let name = file.name();
+            let text = match JsFuture::from(file.text()).await {
+                Ok(text) => text.as_string().unwrap_or_default(),
+                Err(error) => return report(Err(js_error(error))),
+            };
+            match name.strip_suffix(".json") {
+                Some(stem) => {
+                    let result = Document::from_json(&text)
+                        .and_then(|document| state::import_document(stem, document));
+                    report(result);
+                    reload();
+                }
+                None => program.add_file(name, text),
+            }
+        });
+    };
+
+    let workspace_options = move || {
+        state::workspaces()
+            .names()
+            .iter()
+            .cloned()
+            .map(|name| {
+                let is_active = active_workspace.0.with(|active| active == &name);
+                let label = name.clone();
+                view! {
+                    <button
+                        class="action-button"
+                        class:selected=is_active
+                        on:click=move |_| open_workspace(name.clone())
+                    >
+                        {label}
+                    </button>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="dropdown">
+            <button class="button dropdown-button">
+                <i class="fa-solid fa-folder"></i>
+                " "{move || active_workspace.0.get()}" "
+                <i class="fa fa-caret-down"></i>
+            </button>
+            <div class="dropdown-content">
+                {workspace_options}
+                <hr class="dropdown-separator" />
+                <button class="action-button" on:click=create_workspace>
+                    "New workspace"
+                </button>
+                <button class="action-button" on:click=duplicate_workspace>
+                    "Duplicate workspace"
+                </button>
+                <button class="action-button" on:click=delete_workspace>
+                    "Delete workspace"
+                </button>
+                <hr class="dropdown-separator" />
+                <button class="action-button" on:click=move |_| export_workspace(false)>
+                    "Export workspace"
+                </button>
+                <button class="action-button" on:click=move |_| export_workspace(true)>
+                    "Export workspace with keys and preimages"
+                </button>
+                <button
+                    class="action-button"
+                    on:click=move |_| {
+                        if let Some(input) = file_input_ref.get() {
+                            input.click();
+                        }
+                    }
+                >
+                    "Import workspace or file"
+                </button>
+                <input
+                    class="hidden"
+                    type="file"
+                    accept=".json,.simf"
+                    node_ref=file_input_ref
+                    on:change=import_file
+                />
+            </div>
+        </div>
+    }
+}
@@ -1,12 +1,15 @@
 use hashes::Hash;
-use hex_conservative::DisplayHex;
+use hex_conservative::{DisplayHex, FromHex};
 use leptos::{
-    component, create_rw_signal, use_context, view, For, IntoView, RwSignal, SignalGet,
-    SignalUpdate, View,
+    component, create_memo, create_rw_signal, ev, event_target_value, use_context, view, For,
+    IntoView, RwSignal, Show, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+    View,
 };
 use simfony::elements::hashes;
 
 use crate::components::copy_to_clipboard::CopyToClipboard;
+use crate::components::string_box::{ErrorBox, NeutralBox};
+use crate::hash::{CustomPreimage, HashFunction, Sha256Trace};
 use crate::util::{Counter26, HashedData};
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -18,12 +21,29 @@ impl HashCount {
     }
 }
 
+/// Preimages that the user entered into the hash store.
+///
+/// Custom preimage `i` is referred to as `preimage::N` where `N = 26 + i`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CustomPreimages(pub RwSignal<Vec<CustomPreimage>>);
+
+impl CustomPreimages {
+    pub fn new(preimages: Vec<CustomPreimage>) -> Self {
+        Self(create_rw_signal(preimages))
+    }
+}
+
+/// Index of the first custom preimage in `preimage::N` symbols.
+const FIRST_CUSTOM_INDEX: usize = 26;
+
 #[component]
 pub fn HashStoreTab() -> impl IntoView {
     view! {
         <div class="tab-content hash-store-tab">
             <CopyHashesToClipboard />
             <CopyPreimagesToClipboard />
+            <HashCustomPreimage />
+            <StoredCustomPreimages />
         </div>
     }
 }
@@ -47,7 +67,8 @@ fn CopyHashesToClipboard() -> impl IntoView {
     view! {
         <div>
             <p class="tab-description">
-                "The secret preimages are stored in the browser's local storage. Anyone with access to these preimages can sweep your coins."
+                "The secret preimages are stored in the browser's local storage. Anyone with access to these preimages can sweep your coins. "
+                "Set a passphrase in the key store to encrypt them."
             </p>
 
             <div class="tab-title-group">
@@ -139,3 +160,235 @@ fn CopyPreimagesToClipboard() -> impl IntoView {
         </div>
     }
 }
+
+#[component]
+fn HashCustomPreimage() -> impl IntoView {
+    let custom_preimages =
+        use_context::<CustomPreimages>().expect("custom preimages should exist in context");
+    let preimage = create_rw_signal(Vec::<u8>::new());
+    let preimage_parse_error = create_rw_signal("".to_string());
+    let selected_function = create_rw_signal("sha256");
+    let tag = create_rw_signal("".to_string());
+
+    let hash_function = create_memo(move |_| match selected_function.get() {
+        "sha256d" => HashFunction::DoubleSha256,
+        "hash160" => HashFunction::Hash160,
+        "tagged" => HashFunction::TaggedSha256(tag.get()),
+        _ => HashFunction::Sha256,
+    });
+    let hash_hex = move || {
+        hash_function.with(|function| {
+            preimage.with(|preimage| format!("0x{}", function.hash(preimage).as_hex()))
+        })
+    };
+    let trace = create_memo(move |_| {
+        hash_function.with(|function| {
+            preimage.with(|preimage| {
+                let mut data = function.sha256_input_prefix();
+                data.extend_from_slice(preimage);
+                Sha256Trace::new(&data)
+            })
+        })
+    });
+    let midstates_string = move || {
+        trace.with(|trace| {
+            let mut lines = trace
+                .midstates
+                .iter()
+                .enumerate()
+                .map(|(index, midstate)| {
+                    format!(
+                        "midstate after block {index}: 0x{}",
+                        midstate.to_byte_array().as_hex()
+                    )
+                })
+                .collect::<Vec<String>>();
+            lines.push(format!(
+                "Ctx8 before finalize: buffer = 0x{}, length = {} bytes, midstate = 0x{}",
+                trace.buffer.as_hex(),
+                trace.length,
+                trace.current_midstate().to_byte_array().as_hex()
+            ));
+            lines.join("\n")
+        })
+    };
+
+    let update_preimage = move |event: ev::Event| match <Vec<u8>>::from_hex(
+        event_target_value(&event)
+            .as_str()
+            .trim()
+            .trim_start_matches("0x"),
+    ) {
+        Ok(bytes) => {
+            preimage.set(bytes);
+            preimage_parse_error.update(String::clear);
+        }
+        Err(..) => {
+            preimage_parse_error.set("Expected even number of hex digits".to_string());
+        }
+    };
+    let update_function = move |event: ev::Event| {
+        let function = match event_target_value(&event).as_str() {
+            "sha256d" => "sha256d",
+            "hash160" => "hash160",
+            "tagged" => "tagged",
+            _ => "sha256",
+        };
+        selected_function.set(function);
+    };
+    let update_tag = move |event: ev::Event| tag.set(event_target_value(&event));
+    let store_preimage = move |_event: ev::MouseEvent| {
+        let custom_preimage = CustomPreimage {
+            preimage: preimage.get_untracked(),
+            function: hash_function.get_untracked(),
+        };
+        custom_preimages
+            .0
+            .update(|preimages| preimages.push(custom_preimage));
+    };
+
+    view! {
+        <div>
+            <div class="tab-title-group">
+                <h3 class="tab-title">
+                    Custom Preimage
+                </h3>
+            </div>
+            <p class="tab-description">
+                "Hash your own preimage of any length. "
+                "The SHA-256 midstates are the values that the "
+                <code>"sha_256_ctx_8_*"</code>
+                " jets compute after each 64-byte block."
+            </p>
+            <div class="hash-store-display-row">
+                <div class="display-row-label">
+                    preimage
+                </div>
+                <input
+                    class="input"
+                    type="text"
+                    placeholder="Enter hex bytes"
+                    on:input=update_preimage
+                />
+            </div>
+            <ErrorBox error=preimage_parse_error />
+            <div class="hash-store-display-row">
+                <div class="display-row-label">
+                    hash function
+                </div>
+                <select class="input" on:change=update_function>
+                    <option value="sha256" selected>SHA-256</option>
+                    <option value="sha256d">double SHA-256</option>
+                    <option value="hash160">HASH160</option>
+                    <option value="tagged">tagged SHA-256</option>
+                </select>
+            </div>
+            <Show when=move || selected_function.get() == "tagged">
+                <div class="hash-store-display-row">
+                    <div class="display-row-label">
+                        tag
+                    </div>
+                    <input
+                        class="input"
+                        type="text"
+                        placeholder="Enter UTF-8 tag"
+                        on:input=update_tag
+                    />
+                </div>
+            </Show>
+            <div class="button-row is-small">
+                <CopyToClipboard content=hash_hex class="copy-button">
+                    {move || hash_function.with(ToString::to_string)}
+                    <i class="far fa-copy"></i>
+                </CopyToClipboard>
+                <button
+                    class="flat-button bordered"
+                    type="button"
+                    on:click=store_preimage
+                >
+                    <i class="fas fa-plus"></i>
+                    Store
+                </button>
+            </div>
+            <NeutralBox neutral=midstates_string />
+        </div>
+    }
+}
+
+#[component]
+fn StoredCustomPreimages() -> impl IntoView {
+    let custom_preimages =
+        use_context::<CustomPreimages>().expect("custom preimages should exist in context");
+    let stored_preimage = move |index: usize| -> View {
+        let entry = move || {
+            custom_preimages
+                .0
+                .with(|preimages| preimages.get(index).cloned())
+        };
+        let symbol = format!("preimage::{}", FIRST_CUSTOM_INDEX + index);
+        let description = move || {
+            entry()
+                .map(|entry| format!("{} of {} bytes", entry.function, entry.preimage.len()))
+                .unwrap_or_default()
+        };
+        let hash_hex = move || {
+            entry()
+                .map(|entry| format!("0x{}", entry.hash().as_hex()))
+                .unwrap_or_default()
+        };
+        let preimage_hex = move || {
+            entry()
+                .map(|entry| format!("0x{}", entry.preimage.as_hex()))
+                .unwrap_or_default()
+        };
+        let remove = move |_event: ev::MouseEvent| {
+            custom_preimages.0.update(|preimages| {
+                if index < preimages.len() {
+                    preimages.remove(index);
+                }
+            })
+        };
+
+        view! {
+            <div class="hash-store-display-row">
+                <div class="display-row-label">
+                    <code>{symbol}</code>
+                </div>
+                <span>{description}</span>
+                <div class="button-row is-small">
+                    <CopyToClipboard content=hash_hex class="copy-button">
+                        "Hash"
+                        <i class="far fa-copy"></i>
+                    </CopyToClipboard>
+                    <CopyToClipboard content=preimage_hex class="copy-button">
+                        "Pre"
+                        <i class="far fa-copy"></i>
+                    </CopyToClipboard>
+                    <button class="flat-button bordered" type="button" on:click=remove>
+                        <i class="fas fa-trash"></i>
+                    </button>
+                </div>
+            </div>
+        }
+    };
+
+    view! {
+        <Show when=move || custom_preimages.0.with(|preimages| !preimages.is_empty())>
+            <div class="tab-title-group">
+                <h3 class="tab-title">
+                    Stored Preimages
+                </h3>
+            </div>
+            <p class="tab-description">
+                "Use stored preimages as witness values, such as "
+                <code>"preimage::26"</code>
+                ". Removing a preimage renumbers the ones after it."
+            </p>
+            <For
+                each=move || 0..custom_preimages.0.with(Vec::len)
+                key=|index| *index
+                children=stored_preimage
+            />
+        </Show>
+    }
+}
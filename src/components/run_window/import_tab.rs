@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use leptos::{
+    component, create_rw_signal, ev, event_target_value, use_context, view, IntoView, RwSignal,
+    Signal, SignalGetUntracked, SignalSet, SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use simfony::simplicity;
+
+use crate::components::program_window::Runtime;
+use crate::components::run_window::TxEnv;
+use crate::components::string_box::{ErrorBox, SuccessBox};
+use crate::raw::{self, Encoding, Network, Spend};
+use crate::util::{self, Expression};
+
+/// Tab to run a Simplicity program that is given as raw bytes instead of Simfony source code.
+#[component]
+pub fn ImportTab() -> impl IntoView {
+    view! {
+        <div class="tab-content import-tab">
+            <ProgramImport />
+            <SpendImport />
+        </div>
+    }
+}
+
+/// Selection of the encoding in which bytes are pasted.
+#[component]
+fn EncodingSelect(encoding: RwSignal<Encoding>, on_change: impl Fn() + 'static) -> impl IntoView {
+    let select_encoding = move |event: ev::Event| {
+        encoding.set(match event_target_value(&event).as_str() {
+            "base64" => Encoding::Base64,
+            _ => Encoding::Hex,
+        });
+        on_change();
+    };
+
+    view! {
+        <div class="import-display-row">
+            <span class="display-row-label">Encoding</span>
+            <select class="input" on:change=select_encoding>
+                <option value="hex" selected>hex</option>
+                <option value="base64">base64</option>
+            </select>
+        </div>
+    }
+}
+
+/// Import of a Simplicity program and its witness data,
+/// which runs on the transaction of the Transaction tab.
+#[component]
+fn ProgramImport() -> impl IntoView {
+    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
+    let tx_env = use_context::<TxEnv>().expect("transaction environment should exist in context");
+    let program_text = create_rw_signal(String::new());
+    let witness_text = create_rw_signal(String::new());
+    let encoding = create_rw_signal(Encoding::default());
+    let decoded = create_rw_signal(None::<Result<Arc<Expression>, String>>);
+
+    let decode = move || {
+        let program = program_text.get_untracked();
+        let witness = witness_text.get_untracked();
+        match program.trim().is_empty() {
+            true => decoded.set(None),
+            false => decoded.set(Some(raw::decode_program(
+                &program,
+                &witness,
+                encoding.get_untracked(),
+            ))),
+        }
+    };
+    let update_program = move |event: ev::Event| {
+        program_text.set(event_target_value(&event));
+        decode();
+    };
+    let update_witness = move |event: ev::Event| {
+        witness_text.set(event_target_value(&event));
+        decode();
+    };
+    let cmr = move || {
+        decoded.with(|decoded| match decoded {
+            Some(Ok(program)) => Some(program.cmr()),
+            _ => None,
+        })
+    };
+    let decode_error = move || {
+        decoded.with(|decoded| match decoded {
+            Some(Err(error)) => error.clone(),
+            _ => String::new(),
+        })
+    };
+    let run = move |_event: ev::MouseEvent| {
+        let program = decoded.with_untracked(|decoded| match decoded {
+            Some(Ok(program)) => Some(Arc::clone(program)),
+            _ => None,
+        });
+        if let Some(program) = program {
+            // The spent UTXO commits to the CMR of the imported program
+            let env = tx_env
+                .params
+                .with_untracked(|params| params.tx_env(program.cmr()));
+            runtime.run_redeem(program, &env);
+        }
+    };
+
+    view! {
+        <h3 class="tab-title">Program</h3>
+        <p class="tab-description">
+            "Paste a Simplicity program and its witness data as hex or base64, "
+            "such as the first two witness elements of a spend. "
+            "The program runs on the transaction of the Transaction tab."
+        </p>
+        <div>
+            <EncodingSelect encoding on_change=decode />
+            <textarea
+                class="input"
+                rows="4"
+                spellcheck="false"
+                placeholder="Encoded program"
+                on:input=update_program
+            />
+            <textarea
+                class="input"
+                rows="4"
+                spellcheck="false"
+                placeholder="Encoded witness data (empty if the program has no witness nodes)"
+                on:input=update_witness
+            />
+            <ErrorBox error=Signal::derive(decode_error) />
+            {move || cmr().map(|cmr: simplicity::Cmr| view! {
+                <div class="import-display-row">
+                    <span class="display-row-label">CMR</span>
+                    <code>{cmr.to_string()}</code>
+                </div>
+                <div class="import-display-row">
+                    <span class="display-row-label">Address</span>
+                    <code>{util::liquid_testnet_address(cmr).to_string()}</code>
+                </div>
+            })}
+            <button class="button import-run-button" disabled=move || cmr().is_none() on:click=run>
+                Run
+            </button>
+        </div>
+    }
+}
+
+/// Import of a spending transaction and the outputs that it spends.
+///
+/// The Simplicity program of the chosen input runs on the real transaction.
+#[component]
+fn SpendImport() -> impl IntoView {
+    let runtime = use_context::<Runtime>().expect("runtime should exist in context");
+    let tx_text = create_rw_signal(String::new());
+    let utxos_text = create_rw_signal(String::new());
+    let input_index = create_rw_signal(0u32);
+    let encoding = create_rw_signal(Encoding::default());
+    let network = create_rw_signal(Network::default());
+    let index_parse_error = create_rw_signal(String::new());
+    let spend = create_rw_signal(None::<Result<Spend, String>>);
+
+    let decode = move || {
+        let tx = tx_text.get_untracked();
+        match tx.trim().is_empty() {
+            true => spend.set(None),
+            false => {
+                let decoded = utxos_text.with_untracked(|utxos| {
+                    Spend::decode(
+                        &tx,
+                        utxos,
+                        input_index.get_untracked(),
+                        encoding.get_untracked(),
+                        network.get_untracked(),
+                    )
+                });
+                spend.set(Some(decoded));
+            }
+        }
+    };
+    let update_tx = move |event: ev::Event| {
+        tx_text.set(event_target_value(&event));
+        decode();
+    };
+    let update_utxos = move |event: ev::Event| {
+        utxos_text.set(event_target_value(&event));
+        decode();
+    };
+    let update_index = move |event: ev::Event| match event_target_value(&event).parse::<u32>() {
+        Ok(index) => {
+            input_index.set(index);
+            index_parse_error.update(String::clear);
+            decode();
+        }
+        Err(error) => index_parse_error.set(error.to_string()),
+    };
+    let select_network = move |event: ev::Event| {
+        network.set(match event_target_value(&event).as_str() {
+            "liquid" => Network::Liquid,
+            _ => Network::LiquidTestnet,
+        });
+        decode();
+    };
+    let cmr = move || {
+        spend.with(|spend| match spend {
+            Some(Ok(spend)) => Some(spend.cmr),
+            _ => None,
+        })
+    };
+    let decode_error = move || {
+        spend.with(|spend| match spend {
+            Some(Err(error)) => error.clone(),
+            _ => String::new(),
+        })
+    };
+    let verification = move || {
+        spend.with(|spend| match spend {
+            Some(Ok(spend)) => Some(spend.verify_control_block()),
+            _ => None,
+        })
+    };
+    let verified = move || match verification() {
+        Some(Ok(())) => {
+            "Control block commits to the script pubkey of the spent output".to_string()
+        }
+        _ => String::new(),
+    };
+    let verification_error = move || match verification() {
+        Some(Err(error)) => error,
+        _ => String::new(),
+    };
+    let run = move |_event: ev::MouseEvent| {
+        spend.with_untracked(|spend| {
+            if let Some(Ok(spend)) = spend {
+                runtime.run_redeem(Arc::clone(&spend.program), &spend.env);
+            }
+        });
+    };
+
+    view! {
+        <h3 class="tab-title">Spending transaction</h3>
+        <p class="tab-description">
+            "Paste a transaction that spends a Simplicity output, as hex or base64. "
+            "Below, paste the outputs that the transaction spends, one per line in the order of the inputs, "
+            "either as serialized outputs or as the transactions that contain them. "
+            "The program of the chosen input runs on the real transaction "
+            "of the chosen network."
+        </p>
+        <div>
+            <EncodingSelect encoding on_change=decode />
+            <div class="import-display-row">
+                <span class="display-row-label">Network</span>
+                <select class="input" on:change=select_network>
+                    <option value="liquid_testnet" selected>Liquid testnet</option>
+                    <option value="liquid">Liquid</option>
+                </select>
+            </div>
+            <textarea
+                class="input"
+                rows="4"
+                spellcheck="false"
+                placeholder="Encoded transaction"
+                on:input=update_tx
+            />
+            <textarea
+                class="input"
+                rows="4"
+                spellcheck="false"
+                placeholder="Encoded spent outputs, one per line"
+                on:input=update_utxos
+            />
+            <div class="import-display-row">
+                <span class="display-row-label">Input</span>
+                <input class="input" type="number" min=0 value=0 on:input=update_index />
+            </div>
+            <ErrorBox error=index_parse_error />
+            <ErrorBox error=Signal::derive(decode_error) />
+            {move || cmr().map(|cmr: simplicity::Cmr| view! {
+                <div class="import-display-row">
+                    <span class="display-row-label">CMR</span>
+                    <code>{cmr.to_string()}</code>
+                </div>
+            })}
+            <SuccessBox success=Signal::derive(verified) />
+            <ErrorBox error=Signal::derive(verification_error) />
+            <button class="button import-run-button" disabled=move || cmr().is_none() on:click=run>
+                Run
+            </button>
+        </div>
+    }
+}
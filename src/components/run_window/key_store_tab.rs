@@ -1,3 +1,4 @@
+use std::fmt;
 use std::sync::Arc;
 
 use elements::hashes::{sha256, Hash};
@@ -5,13 +6,20 @@ use elements::secp256k1_zkp as secp256k1;
 use hex_conservative::{DisplayHex, FromHex};
 use leptos::{
     component, create_memo, create_rw_signal, ev, event_target_value, html, use_context, view, For,
-    IntoView, NodeRef, RwSignal, Signal, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate,
-    SignalWith, View,
+    IntoView, Memo, NodeRef, RwSignal, Signal, SignalGet, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith, View,
 };
 use simfony::{elements, simplicity};
 
+use crate::adaptor::AdaptorSignature;
 use crate::components::copy_to_clipboard::CopyToClipboard;
-use crate::util::{Counter26, SigningKeys};
+use crate::components::program_window::Program;
+use crate::components::run_window::CustomPreimages;
+use crate::components::storage_encryption::StorageEncryption;
+use crate::components::string_box::{ErrorBox, SuccessBox};
+use crate::function::U256Function;
+use crate::util::{key_name, Counter26, SigningKeys};
+use crate::witness;
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct KeyCount(pub RwSignal<Counter26>);
@@ -22,11 +30,56 @@ impl KeyCount {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub enum SignedDataMode {
+    #[default]
     SighashAll,
     ThirtyTwoBytes,
     HashPreimageBytes,
+    SimfonyFunction,
+}
+
+impl SignedDataMode {
+    pub const ALL: [Self; 4] = [
+        Self::SighashAll,
+        Self::ThirtyTwoBytes,
+        Self::HashPreimageBytes,
+        Self::SimfonyFunction,
+    ];
+
+    /// Return the name of the mode in the local storage.
+    pub const fn key(self) -> &'static str {
+        match self {
+            SignedDataMode::SighashAll => "sighash_all",
+            SignedDataMode::ThirtyTwoBytes => "thirty_two_bytes",
+            SignedDataMode::HashPreimageBytes => "hash_preimage_bytes",
+            SignedDataMode::SimfonyFunction => "simfony_function",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.key() == key)
+    }
+}
+
+impl fmt::Display for SignedDataMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignedDataMode::SighashAll => f.write_str("SIGHASH_ALL"),
+            SignedDataMode::ThirtyTwoBytes => f.write_str("raw bytes"),
+            SignedDataMode::HashPreimageBytes => f.write_str("byte hash"),
+            SignedDataMode::SimfonyFunction => f.write_str("Simfony function"),
+        }
+    }
+}
+
+/// Inputs of the signed data that are kept in the local storage.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SignedDataParams {
+    pub mode: SignedDataMode,
+    pub thirty_two_bytes: [u8; 32],
+    pub hash_preimage_bytes: Vec<u8>,
+    pub simfony_function_name: String,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -35,69 +88,104 @@ pub struct SignedData {
     pub thirty_two_bytes: RwSignal<[u8; 32]>,
     pub sighash_all: Signal<secp256k1::Message>,
     pub hash_preimage_bytes: RwSignal<Vec<u8>>,
+    pub simfony_function_name: RwSignal<String>,
+    /// Output of the Simfony function, or an error message if the function cannot be evaluated.
+    pub simfony_function_output: Memo<Result<[u8; 32], String>>,
     pub message: Signal<secp256k1::Message>,
 }
 
 impl SignedData {
     pub fn new(
+        program: Program,
         tx_env: Signal<simplicity::jet::elements::ElementsEnv<Arc<elements::Transaction>>>,
+        custom_preimages: CustomPreimages,
+        params: SignedDataParams,
     ) -> Self {
-        let mode = create_rw_signal(SignedDataMode::SighashAll);
+        let mode = create_rw_signal(params.mode);
         let sighash_all = Signal::derive(move || {
             tx_env.with(|tx_env| {
                 secp256k1::Message::from_digest(tx_env.c_tx_env().sighash_all().to_byte_array())
             })
         });
-        let thirty_two_bytes = create_rw_signal([0; 32]);
-        let hash_preimage_bytes = create_rw_signal(vec![]);
-        let message = Signal::derive(move || match mode.get() {
-            SignedDataMode::SighashAll => sighash_all.get(),
-            SignedDataMode::ThirtyTwoBytes => {
-                secp256k1::Message::from_digest(thirty_two_bytes.get())
-            }
-            SignedDataMode::HashPreimageBytes => hash_preimage_bytes.with(|bytes| {
-                secp256k1::Message::from_digest(sha256::Hash::hash(bytes).to_byte_array())
-            }),
+        let thirty_two_bytes = create_rw_signal(params.thirty_two_bytes);
+        let hash_preimage_bytes = create_rw_signal(params.hash_preimage_bytes);
+        let simfony_function_name = create_rw_signal(params.simfony_function_name);
+        // The function is compiled when the program changes and evaluated when the transaction changes
+        let simfony_function = create_memo(move |_| {
+            simfony_function_name.with(|name| {
+                // Signatures cannot depend on themselves, so symbols are replaced by placeholders
+                let text = custom_preimages.0.with(|preimages| {
+                    witness::resolve(&program.combined().text, |symbol| {
+                        Ok(symbol.placeholder(preimages))
+                    })
+                })?;
+                U256Function::compile(&text, name)
+            })
+        });
+        let simfony_function_output = create_memo(move |_| {
+            simfony_function.with(|function| match function {
+                Ok(function) => tx_env.with(|tx_env| function.evaluate(tx_env)),
+                Err(error) => Err(error.clone()),
+            })
+        });
+        let message = Signal::derive(move || {
+            message_for(
+                mode.get(),
+                sighash_all,
+                thirty_two_bytes,
+                hash_preimage_bytes,
+                simfony_function_output,
+            )
         });
         Self {
             mode,
             thirty_two_bytes,
             sighash_all,
             hash_preimage_bytes,
+            simfony_function_name,
+            simfony_function_output,
             message,
         }
     }
+
+    /// Return the current inputs of the signed data.
+    pub fn params(self) -> SignedDataParams {
+        SignedDataParams {
+            mode: self.mode.get_untracked(),
+            thirty_two_bytes: self.thirty_two_bytes.get_untracked(),
+            hash_preimage_bytes: self.hash_preimage_bytes.get_untracked(),
+            simfony_function_name: self.simfony_function_name.get_untracked(),
+        }
+    }
+
+    /// Compute the message that would be signed in the given `mode`.
+    pub fn message_for(self, mode: SignedDataMode) -> secp256k1::Message {
+        message_for(
+            mode,
+            self.sighash_all,
+            self.thirty_two_bytes,
+            self.hash_preimage_bytes,
+            self.simfony_function_output,
+        )
+    }
 }
 
-fn key_name(index: usize) -> &'static str {
-    match index {
-        0 => "Alice",
-        1 => "Bob",
-        2 => "Charlie",
-        3 => "David",
-        4 => "Eve",
-        5 => "Frank",
-        6 => "Grace",
-        7 => "Heidi",
-        8 => "Ivan",
-        9 => "Judy",
-        10 => "Kevin",
-        11 => "Luther",
-        12 => "Mallory",
-        13 => "Niaj",
-        14 => "Olivia",
-        15 => "Peggy",
-        16 => "Quentin",
-        17 => "Rupert",
-        18 => "Sybil",
-        19 => "Trent",
-        20 => "Ursula",
-        21 => "Victor",
-        22 => "Wendy",
-        23 => "Xavier",
-        24 => "Yvonne",
-        25 => "Zoe",
-        _ => "Unnamed",
+fn message_for(
+    mode: SignedDataMode,
+    sighash_all: Signal<secp256k1::Message>,
+    thirty_two_bytes: RwSignal<[u8; 32]>,
+    hash_preimage_bytes: RwSignal<Vec<u8>>,
+    simfony_function_output: Memo<Result<[u8; 32], String>>,
+) -> secp256k1::Message {
+    match mode {
+        SignedDataMode::SighashAll => sighash_all.get(),
+        SignedDataMode::ThirtyTwoBytes => secp256k1::Message::from_digest(thirty_two_bytes.get()),
+        SignedDataMode::HashPreimageBytes => hash_preimage_bytes.with(|bytes| {
+            secp256k1::Message::from_digest(sha256::Hash::hash(bytes).to_byte_array())
+        }),
+        // Sign the zero message until the function can be evaluated
+        SignedDataMode::SimfonyFunction => simfony_function_output
+            .with(|output| secp256k1::Message::from_digest(*output.as_ref().unwrap_or(&[0; 32]))),
     }
 }
 
@@ -108,6 +196,9 @@ pub fn KeyStoreTab() -> impl IntoView {
             <CopyPublicKeysToClipboard />
             <CopySignaturesToClipboard />
             <SelectSignedData />
+            <VerifySignature />
+            <AdaptorSignatures />
+            <StorageEncryption />
         </div>
     }
 }
@@ -132,7 +223,8 @@ fn CopyPublicKeysToClipboard() -> impl IntoView {
     view! {
         <div>
             <p class="tab-description">
-                "The secret master key is stored in the browser's local storage. Anyone with access to this key can sweep your coins."
+                "The secret master key is stored in the browser's local storage. Anyone with access to this key can sweep your coins. "
+                "Set a passphrase below to encrypt it."
             </p>
 
             <div class="tab-title-group">
@@ -176,9 +268,8 @@ fn CopySignaturesToClipboard() -> impl IntoView {
     let signed_data = use_context::<SignedData>().expect("signed data should exist in context");
     let key_count = use_context::<KeyCount>().expect("key count should exist in context");
     let signatures = create_memo(move |_| -> [secp256k1::schnorr::Signature; 26] {
-        std::array::from_fn(|index| {
-            signing_keys.secret_keys[index].sign_schnorr(signed_data.message.get())
-        })
+        let message = signed_data.message.get();
+        std::array::from_fn(|index| signing_keys.sign(index, message))
     });
 
     let copy_single_signature =
@@ -248,6 +339,10 @@ fn SelectSignedData() -> impl IntoView {
         signed_data.mode.get_untracked(),
         SignedDataMode::HashPreimageBytes
     );
+    let simfony_function_initial_checked = matches!(
+        signed_data.mode.get_untracked(),
+        SignedDataMode::SimfonyFunction
+    );
     let thirty_two_bytes_initial_value = format!(
         "0x{}",
         signed_data.thirty_two_bytes.get_untracked().as_hex()
@@ -256,6 +351,7 @@ fn SelectSignedData() -> impl IntoView {
         "0x{}",
         signed_data.hash_preimage_bytes.get_untracked().as_hex()
     );
+    let simfony_function_initial_value = signed_data.simfony_function_name.get_untracked();
 
     let sighash_all_radio_ref = NodeRef::<html::Input>::new();
     let thirty_two_bytes_text_ref = NodeRef::<html::Input>::new();
@@ -270,6 +366,20 @@ fn SelectSignedData() -> impl IntoView {
     let select_hash_preimage_bytes = move |_event: ev::Event| {
         signed_data.mode.set(SignedDataMode::HashPreimageBytes);
     };
+    let select_simfony_function = move |_event: ev::Event| {
+        signed_data.mode.set(SignedDataMode::SimfonyFunction);
+    };
+    let update_simfony_function_name = move |event: ev::Event| {
+        signed_data
+            .simfony_function_name
+            .set(event_target_value(&event).trim().to_string());
+    };
+    let simfony_function_error = move || match signed_data.mode.get() {
+        SignedDataMode::SimfonyFunction => signed_data
+            .simfony_function_output
+            .with(|output| output.as_ref().err().cloned().unwrap_or_default()),
+        _ => "".to_string(),
+    };
     let update_thirty_two_bytes = move |event: ev::Event| match <[u8; 32]>::from_hex(
         event_target_value(&event)
             .as_str()
@@ -381,7 +491,453 @@ fn SelectSignedData() -> impl IntoView {
                         node_ref=hash_preimage_bytes_text_ref
                     />
                 </label>
+                <label class="key-store-display-row">
+                    <input
+                        type="radio"
+                        name="signed_data"
+                        checked=simfony_function_initial_checked
+                        on:change=select_simfony_function
+                    />
+                    <div class="display-row-label">
+                        Simfony function
+                    </div>
+                    <input
+                        class="input"
+                        type="text"
+                        placeholder="Enter name of function that returns u256"
+                        on:input=update_simfony_function_name
+                        value=simfony_function_initial_value
+                    />
+                </label>
+                <ErrorBox error=Signal::derive(simfony_function_error) />
             </fieldset>
         </div>
     }
 }
+
+/// Result of verifying a signature against the signed data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Verification {
+    /// The signature is valid for the selected signed data.
+    Valid,
+    /// The signature is invalid for the selected signed data.
+    ///
+    /// The signature would be valid for the signed data of other modes,
+    /// or for the public keys of other key store entries.
+    Invalid {
+        other_modes: Vec<SignedDataMode>,
+        other_keys: Vec<usize>,
+    },
+}
+
+/// Verify the `signature` for the given `public_key` against the selected signed data.
+///
+/// If verification fails, check which other signed data or which other public key
+/// would have made the signature valid.
+fn verify_signature(
+    signature: &secp256k1::schnorr::Signature,
+    public_key: &secp256k1::XOnlyPublicKey,
+    signed_data: SignedData,
+    signing_keys: &SigningKeys,
+    key_count: usize,
+) -> Verification {
+    let secp = secp256k1::SECP256K1;
+    let selected_mode = signed_data.mode.get();
+    let selected_message = signed_data.message_for(selected_mode);
+    if secp
+        .verify_schnorr(signature, &selected_message, public_key)
+        .is_ok()
+    {
+        return Verification::Valid;
+    }
+
+    let other_modes = SignedDataMode::ALL
+        .into_iter()
+        .filter(|mode| *mode != selected_mode)
+        .filter(|mode| {
+            secp.verify_schnorr(signature, &signed_data.message_for(*mode), public_key)
+                .is_ok()
+        })
+        .collect();
+    let other_keys = (0..key_count)
+        .filter(|index| signing_keys.public_keys[*index] != *public_key)
+        .filter(|index| {
+            secp.verify_schnorr(
+                signature,
+                &selected_message,
+                &signing_keys.public_keys[*index],
+            )
+            .is_ok()
+        })
+        .collect();
+
+    Verification::Invalid {
+        other_modes,
+        other_keys,
+    }
+}
+
+#[component]
+fn VerifySignature() -> impl IntoView {
+    let signing_keys = use_context::<SigningKeys>().expect("signing keys should exist in context");
+    let signed_data = use_context::<SignedData>().expect("signed data should exist in context");
+    let key_count = use_context::<KeyCount>().expect("key count should exist in context");
+    let public_key = create_rw_signal(Option::<secp256k1::XOnlyPublicKey>::None);
+    let signature = create_rw_signal(Option::<secp256k1::schnorr::Signature>::None);
+    let public_key_parse_error = create_rw_signal("".to_string());
+    let signature_parse_error = create_rw_signal("".to_string());
+
+    let verification = create_memo(move |_| {
+        let public_key = public_key.get()?;
+        let signature = signature.get()?;
+        Some(verify_signature(
+            &signature,
+            &public_key,
+            signed_data,
+            &signing_keys,
+            key_count.0.get().get(),
+        ))
+    });
+    let success_string = move || match verification.get() {
+        Some(Verification::Valid) => format!(
+            "Signature is valid for the selected signed data ({}).",
+            signed_data.mode.get()
+        ),
+        _ => "".to_string(),
+    };
+    let failure_string = move || match verification.get() {
+        Some(Verification::Invalid {
+            other_modes,
+            other_keys,
+        }) => {
+            let mut lines = vec![format!(
+                "Signature is invalid for the selected signed data ({}).",
+                signed_data.mode.get()
+            )];
+            for mode in other_modes {
+                lines.push(format!(
+                    "The signature would be valid for {mode}. Did you sign the wrong message?"
+                ));
+            }
+            for index in other_keys {
+                lines.push(format!(
+                    "The signature would be valid for the public key of {}. Did you use the wrong key?",
+                    key_name(index)
+                ));
+            }
+            if lines.len() == 1 {
+                lines.push(
+                    "The signature does not match any other signed data or key store key either."
+                        .to_string(),
+                );
+            }
+            lines.join("\n")
+        }
+        _ => "".to_string(),
+    };
+
+    let update_public_key = move |event: ev::Event| {
+        let s = event_target_value(&event);
+        match <[u8; 32]>::from_hex(s.as_str().trim().trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| secp256k1::XOnlyPublicKey::from_slice(&bytes).ok())
+        {
+            Some(key) => {
+                public_key.set(Some(key));
+                public_key_parse_error.update(String::clear);
+            }
+            None if s.is_empty() => {
+                public_key.set(None);
+                public_key_parse_error.update(String::clear);
+            }
+            None => {
+                public_key.set(None);
+                public_key_parse_error
+                    .set("Expected x-only public key (64 hex digits)".to_string());
+            }
+        }
+    };
+    let update_signature = move |event: ev::Event| {
+        let s = event_target_value(&event);
+        match <[u8; 64]>::from_hex(s.as_str().trim().trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| secp256k1::schnorr::Signature::from_slice(&bytes).ok())
+        {
+            Some(sig) => {
+                signature.set(Some(sig));
+                signature_parse_error.update(String::clear);
+            }
+            None if s.is_empty() => {
+                signature.set(None);
+                signature_parse_error.update(String::clear);
+            }
+            None => {
+                signature.set(None);
+                signature_parse_error.set("Expected signature (128 hex digits)".to_string());
+            }
+        }
+    };
+
+    view! {
+        <div>
+            <div class="tab-title-group">
+                <h3 class="tab-title">
+                    Verify Signature
+                </h3>
+            </div>
+            <p class="tab-description">
+                "Check a BIP-340 signature against the selected signed data."
+            </p>
+            <div class="key-store-display-row">
+                <div class="display-row-label">
+                    public key
+                </div>
+                <input
+                    class="input"
+                    type="text"
+                    placeholder="Enter x-only public key"
+                    on:input=update_public_key
+                />
+            </div>
+            <ErrorBox error=public_key_parse_error />
+            <div class="key-store-display-row">
+                <div class="display-row-label">
+                    signature
+                </div>
+                <input
+                    class="input"
+                    type="text"
+                    placeholder="Enter signature"
+                    on:input=update_signature
+                />
+            </div>
+            <ErrorBox error=signature_parse_error />
+            <SuccessBox success=success_string />
+            <ErrorBox error=failure_string />
+        </div>
+    }
+}
+
+#[component]
+fn AdaptorSignatures() -> impl IntoView {
+    let signing_keys = use_context::<SigningKeys>().expect("signing keys should exist in context");
+    let signed_data = use_context::<SignedData>().expect("signed data should exist in context");
+    let key_count = use_context::<KeyCount>().expect("key count should exist in context");
+    let adaptor_point = create_rw_signal(Option::<secp256k1::PublicKey>::None);
+    let typed_secret = create_rw_signal(Option::<secp256k1::SecretKey>::None);
+    let adaptor_point_parse_error = create_rw_signal("".to_string());
+    let adaptor_secret_parse_error = create_rw_signal("".to_string());
+    let extracted_secret = create_rw_signal("".to_string());
+    let extraction_error = create_rw_signal("".to_string());
+
+    // The secret is checked against the adaptor point whenever either of them changes
+    let adaptor_secret = create_memo(move |_| {
+        let secret = typed_secret.get()?;
+        let point = secp256k1::PublicKey::from_secret_key(secp256k1::SECP256K1, &secret);
+        match adaptor_point.get() {
+            Some(expected_point) if expected_point != point => None,
+            _ => Some(secret),
+        }
+    });
+    let adaptor_secret_mismatch = move || match (typed_secret.get(), adaptor_secret.get()) {
+        (Some(..), None) => "Secret does not match the adaptor point".to_string(),
+        _ => String::new(),
+    };
+
+    let pre_signatures = create_memo(move |_| -> Option<[AdaptorSignature; 26]> {
+        let adaptor_point = adaptor_point.get()?;
+        let message = signed_data.message.get();
+        Some(std::array::from_fn(|index| {
+            AdaptorSignature::sign(&signing_keys.secret_keys[index], &adaptor_point, &message)
+        }))
+    });
+    let completed_signatures =
+        create_memo(move |_| -> Option<[secp256k1::schnorr::Signature; 26]> {
+            let pre_signatures = pre_signatures.get()?;
+            let adaptor_secret = adaptor_secret.get()?;
+            let completed =
+                pre_signatures.map(|pre_signature| pre_signature.complete(&adaptor_secret));
+            match completed.iter().all(Option::is_some) {
+                true => Some(completed.map(|signature| signature.unwrap())),
+                false => None,
+            }
+        });
+
+    let update_adaptor_point = move |event: ev::Event| {
+        let s = event_target_value(&event);
+        match <[u8; 33]>::from_hex(s.as_str().trim().trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| secp256k1::PublicKey::from_slice(&bytes).ok())
+        {
+            Some(point) => {
+                adaptor_point.set(Some(point));
+                adaptor_point_parse_error.update(String::clear);
+            }
+            None if s.is_empty() => {
+                adaptor_point.set(None);
+                adaptor_point_parse_error.update(String::clear);
+            }
+            None => {
+                adaptor_point.set(None);
+                adaptor_point_parse_error
+                    .set("Expected compressed public key (66 hex digits)".to_string());
+            }
+        }
+    };
+    let update_adaptor_secret = move |event: ev::Event| {
+        let s = event_target_value(&event);
+        match <[u8; 32]>::from_hex(s.as_str().trim().trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| secp256k1::SecretKey::from_slice(&bytes).ok())
+        {
+            Some(secret) => {
+                typed_secret.set(Some(secret));
+                adaptor_secret_parse_error.update(String::clear);
+            }
+            None if s.is_empty() => {
+                typed_secret.set(None);
+                adaptor_secret_parse_error.update(String::clear);
+            }
+            None => {
+                typed_secret.set(None);
+                adaptor_secret_parse_error.set("Expected secret key (64 hex digits)".to_string());
+            }
+        }
+    };
+    let update_completed_signature = move |event: ev::Event| {
+        extracted_secret.update(String::clear);
+        extraction_error.update(String::clear);
+        let s = event_target_value(&event);
+        if s.is_empty() {
+            return;
+        }
+        let signature = match <[u8; 64]>::from_hex(s.as_str().trim().trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| secp256k1::schnorr::Signature::from_slice(&bytes).ok())
+        {
+            Some(signature) => signature,
+            None => {
+                extraction_error.set("Expected signature (128 hex digits)".to_string());
+                return;
+            }
+        };
+        let (point, pre_signatures) = match (
+            adaptor_point.get_untracked(),
+            pre_signatures.get_untracked(),
+        ) {
+            (Some(point), Some(pre_signatures)) => (point, pre_signatures),
+            _ => {
+                extraction_error.set("Enter an adaptor point first".to_string());
+                return;
+            }
+        };
+        let found = (0..key_count.0.get_untracked().get()).find_map(|index| {
+            pre_signatures[index]
+                .extract_secret(&signature, &point)
+                .map(|secret| (index, secret))
+        });
+        match found {
+            Some((index, secret)) => extracted_secret.set(format!(
+                "Adaptor secret (completed by {}): 0x{}",
+                key_name(index),
+                secret.secret_bytes().as_hex()
+            )),
+            None => extraction_error
+                .set("Signature does not complete any of the pre-signatures".to_string()),
+        }
+    };
+
+    let copy_single_pre_signature =
+        move |(index, pre_signature): (usize, AdaptorSignature)| -> View {
+            let label = key_name(index);
+            let pre_signature_hex = move || format!("0x{}", pre_signature.serialize().as_hex());
+
+            view! {
+                <CopyToClipboard content=pre_signature_hex class="copy-button">
+                    {label}
+                    <i class="far fa-copy"></i>
+                </CopyToClipboard>
+            }
+        };
+    let copy_single_completed_signature =
+        move |(index, signature): (usize, secp256k1::schnorr::Signature)| -> View {
+            let label = key_name(index);
+            let signature_hex = move || format!("0x{}", signature.serialize().as_hex());
+
+            view! {
+                <CopyToClipboard content=signature_hex class="copy-button">
+                    {label}
+                    <i class="far fa-copy"></i>
+                </CopyToClipboard>
+            }
+        };
+
+    view! {
+        <div>
+            <div class="tab-title-group">
+                <h3 class="tab-title">
+                    Adaptor Signatures
+                </h3>
+            </div>
+            <p class="tab-description">
+                "Pre-sign the signed data under an adaptor point. "
+                "Each pre-signature becomes a valid signature once it is completed with the adaptor secret. "
+                "Whoever sees both the pre-signature and the completed signature learns the adaptor secret."
+            </p>
+            <div class="key-store-display-row">
+                <div class="display-row-label">
+                    adaptor point
+                </div>
+                <input
+                    class="input"
+                    type="text"
+                    placeholder="Enter compressed public key"
+                    on:input=update_adaptor_point
+                />
+            </div>
+            <ErrorBox error=adaptor_point_parse_error />
+            <div class="button-row is-small">
+                <For
+                    each=move || (0..key_count.0.get().get()).zip(pre_signatures.get().into_iter().flatten())
+                    key=|(_index, pre_signature)| pre_signature.serialize()
+                    children=copy_single_pre_signature
+                />
+            </div>
+
+            <div class="key-store-display-row">
+                <div class="display-row-label">
+                    adaptor secret
+                </div>
+                <input
+                    class="input"
+                    type="text"
+                    placeholder="Enter secret key"
+                    on:input=update_adaptor_secret
+                />
+            </div>
+            <ErrorBox error=adaptor_secret_parse_error />
+            <ErrorBox error=Signal::derive(adaptor_secret_mismatch) />
+            <div class="button-row is-small">
+                <For
+                    each=move || (0..key_count.0.get().get()).zip(completed_signatures.get().into_iter().flatten())
+                    key=|(_index, signature)| *signature
+                    children=copy_single_completed_signature
+                />
+            </div>
+
+            <div class="key-store-display-row">
+                <div class="display-row-label">
+                    completed signature
+                </div>
+                <input
+                    class="input"
+                    type="text"
+                    placeholder="Enter signature to extract the adaptor secret"
+                    on:input=update_completed_signature
+                />
+            </div>
+            <SuccessBox success=extracted_secret />
+            <ErrorBox error=extraction_error />
+        </div>
+    }
+}
@@ -1,19 +1,23 @@
 mod execution_tab;
 mod hash_store_tab;
+mod import_tab;
 mod key_store_tab;
 mod transaction_tab;
+mod witness_tab;
 
 use crate::components::app::ActiveRunTab;
 use leptos::{component, use_context, view, IntoView};
 
 use self::execution_tab::ExecutionTab;
 use self::hash_store_tab::HashStoreTab;
+use self::import_tab::ImportTab;
 use self::key_store_tab::KeyStoreTab;
 use self::transaction_tab::TransactionTab;
+use self::witness_tab::WitnessTab;
 use crate::components::navbar::{Navbar, Tab};
 
-pub use self::hash_store_tab::HashCount;
-pub use self::key_store_tab::{KeyCount, SignedData};
+pub use self::hash_store_tab::{CustomPreimages, HashCount};
+pub use self::key_store_tab::{KeyCount, SignedData, SignedDataMode, SignedDataParams};
 pub use self::transaction_tab::TxEnv;
 
 #[component]
@@ -28,12 +32,18 @@ pub fn RunWindow() -> impl IntoView {
             <Tab name="Transaction">
                 <TransactionTab />
             </Tab>
+            <Tab name="Witness">
+                <WitnessTab />
+            </Tab>
             <Tab name="Key Store">
                 <KeyStoreTab />
             </Tab>
             <Tab name="Hash Store">
                 <HashStoreTab />
             </Tab>
+            <Tab name="Import">
+                <ImportTab />
+            </Tab>
         </Navbar>
     }
 }
@@ -0,0 +1,421 @@
+use leptos::{
+    component, create_effect, create_memo, create_rw_signal, ev, event_target_checked,
+    event_target_value, store_value, use_context, view, CollectView, IntoView, RwSignal, SignalGet,
+    SignalSet, SignalUpdate, SignalWith, SignalWithUntracked, StoredValue, View,
+};
+use simfony::types::{TypeInner, UIntType};
+use simfony::ResolvedType;
+
+use crate::components::program_window::Program;
+use crate::components::run_window::CustomPreimages;
+use crate::components::string_box::ErrorBox;
+use crate::form::{self, FormValue};
+use crate::witness::{self, Module};
+
+#[component]
+pub fn WitnessTab() -> impl IntoView {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let custom_preimages =
+        use_context::<CustomPreimages>().expect("custom preimages should exist in context");
+    // The form is rebuilt only if the declared names or types change
+    let declarations = create_memo(move |_| {
+        custom_preimages
+            .0
+            .with(|preimages| witness::declarations(&program.combined().text, preimages))
+    });
+    let declarations_error = move || {
+        declarations.with(|declarations| declarations.as_ref().err().cloned().unwrap_or_default())
+    };
+    let module_form = move |module: Module| {
+        move || {
+            declarations.with(|declarations| {
+                declarations
+                    .iter()
+                    .flatten()
+                    .filter(|(entry_module, ..)| *entry_module == module)
+                    .map(|(_, name, ty)| {
+                        view! {
+                            <FormEntry module=module name=name.clone() ty=ty.clone() />
+                        }
+                    })
+                    .collect_view()
+            })
+        }
+    };
+
+    view! {
+        <div class="tab-content witness-tab">
+            <p class="tab-description">
+                "Edit the witness values and parameters of your program. "
+                "Changes are written into the program text, and changes to the program text show up here."
+            </p>
+            <ErrorBox error=declarations_error />
+            <h3 class="tab-title">
+                Witness
+            </h3>
+            <div>
+                {module_form(Module::Witness)}
+            </div>
+            <h3 class="tab-title">
+                Parameters
+            </h3>
+            <div>
+                {module_form(Module::Param)}
+            </div>
+        </div>
+    }
+}
+
+/// Form of a single witness value or parameter.
+#[derive(Copy, Clone)]
+struct EntryForm {
+    program: Program,
+    module: Module,
+    name: StoredValue<String>,
+    ty: StoredValue<ResolvedType>,
+    value: RwSignal<FormValue>,
+}
+
+impl EntryForm {
+    /// Read the value at the given `path` of the form.
+    fn read<T, F>(self, path: &[usize], f: F) -> Option<T>
+    where
+        F: FnOnce(&FormValue) -> T,
+    {
+        self.value.with(|value| value.get(path).map(f))
+    }
+
+    /// Edit the value at the given `path` of the form and write the result into the program file
+    /// that contains the module.
+    fn edit<F>(self, path: &[usize], f: F)
+    where
+        F: FnOnce(&mut FormValue),
+    {
+        self.value.update(|value| {
+            if let Some(value) = value.get_mut(path) {
+                f(value);
+            }
+        });
+        self.program.update_module_file(self.module, |text| {
+            self.name.with_value(|name| {
+                self.ty.with_value(|ty| {
+                    self.value.with_untracked(|value| {
+                        form::set_entry_value(text, self.module, name, ty, value)
+                    })
+                })
+            })
+        });
+    }
+}
+
+#[component]
+fn FormEntry(module: Module, name: String, ty: ResolvedType) -> impl IntoView {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let initial_value = form::entry_value(&program.combined_untracked().text, module, &name, &ty);
+    let entry = EntryForm {
+        program,
+        module,
+        name: store_value(name.clone()),
+        ty: store_value(ty.clone()),
+        value: create_rw_signal(initial_value),
+    };
+
+    // Update the form when the user edits the program text.
+    // Values are compared as text, because the form remembers inputs that the text omits,
+    // such as the unselected side of an `Either` value.
+    create_effect(move |_| {
+        let text = program.combined().text;
+        let text_value = entry.name.with_value(|name| {
+            entry
+                .ty
+                .with_value(|ty| form::entry_value(&text, module, name, ty))
+        });
+        let is_outdated = entry
+            .value
+            .with_untracked(|value| value.to_string() != text_value.to_string());
+        if is_outdated {
+            entry.value.set(text_value);
+        }
+    });
+
+    view! {
+        <div class="witness-entry">
+            <div class="witness-entry-label">
+                <span class="witness-entry-name">{name}</span>
+                <span class="witness-entry-type">{ty.to_string()}</span>
+            </div>
+            {value_input(entry, &ty, vec![])}
+        </div>
+    }
+}
+
+fn child_path(path: &[usize], step: usize) -> Vec<usize> {
+    let mut child_path = path.to_vec();
+    child_path.push(step);
+    child_path
+}
+
+fn literal_input(entry: EntryForm, path: Vec<usize>, placeholder: String) -> View {
+    let literal = {
+        let path = path.clone();
+        move || {
+            entry
+                .read(&path, |value| match value {
+                    FormValue::Literal(literal) => literal.clone(),
+                    _ => "".to_string(),
+                })
+                .unwrap_or_default()
+        }
+    };
+    let update_literal = move |event: ev::Event| {
+        let literal = event_target_value(&event).trim().to_string();
+        entry.edit(&path, |value| *value = FormValue::Literal(literal));
+    };
+
+    view! {
+        <input
+            class="input"
+            type="text"
+            spellcheck="false"
+            placeholder=placeholder
+            prop:value=literal
+            on:input=update_literal
+        />
+    }
+    .into_view()
+}
+
+fn array_rows(entry: EntryForm, element_ty: &ResolvedType, path: &[usize], size: usize) -> View {
+    (0..size)
+        .map(|index| {
+            view! {
+                <div class="witness-row">
+                    <div class="witness-row-label">
+                        {format!("[{index}]")}
+                    </div>
+                    {value_input(entry, element_ty, child_path(path, index))}
+                </div>
+            }
+        })
+        .collect_view()
+}
+
+/// Build input widgets for the value of type `ty` at the given `path` of the form.
+fn value_input(entry: EntryForm, ty: &ResolvedType, path: Vec<usize>) -> View {
+    match ty.as_inner() {
+        TypeInner::Boolean => {
+            let is_true = {
+                let path = path.clone();
+                move || {
+                    entry
+                        .read(&path, |value| matches!(value, FormValue::Boolean(true)))
+                        .unwrap_or(false)
+                }
+            };
+            let update_bit = move |event: ev::Event| {
+                let bit = event_target_checked(&event);
+                entry.edit(&path, |value| *value = FormValue::Boolean(bit));
+            };
+
+            view! {
+                <label class="witness-row">
+                    <input type="checkbox" prop:checked=is_true on:change=update_bit />
+                    <div class="witness-row-label">
+                        true
+                    </div>
+                </label>
+            }
+            .into_view()
+        }
+        TypeInner::UInt(UIntType::U256) => {
+            literal_input(entry, path, format!("0x{}", "00".repeat(32)))
+        }
+        TypeInner::UInt(..) => literal_input(entry, path, ty.to_string()),
+        TypeInner::Either(left_ty, right_ty) => {
+            let is_right = {
+                let path = path.clone();
+                create_memo(move |_| {
+                    entry
+                        .read(&path, |value| {
+                            matches!(value, FormValue::Either { is_right: true, .. })
+                        })
+                        .unwrap_or(false)
+                })
+            };
+            let select_side = {
+                let path = path.clone();
+                move |event: ev::Event| {
+                    let select_right = event_target_value(&event) == "right";
+                    entry.edit(&path, |value| {
+                        if let FormValue::Either { is_right, .. } = value {
+                            *is_right = select_right;
+                        }
+                    });
+                }
+            };
+            let left_ty = ResolvedType::clone(left_ty);
+            let right_ty = ResolvedType::clone(right_ty);
+            let side_input = move || match is_right.get() {
+                false => value_input(entry, &left_ty, child_path(&path, 0)),
+                true => value_input(entry, &right_ty, child_path(&path, 1)),
+            };
+
+            view! {
+                <div class="witness-group">
+                    <select class="input witness-select" on:change=select_side>
+                        <option value="left" prop:selected=move || !is_right.get()>Left</option>
+                        <option value="right" prop:selected=move || is_right.get()>Right</option>
+                    </select>
+                    {side_input}
+                </div>
+            }
+            .into_view()
+        }
+        TypeInner::Option(inner_ty) => {
+            let is_some = {
+                let path = path.clone();
+                create_memo(move |_| {
+                    entry
+                        .read(&path, |value| {
+                            matches!(value, FormValue::Option { is_some: true, .. })
+                        })
+                        .unwrap_or(false)
+                })
+            };
+            let toggle_some = {
+                let path = path.clone();
+                move |event: ev::Event| {
+                    let select_some = event_target_checked(&event);
+                    entry.edit(&path, |value| {
+                        if let FormValue::Option { is_some, .. } = value {
+                            *is_some = select_some;
+                        }
+                    });
+                }
+            };
+            let inner_ty = ResolvedType::clone(inner_ty);
+            let inner_input = move || {
+                is_some
+                    .get()
+                    .then(|| value_input(entry, &inner_ty, child_path(&path, 0)))
+            };
+
+            view! {
+                <div class="witness-group">
+                    <label class="witness-row">
+                        <input type="checkbox" prop:checked=move || is_some.get() on:change=toggle_some />
+                        <div class="witness-row-label">
+                            Some
+                        </div>
+                    </label>
+                    {inner_input}
+                </div>
+            }
+            .into_view()
+        }
+        TypeInner::Tuple(element_tys) => {
+            let elements = element_tys
+                .iter()
+                .enumerate()
+                .map(|(index, element_ty)| value_input(entry, element_ty, child_path(&path, index)))
+                .collect_view();
+
+            view! {
+                <div class="witness-group">
+                    {elements}
+                </div>
+            }
+            .into_view()
+        }
+        TypeInner::Array(element_ty, size) => {
+            let size = *size;
+            let is_literal = {
+                let path = path.clone();
+                create_memo(move |_| {
+                    entry
+                        .read(&path, |value| matches!(value, FormValue::Literal(..)))
+                        .unwrap_or(false)
+                })
+            };
+            let element_ty = ResolvedType::clone(element_ty);
+            // Byte arrays are usually written as hex literals
+            let array_input = move || match is_literal.get() {
+                true => literal_input(entry, path.clone(), format!("0x{}", "00".repeat(size))),
+                false => array_rows(entry, &element_ty, &path, size),
+            };
+
+            view! {
+                <div class="witness-group">
+                    {array_input}
+                </div>
+            }
+            .into_view()
+        }
+        TypeInner::List(element_ty, bound) => {
+            let max_len = bound.get() - 1;
+            let len = {
+                let path = path.clone();
+                create_memo(move |_| {
+                    entry
+                        .read(&path, |value| match value {
+                            FormValue::List(elements) => elements.len(),
+                            _ => 0,
+                        })
+                        .unwrap_or(0)
+                })
+            };
+            let element_ty = ResolvedType::clone(element_ty);
+            let push_element = {
+                let path = path.clone();
+                let element_ty = element_ty.clone();
+                move |_event: ev::MouseEvent| {
+                    entry.edit(&path, |value| {
+                        if let FormValue::List(elements) = value {
+                            if elements.len() < max_len {
+                                elements.push(FormValue::placeholder(&element_ty));
+                            }
+                        }
+                    });
+                }
+            };
+            let pop_element = {
+                let path = path.clone();
+                move |_event: ev::MouseEvent| {
+                    entry.edit(&path, |value| {
+                        if let FormValue::List(elements) = value {
+                            elements.pop();
+                        }
+                    });
+                }
+            };
+            let rows = move || array_rows(entry, &element_ty, &path, len.get());
+
+            view! {
+                <div class="witness-group">
+                    {rows}
+                    <div class="button-row is-small">
+                        <button
+                            class="flat-button bordered"
+                            type="button"
+                            disabled=move || max_len <= len.get()
+                            on:click=push_element
+                        >
+                            <i class="fas fa-plus"></i>
+                            More
+                        </button>
+                        <button
+                            class="flat-button bordered"
+                            type="button"
+                            disabled=move || len.get() == 0
+                            on:click=pop_element
+                        >
+                            <i class="fas fa-minus"></i>
+                            Less
+                        </button>
+                    </div>
+                </div>
+            }
+            .into_view()
+        }
+    }
+}
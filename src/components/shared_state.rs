@@ -0,0 +1,75 @@
+use leptos::{
+    component, create_memo, create_rw_signal, ev, store_value, use_context, view, IntoView, Show,
+    SignalGet, SignalGetUntracked, SignalSet, SignalWith,
+};
+use leptos_router::{use_location, use_navigate, NavigateOptions};
+
+use crate::components::app::ActiveWorkspace;
+use crate::components::program_window::SHARE_FRAGMENT_PREFIX;
+use crate::components::state;
+use crate::share;
+
+/// Offer to open the IDE state of a share link.
+///
+/// The state is opened in a new workspace once the visitor accepts,
+/// so their own workspaces stay untouched.
+#[component]
+pub fn SharedState() -> impl IntoView {
+    let active_workspace =
+        use_context::<ActiveWorkspace>().expect("active workspace should exist in context");
+    let location = use_location();
+    let navigate = store_value(use_navigate());
+    let dismissed = create_rw_signal(false);
+    let shared_values = create_memo(move |_| {
+        location.hash.with(|hash| {
+            hash.trim_start_matches('#')
+                .strip_prefix(SHARE_FRAGMENT_PREFIX)
+                .map(share::decode_state)
+        })
+    });
+
+    // Remove the state from the URL, so reloading the page does not offer it again
+    let clear_url = move || {
+        dismissed.set(true);
+        navigate.with_value(|navigate| {
+            navigate(
+                &location.pathname.get_untracked(),
+                NavigateOptions {
+                    replace: true,
+                    ..Default::default()
+                },
+            )
+        });
+    };
+    let accept = move |_event: ev::MouseEvent| {
+        if let Some(Some(values)) = shared_values.get_untracked() {
+            match state::import_workspace("Shared", &values) {
+                Ok(()) => active_workspace
+                    .0
+                    .set(state::workspaces().active().to_string()),
+                Err(error) => leptos::logging::error!("{error}"),
+            }
+        }
+        clear_url();
+    };
+    let dismiss = move |_event: ev::MouseEvent| clear_url();
+    let is_invalid = move || shared_values.with(|values| matches!(values, Some(None)));
+
+    view! {
+        <Show when=move || !dismissed.get() && shared_values.with(Option::is_some)>
+            <div class="shared-state">
+                <Show
+                    when=move || !is_invalid()
+                    fallback=|| view! { <span>"The shared link is broken."</span> }
+                >
+                    <span>
+                        "This link contains a shared program. "
+                        "Open it in a new workspace? Your own workspaces stay as they are."
+                    </span>
+                    <button class="button" on:click=accept>"Open"</button>
+                </Show>
+                <button class="button" on:click=dismiss>"Dismiss"</button>
+            </div>
+        </Show>
+    }
+}
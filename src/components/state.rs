@@ -1,11 +1,41 @@
+use std::cell::RefCell;
+
+use hex_conservative::{DisplayHex, FromHex};
 use leptos::{use_context, SignalGetUntracked, SignalWithUntracked};
 use simfony::num::U256;
 use web_sys::window;
 
 use crate::components::program_window::Program;
-use crate::components::run_window::{HashCount, KeyCount, TxEnv};
+use crate::components::run_window::{
+    CustomPreimages, HashCount, KeyCount, SignedData, SignedDataMode, SignedDataParams, TxEnv,
+};
+use crate::document::{
+    Document, HashStoreSection, KeyStoreSection, ProgramSection, SignedDataSection,
+    TransactionSection, DOCUMENT_VERSION,
+};
+use crate::encryption;
+use crate::hash::CustomPreimage;
+use crate::project::{self, SourceFile};
 use crate::transaction::TxParams;
 use crate::util::{Counter26, HashedData, SigningKeys};
+use crate::workspace::{self, Store, Workspaces};
+
+/// Storage key of the salt of the passphrase.
+///
+/// The salt exists if and only if the secret values are encrypted.
+const ENCRYPTION_SALT_KEY: &str = "encryption_salt";
+/// Storage key of the verifier of the passphrase, which is stored next to the salt.
+const ENCRYPTION_VERIFIER_KEY: &str = "encryption_verifier";
+
+thread_local! {
+    /// Key that encrypts secret values.
+    ///
+    /// The key exists if the user set a passphrase or unlocked the storage with their passphrase.
+    static ENCRYPTION_KEY: RefCell<Option<encryption::Key>> = const { RefCell::new(None) };
+
+    /// List of workspaces, which is loaded from the storage on first use.
+    static WORKSPACES: RefCell<Option<Workspaces>> = const { RefCell::new(None) };
+}
 
 /// Get the browser's local storage.
 fn local_storage() -> Option<web_sys::Storage> {
@@ -13,8 +43,494 @@ fn local_storage() -> Option<web_sys::Storage> {
     window.local_storage().ok().flatten()
 }
 
+/// Get random bytes from the browser's cryptographically secure random number generator.
+///
+/// Return an error if the browser provides no secure randomness.
+fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0; N];
+    window()
+        .and_then(|window| window.crypto().ok())
+        .and_then(|crypto| crypto.get_random_values_with_u8_array(&mut bytes).ok())
+        .ok_or("Browser provides no secure randomness".to_string())?;
+    Ok(bytes)
+}
+
+/// Encrypt the value that is stored under `storage_key` if the storage is encrypted.
+///
+/// Return `None` if the value cannot be encrypted.
+/// The value must not be stored in this case.
+fn encrypt_value(storage_key: &str, value: String) -> Option<String> {
+    ENCRYPTION_KEY.with_borrow(|key| match key {
+        Some(key) => random_bytes()
+            .ok()
+            .map(|nonce| key.encrypt(storage_key, &value, nonce)),
+        None => Some(value),
+    })
+}
+
+/// Decrypt the value that is stored under `storage_key` if it is encrypted.
+///
+/// Return `None` if the storage is locked or if the value cannot be decrypted.
+fn decrypt_value(storage_key: &str, value: String) -> Option<String> {
+    if !encryption::is_encrypted(&value) {
+        return Some(value);
+    }
+    ENCRYPTION_KEY.with_borrow(|key| {
+        key.as_ref()
+            .and_then(|key| key.decrypt(storage_key, &value))
+    })
+}
+
+impl Store for web_sys::Storage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.get_item(key).ok().flatten()
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let _result = self.set_item(key, value);
+    }
+
+    fn remove(&mut self, key: &str) {
+        let _result = self.remove_item(key);
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let length = self.length().unwrap_or(0);
+        (0..length)
+            .filter_map(|index| self.key(index).ok().flatten())
+            .collect()
+    }
+}
+
+/// Return the keys of all values that make up a workspace.
+fn workspace_keys() -> Vec<&'static str> {
+    let mut keys: Vec<_> = Program::keys()
+        .chain(TxParams::keys())
+        .chain(SignedDataParams::keys())
+        .chain(SigningKeys::keys())
+        .chain(HashedData::keys())
+        .chain(CustomPreimages::keys())
+        .chain(KeyCount::keys())
+        .chain(HashCount::keys())
+        .collect();
+    keys.dedup();
+    keys
+}
+
+/// Return the list of workspaces.
+///
+/// Values that were stored with an older schema are migrated on first use.
+pub fn workspaces() -> Workspaces {
+    WORKSPACES.with_borrow_mut(|workspaces| {
+        workspaces
+            .get_or_insert_with(|| match local_storage() {
+                Some(mut storage) => workspace::migrate(&mut storage, &workspace_keys()),
+                None => Workspaces::default(),
+            })
+            .clone()
+    })
+}
+
+fn set_workspaces(workspaces: Workspaces) {
+    if let Some(mut storage) = local_storage() {
+        storage.set(workspace::WORKSPACES_KEY, &workspaces.to_string());
+    }
+    WORKSPACES.set(Some(workspaces));
+}
+
+/// Return the keys of the secret values that are part of a workspace.
+fn secret_workspace_keys() -> Vec<&'static str> {
+    let mut keys: Vec<_> = SigningKeys::keys()
+        .chain(HashedData::keys())
+        .chain(CustomPreimages::keys())
+        .collect();
+    keys.dedup();
+    keys
+}
+
+/// Iterate over the storage keys of all secret values of all workspaces.
+fn secret_keys() -> impl Iterator<Item = String> {
+    let workspaces = workspaces();
+    let keys: Vec<_> = workspaces
+        .names()
+        .iter()
+        .flat_map(|name| {
+            secret_workspace_keys()
+                .into_iter()
+                .map(|key| workspace::storage_key(name, key))
+        })
+        .collect();
+    keys.into_iter()
+}
+
+/// Encrypt the secret values of all workspaces with the current encryption key,
+/// after decrypting them with the `previous` key.
+///
+/// Values that cannot be decrypted are left as they are.
+fn reencrypt_secrets(previous: Option<&encryption::Key>) {
+    let mut storage = match local_storage() {
+        Some(storage) => storage,
+        None => return,
+    };
+    for key in secret_keys() {
+        let value = match storage.get(&key) {
+            Some(value) => value,
+            None => continue,
+        };
+        let plaintext = match encryption::is_encrypted(&value) {
+            true => match previous.and_then(|previous| previous.decrypt(&key, &value)) {
+                Some(plaintext) => plaintext,
+                None => continue,
+            },
+            false => value,
+        };
+        if let Some(value) = encrypt_value(&key, plaintext) {
+            storage.set(&key, &value);
+        }
+    }
+}
+
+/// Check if the secret values in the browser's local storage are encrypted.
+pub fn is_encryption_enabled() -> bool {
+    local_storage()
+        .and_then(|storage| storage.get_item(ENCRYPTION_SALT_KEY).ok().flatten())
+        .is_some()
+}
+
+/// Check if the secret values are encrypted and the passphrase has not been entered yet.
+pub fn is_locked() -> bool {
+    is_encryption_enabled() && ENCRYPTION_KEY.with_borrow(Option::is_none)
+}
+
+/// Unlock the encrypted secret values using the given `passphrase`.
+///
+/// The passphrase is checked against the stored verifier.
+/// Storage that was encrypted before verifiers existed is checked against its encrypted values,
+/// of which there must be at least one, and the verifier is stored.
+/// The key is derived by the browser, so the page stays responsive in the meantime.
+///
+/// Return an error if the passphrase is wrong
+/// or if the storage was encrypted by an older version of the IDE.
+pub async fn unlock(passphrase: &str) -> Result<(), String> {
+    let storage = local_storage().ok_or("Local storage is unavailable")?;
+    let salt = storage
+        .get_item(ENCRYPTION_SALT_KEY)
+        .ok()
+        .flatten()
+        .and_then(|s| <[u8; encryption::SALT_LEN]>::from_hex(&s).ok())
+        .ok_or("Encryption salt is missing")?;
+    let verifier = storage.get_item(ENCRYPTION_VERIFIER_KEY).ok().flatten();
+    if verifier.as_deref().is_some_and(encryption::is_legacy) {
+        return Err(
+            "Storage was encrypted by an older version of the IDE. Forget the secrets to continue."
+                .to_string(),
+        );
+    }
+    let key = encryption::Key::from_passphrase(passphrase, &salt).await?;
+    match verifier {
+        Some(verifier) => {
+            if !key.verify(&verifier) {
+                return Err("Wrong passphrase".to_string());
+            }
+        }
+        None => {
+            let encrypted_values: Vec<(String, String)> = secret_keys()
+                .filter_map(|storage_key| {
+                    let value = storage.get_item(&storage_key).ok().flatten()?;
+                    Some((storage_key, value))
+                })
+                .filter(|(_, value)| encryption::is_encrypted(value))
+                .collect();
+            if encrypted_values.is_empty() {
+                return Err("Passphrase cannot be verified".to_string());
+            }
+            if !encrypted_values
+                .iter()
+                .all(|(storage_key, value)| key.decrypt(storage_key, value).is_some())
+            {
+                return Err("Wrong passphrase".to_string());
+            }
+            let verifier = key.verifier(random_bytes()?);
+            let _result = storage.set_item(ENCRYPTION_VERIFIER_KEY, &verifier);
+        }
+    }
+    ENCRYPTION_KEY.set(Some(key));
+    Ok(())
+}
+
+/// Encrypt the secret values using the given `passphrase` from now on.
+///
+/// Secret values that are already stored are encrypted, in all workspaces.
+/// The key is derived by the browser, so the page stays responsive in the meantime.
+///
+/// Return an error if the browser provides no secure randomness.
+pub async fn enable_encryption(passphrase: &str) -> Result<(), String> {
+    let storage = local_storage().ok_or("Local storage is unavailable")?;
+    let salt: [u8; encryption::SALT_LEN] = random_bytes()?;
+    let key = encryption::Key::from_passphrase(passphrase, &salt).await?;
+    let verifier = key.verifier(random_bytes()?);
+    let _result = storage.set_item(ENCRYPTION_SALT_KEY, &salt.to_lower_hex_string());
+    let _result = storage.set_item(ENCRYPTION_VERIFIER_KEY, &verifier);
+    let previous = ENCRYPTION_KEY.replace(Some(key));
+    reencrypt_secrets(previous.as_ref());
+    Ok(())
+}
+
+/// Store the secret values in plain text from now on.
+///
+/// Secret values that are already stored are decrypted, in all workspaces.
+pub fn disable_encryption() {
+    if let Some(storage) = local_storage() {
+        let _result = storage.remove_item(ENCRYPTION_SALT_KEY);
+        let _result = storage.remove_item(ENCRYPTION_VERIFIER_KEY);
+    }
+    let previous = ENCRYPTION_KEY.take();
+    reencrypt_secrets(previous.as_ref());
+}
+
+/// Delete the encrypted secret values, for when the passphrase is lost.
+///
+/// New secret values are generated on the next load.
+pub fn forget_secrets() {
+    if let Some(storage) = local_storage() {
+        for key in secret_keys() {
+            let _result = storage.remove_item(&key);
+        }
+    }
+    disable_encryption();
+}
+
+/// Create an empty workspace with the given `name` and open it.
+///
+/// The IDE stores the state of the previously open workspace when it is rebuilt.
+pub fn create_workspace(name: &str) -> Result<(), String> {
+    let mut workspaces = workspaces();
+    workspaces.create(name)?;
+    workspaces.open(name)?;
+    set_workspaces(workspaces);
+    Ok(())
+}
+
+/// Open the workspace with the given `name`.
+///
+/// The IDE stores the state of the previously open workspace when it is rebuilt.
+pub fn open_workspace(name: &str) -> Result<(), String> {
+    let mut workspaces = workspaces();
+    workspaces.open(name)?;
+    set_workspaces(workspaces);
+    Ok(())
+}
+
+/// Copy the open workspace into a new workspace and open the copy.
+pub fn duplicate_workspace() -> Result<(), String> {
+    let mut workspaces = workspaces();
+    let original = workspaces.active().to_string();
+    let copy = workspaces.copy_name(&original);
+    workspaces.create(&copy)?;
+    update_local_storage();
+    if let Some(mut storage) = local_storage() {
+        let secret_keys = secret_workspace_keys();
+        let public_keys: Vec<_> = workspace_keys()
+            .into_iter()
+            .filter(|key| !secret_keys.contains(key))
+            .collect();
+        workspace::copy_values(&mut storage, &public_keys, &original, &copy);
+        // Encrypted values are bound to their storage key, so they are encrypted anew
+        for key in secret_keys {
+            let original_key = workspace::storage_key(&original, key);
+            let copy_key = workspace::storage_key(&copy, key);
+            let value = storage
+                .get(&original_key)
+                .and_then(|value| decrypt_value(&original_key, value))
+                .and_then(|value| encrypt_value(&copy_key, value));
+            if let Some(value) = value {
+                storage.set(&copy_key, &value);
+            }
+        }
+    }
+    workspaces.open(&copy)?;
+    set_workspaces(workspaces);
+    Ok(())
+}
+
+/// Delete the open workspace and all of its values, and open another workspace.
+pub fn delete_workspace() -> Result<(), String> {
+    let mut workspaces = workspaces();
+    let name = workspaces.active().to_string();
+    workspaces.remove(&name)?;
+    if let Some(mut storage) = local_storage() {
+        workspace::remove_values(&mut storage, &workspace_keys(), &name);
+    }
+    set_workspaces(workspaces);
+    Ok(())
+}
+
+/// Return the values of the open workspace that a share link contains, with their keys.
+///
+/// The seeds and the custom preimages of the key store and the hash store are included
+/// only if `include_secrets` is true, like in [`export_document`].
+pub fn shared_values(include_secrets: bool) -> Vec<(&'static str, String)> {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let tx_env = use_context::<TxEnv>().expect("transaction environment should exist in context");
+    let mut values = pairs(&program);
+    values.extend(tx_env.params.with_untracked(pairs));
+    if include_secrets {
+        let signing_keys =
+            use_context::<SigningKeys>().expect("signing keys should exist in context");
+        let key_count = use_context::<KeyCount>().expect("key count should exist in context");
+        let hash_count = use_context::<HashCount>().expect("hash count should exist in context");
+        let custom_preimages =
+            use_context::<CustomPreimages>().expect("custom preimages should exist in context");
+        values.extend(pairs(&signing_keys));
+        values.extend(pairs(&key_count));
+        values.extend(pairs(&hash_count));
+        values.extend(pairs(&custom_preimages));
+    }
+    values
+}
+
+/// Return the entire state of the open workspace as a document.
+///
+/// The seeds of the key store and the hash store are included in plain text
+/// if `include_secrets` is true, and otherwise the key store and the hash store are left out.
+pub fn export_document(include_secrets: bool) -> Document {
+    let program = use_context::<Program>().expect("program should exist in context");
+    let tx_env = use_context::<TxEnv>().expect("transaction environment should exist in context");
+    let signed_data = use_context::<SignedData>().expect("signed data should exist in context");
+    let signing_keys = use_context::<SigningKeys>().expect("signing keys should exist in context");
+    let hashed_data = use_context::<HashedData>().expect("hashed data should exist in context");
+    let key_count = use_context::<KeyCount>().expect("key count should exist in context");
+    let hash_count = use_context::<HashCount>().expect("hash count should exist in context");
+    let custom_preimages =
+        use_context::<CustomPreimages>().expect("custom preimages should exist in context");
+    let key_store = include_secrets.then(|| {
+        KeyStoreSection::from_pairs(&[pairs(&signing_keys), pairs(&key_count)].concat())
+            .expect("key store should be complete")
+    });
+    let hash_store = include_secrets.then(|| {
+        let values = [
+            pairs(&hashed_data),
+            pairs(&hash_count),
+            pairs(&custom_preimages),
+        ]
+        .concat();
+        HashStoreSection::from_pairs(&values).expect("hash store should be complete")
+    });
+
+    Document {
+        version: DOCUMENT_VERSION,
+        program: ProgramSection {
+            files: program.current_files(),
+            active_file: program.active_file.get_untracked(),
+        },
+        transaction: Some(
+            TransactionSection::from_pairs(&tx_env.params.with_untracked(pairs))
+                .expect("transaction should be complete"),
+        ),
+        signed_data: Some(
+            SignedDataSection::from_pairs(&pairs(&signed_data.params()))
+                .expect("signed data should be complete"),
+        ),
+        key_store,
+        hash_store,
+    }
+}
+
+/// Check that the stored `values` of a document section make up a valid object.
+///
+/// Return an error that names the `section` otherwise.
+fn validate<T: LocalStorage>(
+    section: &str,
+    values: &[(&'static str, String)],
+) -> Result<(), String> {
+    let ordered = T::keys().filter_map(|key| {
+        values
+            .iter()
+            .find(|(existing, _)| *existing == key)
+            .map(|(_, value)| value.clone())
+    });
+    match T::from_values(ordered) {
+        Some(..) => Ok(()),
+        None => Err(format!("Section `{section}` contains an invalid value")),
+    }
+}
+
+/// Create a workspace from the `document` and open it.
+///
+/// The name of the workspace starts with `name`.
+/// Return an error if a section of the document contains an invalid value.
+pub fn import_document(name: &str, document: Document) -> Result<(), String> {
+    let program_values = program_values(&document.program.files, document.program.active_file);
+    let mut values: Vec<(String, String)> = Program::keys()
+        .map(str::to_string)
+        .zip(program_values)
+        .collect();
+    let mut extend = |pairs: Vec<(&'static str, String)>| {
+        values.extend(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value)),
+        );
+    };
+    if let Some(section) = &document.transaction {
+        validate::<TxParams>("transaction", &section.pairs())?;
+        extend(section.pairs());
+    }
+    if let Some(section) = &document.signed_data {
+        validate::<SignedDataParams>("signed_data", &section.pairs())?;
+        extend(section.pairs());
+    }
+    if let Some(section) = &document.key_store {
+        validate::<SigningKeys>("key_store", &section.pairs())?;
+        validate::<KeyCount>("key_store", &section.pairs())?;
+        extend(section.pairs());
+    }
+    if let Some(section) = &document.hash_store {
+        validate::<HashedData>("hash_store", &section.pairs())?;
+        validate::<HashCount>("hash_store", &section.pairs())?;
+        validate::<CustomPreimages>("hash_store", &section.pairs())?;
+        extend(section.pairs());
+    }
+    import_workspace(name, &values)
+}
+
+/// Create a workspace from the given `values` with their keys and open it.
+///
+/// The name of the workspace starts with `name`.
+/// Values with unknown keys are ignored.
+/// The IDE stores the state of the previously open workspace when it is rebuilt,
+/// so this function can be called from outside the IDE.
+pub fn import_workspace(name: &str, values: &[(String, String)]) -> Result<(), String> {
+    let mut storage = local_storage().ok_or("Local storage is unavailable")?;
+    let mut workspaces = workspaces();
+    let secret_keys = secret_workspace_keys();
+    workspace::import(
+        &mut storage,
+        &mut workspaces,
+        name,
+        &workspace_keys(),
+        values,
+        |key, storage_key, value| match secret_keys.contains(&key) {
+            true => encrypt_value(storage_key, value),
+            false => Some(value),
+        },
+    )?;
+    set_workspaces(workspaces);
+    Ok(())
+}
+
+/// Return the values of the `object` with their keys.
+fn pairs<T: LocalStorage>(object: &T) -> Vec<(&'static str, String)> {
+    T::keys().zip(object.to_values()).collect()
+}
+
 /// Read / write an object to / from the browser's local storage.
 pub trait LocalStorage: Sized {
+    /// Whether the values of the object are secret.
+    ///
+    /// Secret values are encrypted if the user set a passphrase.
+    const SECRET: bool = false;
+
     /// Iterate over the keys that make up the object.
     fn keys() -> impl Iterator<Item = &'static str>;
 
@@ -29,8 +545,17 @@ pub trait LocalStorage: Sized {
 
     /// Load an object from the browser's local storage.
     fn load_from_storage() -> Option<Self> {
-        let storage = local_storage()?;
-        let values = Self::keys().filter_map(|key| storage.get_item(key).ok().flatten());
+        Self::load_from(&local_storage()?, workspaces().active())
+    }
+
+    /// Load an object from the workspace called `name` in the `store`.
+    fn load_from<S: Store>(store: &S, name: &str) -> Option<Self> {
+        let values = Self::keys().filter_map(|key| {
+            let storage_key = workspace::storage_key(name, key);
+            store
+                .get(&storage_key)
+                .and_then(|value| decrypt_value(&storage_key, value))
+        });
         Self::from_values(values)
     }
 
@@ -38,52 +563,159 @@ pub trait LocalStorage: Sized {
     ///
     /// Replaces any existing value.
     fn store_in_storage(&self) {
-        let storage = match local_storage() {
-            Some(storage) => storage,
-            _ => return,
-        };
+        if let Some(mut storage) = local_storage() {
+            self.store_in(&mut storage, workspaces().active());
+        }
+    }
+
+    /// Store an object in the workspace called `name` in the `store`.
+    ///
+    /// Replaces any existing value.
+    fn store_in<S: Store>(&self, store: &mut S, name: &str) {
         for (key, value) in Self::keys().zip(self.to_values()) {
-            let _result = storage.set_item(key, value.as_str());
+            let storage_key = workspace::storage_key(name, key);
+            let value = match Self::SECRET {
+                true => encrypt_value(&storage_key, value),
+                false => Some(value),
+            };
+            if let Some(value) = value {
+                store.set(&storage_key, &value);
+            }
         }
     }
 }
 
+/// State of the IDE that is stored in a workspace.
+///
+/// The state is taken from the context of the IDE once,
+/// so it can still be stored while the IDE is rebuilt for another workspace.
+#[derive(Clone, Debug)]
+pub struct IdeState {
+    program: Program,
+    tx_env: TxEnv,
+    signed_data: SignedData,
+    signing_keys: SigningKeys,
+    hashed_data: HashedData,
+    custom_preimages: CustomPreimages,
+    key_count: KeyCount,
+    hash_count: HashCount,
+}
+
+impl IdeState {
+    /// Take the state from the context of the IDE.
+    pub fn from_context() -> Self {
+        Self {
+            program: use_context::<Program>().expect("program should exist in context"),
+            tx_env: use_context::<TxEnv>()
+                .expect("transaction environment should exist in context"),
+            signed_data: use_context::<SignedData>().expect("signed data should exist in context"),
+            signing_keys: use_context::<SigningKeys>()
+                .expect("signing keys should exist in context"),
+            hashed_data: use_context::<HashedData>().expect("hashed data should exist in context"),
+            custom_preimages: use_context::<CustomPreimages>()
+                .expect("custom preimages should exist in context"),
+            key_count: use_context::<KeyCount>().expect("key count should exist in context"),
+            hash_count: use_context::<HashCount>().expect("hash count should exist in context"),
+        }
+    }
+
+    /// Store the state in the workspace with the given `name`.
+    ///
+    /// Nothing is stored if the workspace was deleted.
+    pub fn store_in_workspace(&self, name: &str) {
+        if !workspaces().names().iter().any(|existing| existing == name) {
+            return;
+        }
+        if let Some(mut storage) = local_storage() {
+            self.store_in(&mut storage, name);
+            leptos::logging::log!("Update storage");
+        }
+    }
+
+    /// Store the state in the workspace called `name` in the `store`.
+    fn store_in<S: Store>(&self, store: &mut S, name: &str) {
+        self.program.store_in(store, name);
+        self.tx_env
+            .params
+            .with_untracked(|params| params.store_in(store, name));
+        self.signed_data.params().store_in(store, name);
+        self.signing_keys.store_in(store, name);
+        self.hashed_data.store_in(store, name);
+        self.custom_preimages.store_in(store, name);
+        self.key_count.store_in(store, name);
+        self.hash_count.store_in(store, name);
+    }
+}
+
 /// Store the app's entire state in the browser's local storage.
 pub fn update_local_storage() {
-    use_context::<Program>()
-        .expect("program should exist in context")
-        .store_in_storage();
-    use_context::<TxEnv>()
-        .expect("transaction environment should exist in context")
-        .params
-        .with_untracked(LocalStorage::store_in_storage);
-    use_context::<SigningKeys>()
-        .expect("signing keys should exist in context")
-        .store_in_storage();
-    use_context::<HashedData>()
-        .expect("hashed data should exist in context")
-        .store_in_storage();
-    use_context::<HashCount>()
-        .expect("hash count should exist in context")
-        .store_in_storage();
-    leptos::logging::log!("Update storage");
+    IdeState::from_context().store_in_workspace(workspaces().active());
 }
 
 impl LocalStorage for Program {
     fn keys() -> impl Iterator<Item = &'static str> {
-        ["program"].into_iter()
+        // Programs that were stored before projects existed have no files
+        ["program", "program_files"].into_iter()
     }
 
     fn from_values(mut values: impl Iterator<Item = String>) -> Option<Self> {
-        values.next().map(Self::new)
+        let text = values.next()?;
+        match values.next().and_then(|s| project::decode(&s)) {
+            Some((files, active)) => Some(Self::with_files(files, active)),
+            None => Some(Self::new(text)),
+        }
     }
 
     fn to_values(&self) -> impl Iterator<Item = String> {
-        [self.text.get_untracked()].into_iter()
+        program_values(&self.current_files(), self.active_file.get_untracked()).into_iter()
+    }
+}
+
+/// Return the stored values of a program that consists of the given `files`,
+/// where the file at index `active` is open.
+fn program_values(files: &[SourceFile], active: usize) -> [String; 2] {
+    [files[active].text.clone(), project::encode(files, active)]
+}
+
+impl LocalStorage for SignedDataParams {
+    fn keys() -> impl Iterator<Item = &'static str> {
+        [
+            "signed_data_mode",
+            "signed_data_bytes",
+            "signed_data_preimage",
+            "signed_data_function",
+        ]
+        .into_iter()
+    }
+
+    fn from_values(mut values: impl Iterator<Item = String>) -> Option<Self> {
+        let mode = values.next().and_then(|s| SignedDataMode::from_key(&s))?;
+        let thirty_two_bytes = values.next().and_then(|s| FromHex::from_hex(&s).ok())?;
+        let hash_preimage_bytes = values.next().and_then(|s| FromHex::from_hex(&s).ok())?;
+        let simfony_function_name = values.next()?;
+
+        Some(Self {
+            mode,
+            thirty_two_bytes,
+            hash_preimage_bytes,
+            simfony_function_name,
+        })
+    }
+
+    fn to_values(&self) -> impl Iterator<Item = String> {
+        [
+            self.mode.key().to_string(),
+            self.thirty_two_bytes.to_lower_hex_string(),
+            self.hash_preimage_bytes.to_lower_hex_string(),
+            self.simfony_function_name.clone(),
+        ]
+        .into_iter()
     }
 }
 
 impl LocalStorage for SigningKeys {
+    const SECRET: bool = true;
+
     fn keys() -> impl Iterator<Item = &'static str> {
         ["random_seed"].into_iter()
     }
@@ -101,6 +733,8 @@ impl LocalStorage for SigningKeys {
 }
 
 impl LocalStorage for HashedData {
+    const SECRET: bool = true;
+
     fn keys() -> impl Iterator<Item = &'static str> {
         ["random_seed"].into_iter()
     }
@@ -135,6 +769,34 @@ impl LocalStorage for KeyCount {
     }
 }
 
+impl LocalStorage for CustomPreimages {
+    const SECRET: bool = true;
+
+    fn keys() -> impl Iterator<Item = &'static str> {
+        ["custom_preimages"].into_iter()
+    }
+
+    fn from_values(mut values: impl Iterator<Item = String>) -> Option<Self> {
+        values
+            .next()?
+            .lines()
+            .map(|line| line.parse::<CustomPreimage>().ok())
+            .collect::<Option<Vec<_>>>()
+            .map(Self::new)
+    }
+
+    fn to_values(&self) -> impl Iterator<Item = String> {
+        let preimages = self.0.with_untracked(|preimages| {
+            preimages
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+        [preimages].into_iter()
+    }
+}
+
 impl LocalStorage for HashCount {
     fn keys() -> impl Iterator<Item = &'static str> {
         ["hash_count"].into_iter()
@@ -203,3 +865,63 @@ impl LocalStorage for TxParams {
         .into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::hash::HashFunction;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn workspace_roundtrip() {
+        let _runtime = leptos::create_runtime();
+        let program = Program::new("fn main() {}".to_string());
+        let tx_env = TxEnv::new(program, TxParams::default());
+        let custom_preimage = CustomPreimage {
+            preimage: vec![0xab; 20],
+            function: HashFunction::Sha256,
+        };
+        let custom_preimages = CustomPreimages::new(vec![custom_preimage.clone()]);
+        let signed_data = SignedData::new(
+            program,
+            tx_env.lazy_env,
+            custom_preimages,
+            SignedDataParams::default(),
+        );
+        let state = IdeState {
+            program,
+            tx_env,
+            signed_data,
+            signing_keys: SigningKeys::default(),
+            hashed_data: HashedData::default(),
+            custom_preimages,
+            key_count: KeyCount::new(Counter26::new(7).unwrap()),
+            hash_count: HashCount::new(Counter26::new(3).unwrap()),
+        };
+        let mut store = HashMap::<String, String>::new();
+        state.store_in(&mut store, "Vault");
+
+        // Each value of the workspace is stored
+        let mut stored: Vec<_> = workspace_keys()
+            .into_iter()
+            .filter(|key| Store::get(&store, &workspace::storage_key("Vault", key)).is_some())
+            .collect();
+        stored.sort();
+        let mut expected = workspace_keys();
+        expected.sort();
+        assert_eq!(expected, stored);
+        assert_eq!(workspace_keys().len(), store.len());
+
+        let key_count = KeyCount::load_from(&store, "Vault").unwrap();
+        assert_eq!(7, key_count.0.get_untracked().get());
+        let hash_count = HashCount::load_from(&store, "Vault").unwrap();
+        assert_eq!(3, hash_count.0.get_untracked().get());
+        let custom_preimages = CustomPreimages::load_from(&store, "Vault").unwrap();
+        assert_eq!(vec![custom_preimage], custom_preimages.0.get_untracked());
+        let loaded = Program::load_from(&store, "Vault").unwrap();
+        assert_eq!(program.current_files(), loaded.current_files());
+        assert!(KeyCount::load_from(&store, "Swap").is_none());
+    }
+}
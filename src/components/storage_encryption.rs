@@ -0,0 +1,218 @@
+use leptos::{
+    component, create_rw_signal, ev, event_target_value, spawn_local, store_value, view, IntoView,
+    RwSignal, Show, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate,
+};
+
+use crate::components::state::{self, IdeState};
+use crate::components::string_box::ErrorBox;
+
+#[component]
+pub fn UnlockStorage(unlocked: RwSignal<bool>) -> impl IntoView {
+    let passphrase = create_rw_signal("".to_string());
+    let unlock_error = create_rw_signal("".to_string());
+    let unlocking = create_rw_signal(false);
+
+    let update_passphrase = move |event: ev::Event| {
+        passphrase.set(event_target_value(&event));
+        unlock_error.update(String::clear);
+    };
+    let unlock = move || {
+        if unlocking.get_untracked() {
+            return;
+        }
+        unlocking.set(true);
+        spawn_local(async move {
+            match state::unlock(&passphrase.get_untracked()).await {
+                Ok(()) => unlocked.set(true),
+                Err(error) => unlock_error.set(error),
+            }
+            unlocking.set(false);
+        });
+    };
+    let handle_keydown = move |event: ev::KeyboardEvent| {
+        if event.key() == "Enter" {
+            unlock();
+        }
+    };
+    let forget_secrets = move |_event: ev::MouseEvent| {
+        let confirmed = web_sys::window()
+            .and_then(|window| {
+                window
+                    .confirm_with_message(
+                        "Delete the encrypted keys and preimages? Coins that depend on them will be lost.",
+                    )
+                    .ok()
+            })
+            .unwrap_or(false);
+        if confirmed {
+            state::forget_secrets();
+            unlocked.set(true);
+        }
+    };
+
+    view! {
+        <div class="tab-content unlock-storage">
+            <h3 class="tab-title">
+                Unlock Storage
+            </h3>
+            <p class="tab-description">
+                "The secret keys and preimages in the browser's local storage are encrypted. "
+                "Enter your passphrase to unlock them."
+            </p>
+            <div class="key-store-display-row">
+                <input
+                    class="input"
+                    type="password"
+                    placeholder="Enter passphrase"
+                    on:input=update_passphrase
+                    on:keydown=handle_keydown
+                />
+            </div>
+            <ErrorBox error=unlock_error />
+            <div class="button-row is-small">
+                <button
+                    class="flat-button"
+                    type="button"
+                    disabled=move || unlocking.get()
+                    on:click=move |_| unlock()
+                >
+                    <i class="fas fa-lock-open"></i>
+                    {move || match unlocking.get() {
+                        true => "Unlocking...",
+                        false => "Unlock",
+                    }}
+                </button>
+                <button
+                    class="flat-button bordered"
+                    type="button"
+                    on:click=forget_secrets
+                >
+                    <i class="fas fa-trash"></i>
+                    Forget secrets
+                </button>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn StorageEncryption() -> impl IntoView {
+    let encryption_enabled = create_rw_signal(state::is_encryption_enabled());
+    let passphrase = create_rw_signal("".to_string());
+    let confirmation = create_rw_signal("".to_string());
+    let passphrase_error = create_rw_signal("".to_string());
+    let encrypting = create_rw_signal(false);
+    // The state is taken now, because the context is gone once the key is derived
+    let ide_state = store_value(IdeState::from_context());
+
+    let update_passphrase = move |event: ev::Event| {
+        passphrase.set(event_target_value(&event));
+        passphrase_error.update(String::clear);
+    };
+    let update_confirmation = move |event: ev::Event| {
+        confirmation.set(event_target_value(&event));
+        passphrase_error.update(String::clear);
+    };
+    let enable_encryption = move |_event: ev::MouseEvent| {
+        let passphrase = passphrase.get_untracked();
+        if passphrase.is_empty() {
+            passphrase_error.set("Passphrase must not be empty".to_string());
+        } else if passphrase != confirmation.get_untracked() {
+            passphrase_error.set("Passphrases do not match".to_string());
+        } else if !encrypting.get_untracked() {
+            encrypting.set(true);
+            let ide_state = ide_state.get_value();
+            spawn_local(async move {
+                match state::enable_encryption(&passphrase).await {
+                    Ok(()) => {
+                        ide_state.store_in_workspace(state::workspaces().active());
+                        encryption_enabled.set(true);
+                    }
+                    Err(error) => passphrase_error.set(error),
+                }
+                encrypting.set(false);
+            });
+        }
+    };
+    let disable_encryption = move |_event: ev::MouseEvent| {
+        state::disable_encryption();
+        state::update_local_storage();
+        encryption_enabled.set(false);
+    };
+    let lock = move |_event: ev::MouseEvent| {
+        state::update_local_storage();
+        let _result = web_sys::window().map(|window| window.location().reload());
+    };
+
+    view! {
+        <div>
+            <div class="tab-title-group">
+                <h3 class="tab-title">
+                    Storage Encryption
+                </h3>
+            </div>
+            <Show
+                when=move || encryption_enabled.get()
+                fallback=move || view! {
+                    <p class="tab-description">
+                        "Encrypt the secret keys and preimages with a passphrase. "
+                        "You will need to enter the passphrase each time you open the IDE."
+                    </p>
+                    <div class="key-store-display-row">
+                        <input
+                            class="input"
+                            type="password"
+                            placeholder="Enter passphrase"
+                            on:input=update_passphrase
+                        />
+                    </div>
+                    <div class="key-store-display-row">
+                        <input
+                            class="input"
+                            type="password"
+                            placeholder="Repeat passphrase"
+                            on:input=update_confirmation
+                        />
+                    </div>
+                    <ErrorBox error=passphrase_error />
+                    <div class="button-row is-small">
+                        <button
+                            class="flat-button"
+                            type="button"
+                            disabled=move || encrypting.get()
+                            on:click=enable_encryption
+                        >
+                            <i class="fas fa-lock"></i>
+                            {move || match encrypting.get() {
+                                true => "Encrypting...",
+                                false => "Encrypt",
+                            }}
+                        </button>
+                    </div>
+                }
+            >
+                <p class="tab-description">
+                    "The secret keys and preimages are encrypted with your passphrase."
+                </p>
+                <div class="button-row is-small">
+                    <button
+                        class="flat-button"
+                        type="button"
+                        on:click=lock
+                    >
+                        <i class="fas fa-lock"></i>
+                        Lock
+                    </button>
+                    <button
+                        class="flat-button bordered"
+                        type="button"
+                        on:click=disable_encryption
+                    >
+                        <i class="fas fa-lock-open"></i>
+                        Remove passphrase
+                    </button>
+                </div>
+            </Show>
+        </div>
+    }
+}
@@ -0,0 +1,505 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::{fmt, io};
+
+use simfony::simplicity;
+use simplicity::dag::{DagLike, MaxSharing};
+use simplicity::jet::{Elements, Jet};
+use simplicity::node::{self, Inner};
+use simplicity::{encode, Amr, BitWriter, Cmr, Imr};
+
+use crate::util::{DisplayInner, Expression};
+
+/// What pruning does to a node of a Simplicity program.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Pruning {
+    /// The program was not pruned, because it failed to run.
+    Unknown,
+    /// The node stays in the pruned program.
+    Kept,
+    /// The node is a case whose right branch is pruned, so it becomes an `assertl`.
+    KeptLeft,
+    /// The node is a case whose left branch is pruned, so it becomes an `assertr`.
+    KeptRight,
+    /// The node is not executed and is removed from the pruned program.
+    Removed,
+}
+
+impl fmt::Display for Pruning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pruning::Unknown => f.write_str("unknown (the program fails)"),
+            Pruning::Kept => f.write_str("kept"),
+            Pruning::KeptLeft => f.write_str("kept, becomes assertl"),
+            Pruning::KeptRight => f.write_str("kept, becomes assertr"),
+            Pruning::Removed => f.write_str("removed"),
+        }
+    }
+}
+
+/// Node of a Simplicity program.
+#[derive(Clone, Debug)]
+pub struct DagNode {
+    /// Name of the combinator, jet or word.
+    pub name: String,
+    /// Identifiers of the children, which are their indices in the node list.
+    pub children: Vec<usize>,
+    pub cmr: Cmr,
+    pub imr: Imr,
+    pub amr: Amr,
+    pub source_type: String,
+    pub target_type: String,
+    pub pruning: Pruning,
+    /// Upper bound on the cost of the node and its descendants in milliweight units.
+    pub milli_weight: u64,
+    /// Number of bits that the node takes up in the encoding of the program and witness data,
+    /// without its descendants.
+    ///
+    /// Identical nodes are encoded once, so the bits belong to all users of the node.
+    pub bits: u64,
+    /// Whether the node is a case, which executes only one of its children.
+    pub is_case: bool,
+}
+
+/// Simplicity program as a list of nodes, where identical subexpressions are shared.
+///
+/// Nodes are listed in post order, so children come before their parents,
+/// and the root comes last.
+#[derive(Clone, Debug)]
+pub struct Dag {
+    nodes: Vec<DagNode>,
+}
+
+/// Return the number of bits of the encoding of the positive natural number `n`.
+///
+/// The encoding of 1 is a single bit. The encoding of `2^k + r` with `r < 2^k` is a bit,
+/// followed by the encoding of `k` and by the `k` bits of `r`.
+fn natural_bits(n: usize) -> u64 {
+    debug_assert!(0 < n);
+    match n.ilog2() {
+        0 => 1,
+        len => 1 + natural_bits(len as usize) + u64::from(len),
+    }
+}
+
+/// Identifier of a node of the encoding of a program.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+enum EncodedId {
+    Node(Imr),
+    /// Hidden branch of an assertion, which is encoded by its CMR.
+    Hidden(Cmr),
+}
+
+/// Number of bits that the nodes of a program take up in its encoding.
+#[derive(Clone, Debug, Default)]
+struct EncodedSize {
+    /// Bits of each node in the encoding of the program, by IMR.
+    program: HashMap<Imr, u64>,
+    /// Bits of the value of each witness node in the encoding of the witness data, by IMR.
+    witness: HashMap<Imr, u64>,
+    /// Number of encoded nodes, including hidden branches.
+    len: usize,
+}
+
+impl EncodedSize {
+    /// Compute the encoded size of the nodes of the `program`.
+    ///
+    /// Nodes are encoded once in post order, and parents refer to their children
+    /// by the distance between their positions.
+    /// The hidden branch of an assertion is encoded as a node of its own,
+    /// whose bits belong to the first assertion that refers to it.
+    fn new(program: &Expression) -> Self {
+        enum Visit<'a> {
+            Enter(&'a Expression),
+            Exit(&'a Expression),
+            Hidden(Cmr),
+        }
+
+        let mut size = Self::default();
+        let mut indices: HashMap<EncodedId, usize> = HashMap::new();
+        // Bits of hidden branches that no assertion claimed yet
+        let mut hidden_bits: HashMap<Cmr, u64> = HashMap::new();
+        let mut stack = vec![Visit::Enter(program)];
+
+        while let Some(visit) = stack.pop() {
+            let node = match visit {
+                Visit::Enter(node) => {
+                    if !indices.contains_key(&EncodedId::Node(node.imr())) {
+                        stack.push(Visit::Exit(node));
+                        // Children are pushed in reverse, so the left child is visited first
+                        match node.inner() {
+                            Inner::AssertL(left, cmr) => {
+                                stack.push(Visit::Hidden(*cmr));
+                                stack.push(Visit::Enter(left));
+                            }
+                            Inner::AssertR(cmr, right) => {
+                                stack.push(Visit::Enter(right));
+                                stack.push(Visit::Hidden(*cmr));
+                            }
+                            _ => {
+                                stack.extend(node.right_child().map(Visit::Enter));
+                                stack.extend(node.left_child().map(Visit::Enter));
+                            }
+                        }
+                    }
+                    continue;
+                }
+                Visit::Hidden(cmr) => {
+                    if !indices.contains_key(&EncodedId::Hidden(cmr)) {
+                        indices.insert(EncodedId::Hidden(cmr), size.len);
+                        size.len += 1;
+                        hidden_bits.insert(cmr, 4 + 256);
+                    }
+                    continue;
+                }
+                Visit::Exit(node) => node,
+            };
+
+            let index = size.len;
+            let reference = |id: EncodedId| natural_bits(index - indices[&id]);
+            let child = |child: &Expression| reference(EncodedId::Node(child.imr()));
+            let mut hidden = |cmr: &Cmr| {
+                reference(EncodedId::Hidden(*cmr)) + hidden_bits.remove(cmr).unwrap_or(0)
+            };
+            let bits = match node.inner() {
+                Inner::Iden | Inner::Unit => 5,
+                Inner::InjL(c) | Inner::InjR(c) | Inner::Take(c) | Inner::Drop(c) => 5 + child(c),
+                Inner::Comp(l, r) | Inner::Case(l, r) | Inner::Pair(l, r) => {
+                    5 + child(l) + child(r)
+                }
+                Inner::Disconnect(l, r) => 5 + child(l) + child(r),
+                Inner::AssertL(l, cmr) => 5 + child(l) + hidden(cmr),
+                Inner::AssertR(cmr, r) => 5 + hidden(cmr) + child(r),
+                Inner::Fail(..) => 5 + 512,
+                Inner::Witness(value) => {
+                    let mut writer = BitWriter::new(io::sink());
+                    let value_bits = encode::encode_value(value, &mut writer)
+                        .expect("writing to a sink should not fail");
+                    size.witness.insert(node.imr(), value_bits as u64);
+                    4
+                }
+                Inner::Jet(jet) => {
+                    let mut writer = BitWriter::new(io::sink());
+                    let jet_bits = jet
+                        .encode(&mut writer)
+                        .expect("writing to a sink should not fail");
+                    2 + jet_bits as u64
+                }
+                // Words are as wide as their type, which is a power of two
+                Inner::Word(..) => {
+                    let width = node.arrow().target.bit_width();
+                    2 + natural_bits(1 + width.trailing_zeros() as usize) + width as u64
+                }
+            };
+            indices.insert(EncodedId::Node(node.imr()), index);
+            size.program.insert(node.imr(), bits);
+            size.len += 1;
+        }
+
+        size
+    }
+
+    /// Return the number of bits that the node with the given `imr` takes up
+    /// in the encoding of the program and of the witness data.
+    fn bits(&self, imr: Imr) -> u64 {
+        self.program.get(&imr).copied().unwrap_or(0) + self.witness.get(&imr).copied().unwrap_or(0)
+    }
+}
+
+/// Return what pruning does to each node of a program,
+/// given the `children` of each node of the program and of the `pruned` program.
+///
+/// Both programs are walked in parallel from their roots, so each node is compared with the node
+/// at the same position in the pruned program, even if other nodes share its CMR.
+/// A case that becomes an `assertl` in one place and an `assertr` in another place
+/// executes both branches, so it is kept.
+fn prune_statuses(
+    children: &[(Vec<usize>, bool)],
+    pruned: &[(Vec<usize>, Pruning)],
+) -> Vec<Pruning> {
+    let mut statuses: Vec<Option<Pruning>> = vec![None; children.len()];
+    let mut visited = HashSet::new();
+    let mut stack = match (children.len(), pruned.len()) {
+        (0, _) | (_, 0) => vec![],
+        (len, pruned_len) => vec![(len - 1, pruned_len - 1)],
+    };
+
+    while let Some((index, pruned_index)) = stack.pop() {
+        if !visited.insert((index, pruned_index)) {
+            continue;
+        }
+        let (node_children, is_case) = &children[index];
+        let (pruned_children, pruned_status) = &pruned[pruned_index];
+        let status = match is_case {
+            true => *pruned_status,
+            false => Pruning::Kept,
+        };
+        statuses[index] = Some(match (statuses[index], status) {
+            (None, status) => status,
+            (Some(previous), status) if previous == status => status,
+            _ => Pruning::Kept,
+        });
+        let kept_children = match (is_case, status) {
+            (true, Pruning::KeptRight) => &node_children[1..],
+            (true, Pruning::KeptLeft) => &node_children[..1],
+            _ => &node_children[..],
+        };
+        stack.extend(
+            kept_children
+                .iter()
+                .copied()
+                .zip(pruned_children.iter().copied()),
+        );
+    }
+
+    statuses
+        .into_iter()
+        .map(|status| status.unwrap_or(Pruning::Removed))
+        .collect()
+}
+
+impl Dag {
+    /// Create the DAG of the given `program`.
+    ///
+    /// The pruning status of each node is determined by comparing with the `pruned` program.
+    pub fn new(program: &Expression, pruned: Option<&Expression>) -> Self {
+        let pruned_nodes: Option<Vec<(Vec<usize>, Pruning)>> = pruned.map(|pruned| {
+            pruned
+                .post_order_iter::<MaxSharing<node::Redeem<Elements>>>()
+                .map(|data| {
+                    let pruning = match data.node.inner() {
+                        Inner::AssertL(..) => Pruning::KeptLeft,
+                        Inner::AssertR(..) => Pruning::KeptRight,
+                        _ => Pruning::Kept,
+                    };
+                    let children = data.left_index.into_iter().chain(data.right_index);
+                    (children.collect(), pruning)
+                })
+                .collect()
+        });
+
+        let size = EncodedSize::new(program);
+        let mut nodes: Vec<DagNode> = program
+            .post_order_iter::<MaxSharing<node::Redeem<Elements>>>()
+            .map(|data| {
+                let is_case = matches!(data.node.inner(), Inner::Case(..));
+                let arrow = data.node.arrow();
+                // FIXME: Add conversion method to simplicity::Cost
+                let milli_weight = u64::from_str(&data.node.bounds().cost.to_string()).unwrap();
+                DagNode {
+                    name: DisplayInner::from(data.node).to_string(),
+                    children: data
+                        .left_index
+                        .into_iter()
+                        .chain(data.right_index)
+                        .collect(),
+                    cmr: data.node.cmr(),
+                    imr: data.node.imr(),
+                    amr: data.node.amr(),
+                    source_type: arrow.source.to_string(),
+                    target_type: arrow.target.to_string(),
+                    pruning: Pruning::Unknown,
+                    milli_weight,
+                    bits: size.bits(data.node.imr()),
+                    is_case,
+                }
+            })
+            .collect();
+
+        if let Some(pruned_nodes) = pruned_nodes {
+            let children: Vec<_> = nodes
+                .iter()
+                .map(|node| (node.children.clone(), node.is_case))
+                .collect();
+            for (node, pruning) in nodes
+                .iter_mut()
+                .zip(prune_statuses(&children, &pruned_nodes))
+            {
+                node.pruning = pruning;
+            }
+        }
+
+        Self { nodes }
+    }
+
+    pub fn nodes(&self) -> &[DagNode] {
+        &self.nodes
+    }
+
+    pub fn get(&self, id: usize) -> Option<&DagNode> {
+        self.nodes.get(id)
+    }
+
+    /// Return the identifier of the root node.
+    pub fn root(&self) -> usize {
+        self.nodes.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simfony::parse::ParseFromStr;
+    use simfony::{Arguments, CompiledProgram, WitnessValues};
+
+    use super::*;
+    use crate::examples;
+    use crate::test_util::satisfied_and_tx_env;
+    use crate::transaction::TxParams;
+    use crate::util::{HashedData, SigningKeys};
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn share_and_prune_examples() {
+        let signing_keys = SigningKeys::default();
+        let hashed_data = HashedData::default();
+
+        for name in examples::keys() {
+            let example = examples::get(name).unwrap();
+            let (satisfied, tx_env) = satisfied_and_tx_env(example, &signing_keys, &hashed_data);
+            let program = satisfied.redeem();
+
+            let dag = Dag::new(program, None);
+            let shared_len = program
+                .pre_order_iter::<MaxSharing<node::Redeem<Elements>>>()
+                .count();
+            assert_eq!(shared_len, dag.nodes().len(), "{name}");
+            assert_eq!(program.cmr(), dag.get(dag.root()).unwrap().cmr, "{name}");
+            assert_eq!(
+                program.bounds().cost.to_string(),
+                dag.get(dag.root()).unwrap().milli_weight.to_string(),
+                "{name}"
+            );
+            for (id, node) in dag.nodes().iter().enumerate() {
+                assert!(node.children.iter().all(|&child| child < id), "{name}");
+                assert_eq!(Pruning::Unknown, node.pruning, "{name}");
+            }
+
+            let unpruned = Dag::new(program, Some(program));
+            assert!(unpruned
+                .nodes()
+                .iter()
+                .all(|node| node.pruning == Pruning::Kept));
+
+            let pruned = program.prune(&tx_env).unwrap();
+            let dag = Dag::new(program, Some(&pruned));
+            assert_eq!(
+                Pruning::Kept,
+                dag.get(dag.root()).unwrap().pruning,
+                "{name}"
+            );
+            for node in dag.nodes() {
+                if matches!(node.pruning, Pruning::KeptLeft | Pruning::KeptRight) {
+                    assert_eq!("case", node.name, "{name}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn encode_naturals() {
+        // 1 = "0", 2 = "1" "0" "0", 3 = "1" "0" "1", 4 = "1" "100" "00"
+        let bits: Vec<u64> = [1, 2, 3, 4, 7, 8].into_iter().map(natural_bits).collect();
+        assert_eq!(vec![1, 3, 3, 6, 6, 7], bits);
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn encoded_size_of_examples() {
+        let signing_keys = SigningKeys::default();
+        let hashed_data = HashedData::default();
+
+        for name in examples::keys() {
+            let example = examples::get(name).unwrap();
+            let (satisfied, tx_env) = satisfied_and_tx_env(example, &signing_keys, &hashed_data);
+            let unpruned = satisfied.redeem().clone();
+            let pruned = unpruned.prune(&tx_env).unwrap();
+
+            for program in [unpruned, pruned] {
+                let size = EncodedSize::new(&program);
+                let (program_bytes, witness_bytes) = program.encode_to_vec();
+                let node_bits: u64 = size.program.values().sum();
+                let program_bits = natural_bits(size.len) + node_bits;
+                assert_eq!(
+                    program_bytes.len() as u64,
+                    program_bits.div_ceil(8),
+                    "{name}"
+                );
+                let witness_bits: u64 = size.witness.values().sum();
+                assert!(witness_bits <= 8 * witness_bytes.len() as u64, "{name}");
+
+                let dag = Dag::new(&program, None);
+                let dag_bits: u64 = dag.nodes().iter().map(|node| node.bits).sum();
+                assert_eq!(node_bits + witness_bits, dag_bits, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn walk_pruned_program_in_parallel() {
+        // A case whose right branch is pruned
+        let children = [(vec![], false), (vec![], false), (vec![0, 1], true)];
+        let pruned = [(vec![], Pruning::Kept), (vec![0], Pruning::KeptLeft)];
+        assert_eq!(
+            vec![Pruning::Kept, Pruning::Removed, Pruning::KeptLeft],
+            prune_statuses(&children, &pruned)
+        );
+
+        // A shared case that executes its left branch in one place and its right branch in another
+        let children = [
+            (vec![], false),
+            (vec![], false),
+            (vec![0, 1], true),
+            (vec![2, 2], false),
+        ];
+        let pruned = [
+            (vec![], Pruning::Kept),
+            (vec![0], Pruning::KeptLeft),
+            (vec![], Pruning::Kept),
+            (vec![2], Pruning::KeptRight),
+            (vec![1, 3], Pruning::Kept),
+        ];
+        assert_eq!(vec![Pruning::Kept; 4], prune_statuses(&children, &pruned));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn prune_untaken_branch() {
+        let text = r#"fn main() {
+    let bit: bool = witness::BIT;
+    match bit {
+        false => assert!(jet::eq_8(1, 1)),
+        true => assert!(jet::eq_8(2, 2)),
+    }
+}
+
+mod witness {
+    const BIT: bool = false;
+}"#;
+        let arguments = Arguments::parse_from_str(text).unwrap();
+        let compiled = CompiledProgram::new(text, arguments).unwrap();
+        let tx_env = TxParams::default().tx_env(compiled.commit().cmr());
+        let witness = WitnessValues::parse_from_str(text).unwrap();
+        let program = compiled.satisfy(witness).unwrap().redeem().clone();
+        let pruned = program.prune(&tx_env).unwrap();
+
+        let dag = Dag::new(&program, Some(&pruned));
+        // The constants of the untaken branch are removed, the others are kept
+        let constants = |pruning: Pruning| -> Vec<&str> {
+            dag.nodes()
+                .iter()
+                .filter(|node| node.name.starts_with("const") && node.pruning == pruning)
+                .map(|node| node.name.as_str())
+                .collect()
+        };
+        let (kept, removed) = (constants(Pruning::Kept), constants(Pruning::Removed));
+        assert!(!kept.is_empty());
+        assert!(!removed.is_empty());
+        assert!(removed.iter().all(|name| !kept.contains(name)));
+        assert!(dag
+            .nodes()
+            .iter()
+            .any(|node| node.is_case && node.pruning == Pruning::KeptLeft));
+    }
+}
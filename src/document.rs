@@ -0,0 +1,347 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::project::SourceFile;
+
+/// Version of the workspace document format.
+///
+/// Increase the version when the format changes incompatibly.
+/// Documents of newer versions are rejected.
+pub const DOCUMENT_VERSION: u32 = 1;
+
+/// Entire state of a workspace as a single JSON document.
+///
+/// Each section holds the stored values of one part of the IDE, named after their storage keys.
+/// A section is either complete or missing; missing sections are left at their defaults
+/// when the document is imported.
+/// The key store and the hash store hold secret seeds, so they are left out of documents
+/// that are exported without secrets.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub version: u32,
+    pub program: ProgramSection,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TransactionSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_data: Option<SignedDataSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_store: Option<KeyStoreSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_store: Option<HashStoreSection>,
+}
+
+/// Source files of the program, with the index of the file that is open in the editor.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProgramSection {
+    pub files: Vec<SourceFile>,
+    #[serde(default)]
+    pub active_file: usize,
+}
+
+/// Parameters of the spending transaction.
+///
+/// The transaction ID and the recipient are checked when the document is imported.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionSection {
+    pub txid: String,
+    pub vout: u32,
+    pub value: u64,
+    /// Address of the recipient, which is empty if there is none.
+    pub recipient: String,
+    pub fee: u64,
+    pub lock_time: u32,
+    pub sequence: u32,
+}
+
+/// Kind of data that the key store signs.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignedDataKind {
+    #[default]
+    SighashAll,
+    ThirtyTwoBytes,
+    HashPreimageBytes,
+    SimfonyFunction,
+}
+
+impl SignedDataKind {
+    const ALL: [Self; 4] = [
+        Self::SighashAll,
+        Self::ThirtyTwoBytes,
+        Self::HashPreimageBytes,
+        Self::SimfonyFunction,
+    ];
+
+    /// Return the stored value of the kind.
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::SighashAll => "sighash_all",
+            Self::ThirtyTwoBytes => "thirty_two_bytes",
+            Self::HashPreimageBytes => "hash_preimage_bytes",
+            Self::SimfonyFunction => "simfony_function",
+        }
+    }
+}
+
+impl FromStr for SignedDataKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.key() == s)
+            .ok_or(format!("Unknown kind of signed data: `{s}`"))
+    }
+}
+
+/// Data that the key store signs.
+///
+/// The bytes are checked when the document is imported.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignedDataSection {
+    pub signed_data_mode: SignedDataKind,
+    pub signed_data_bytes: String,
+    pub signed_data_preimage: String,
+    pub signed_data_function: String,
+}
+
+/// Seed of the signing keys, with the number of keys that are shown.
+///
+/// The seed is checked when the document is imported.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyStoreSection {
+    pub random_seed: String,
+    pub key_count: usize,
+}
+
+/// Seed of the hash preimages, with the number of preimages that are shown
+/// and the preimages that the user added, one per line.
+///
+/// The seed and the preimages are checked when the document is imported.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HashStoreSection {
+    pub random_seed: String,
+    pub hash_count: usize,
+    pub custom_preimages: String,
+}
+
+/// Return the value of `key` among the stored `values`.
+fn value(values: &[(&str, String)], key: &str) -> Result<String, String> {
+    values
+        .iter()
+        .find(|(existing, _)| *existing == key)
+        .map(|(_, value)| value.clone())
+        .ok_or(format!("Missing value `{key}`"))
+}
+
+/// Parse the value of `key` among the stored `values`.
+fn parse<T: FromStr>(values: &[(&str, String)], key: &str) -> Result<T, String> {
+    value(values, key)?
+        .parse()
+        .map_err(|_| format!("Invalid value `{key}`"))
+}
+
+impl TransactionSection {
+    /// Collect the section from the stored `values` with their keys.
+    pub fn from_pairs(values: &[(&str, String)]) -> Result<Self, String> {
+        Ok(Self {
+            txid: value(values, "txid")?,
+            vout: parse(values, "vout")?,
+            value: parse(values, "value")?,
+            recipient: value(values, "recipient")?,
+            fee: parse(values, "fee")?,
+            lock_time: parse(values, "lock_time")?,
+            sequence: parse(values, "sequence")?,
+        })
+    }
+
+    /// Return the stored values of the section with their keys.
+    pub fn pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("txid", self.txid.clone()),
+            ("vout", self.vout.to_string()),
+            ("value", self.value.to_string()),
+            ("recipient", self.recipient.clone()),
+            ("fee", self.fee.to_string()),
+            ("lock_time", self.lock_time.to_string()),
+            ("sequence", self.sequence.to_string()),
+        ]
+    }
+}
+
+impl SignedDataSection {
+    /// Collect the section from the stored `values` with their keys.
+    pub fn from_pairs(values: &[(&str, String)]) -> Result<Self, String> {
+        Ok(Self {
+            signed_data_mode: parse(values, "signed_data_mode")?,
+            signed_data_bytes: value(values, "signed_data_bytes")?,
+            signed_data_preimage: value(values, "signed_data_preimage")?,
+            signed_data_function: value(values, "signed_data_function")?,
+        })
+    }
+
+    /// Return the stored values of the section with their keys.
+    pub fn pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("signed_data_mode", self.signed_data_mode.key().to_string()),
+            ("signed_data_bytes", self.signed_data_bytes.clone()),
+            ("signed_data_preimage", self.signed_data_preimage.clone()),
+            ("signed_data_function", self.signed_data_function.clone()),
+        ]
+    }
+}
+
+impl KeyStoreSection {
+    /// Collect the section from the stored `values` with their keys.
+    pub fn from_pairs(values: &[(&str, String)]) -> Result<Self, String> {
+        Ok(Self {
+            random_seed: value(values, "random_seed")?,
+            key_count: parse(values, "key_count")?,
+        })
+    }
+
+    /// Return the stored values of the section with their keys.
+    pub fn pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("random_seed", self.random_seed.clone()),
+            ("key_count", self.key_count.to_string()),
+        ]
+    }
+}
+
+impl HashStoreSection {
+    /// Collect the section from the stored `values` with their keys.
+    pub fn from_pairs(values: &[(&str, String)]) -> Result<Self, String> {
+        Ok(Self {
+            random_seed: value(values, "random_seed")?,
+            hash_count: parse(values, "hash_count")?,
+            custom_preimages: value(values, "custom_preimages")?,
+        })
+    }
+
+    /// Return the stored values of the section with their keys.
+    pub fn pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("random_seed", self.random_seed.clone()),
+            ("hash_count", self.hash_count.to_string()),
+            ("custom_preimages", self.custom_preimages.clone()),
+        ]
+    }
+}
+
+impl Document {
+    /// Serialize the document as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("document should serialize")
+    }
+
+    /// Parse a document from JSON.
+    ///
+    /// Return an error if the JSON is ill-formatted, if a section is incomplete,
+    /// if the document has a newer version, or if the document has no program files.
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let document: Self = serde_json::from_str(s).map_err(|error| error.to_string())?;
+        if DOCUMENT_VERSION < document.version {
+            return Err(format!(
+                "Document version {} is newer than the supported version {DOCUMENT_VERSION}",
+                document.version
+            ));
+        }
+        if document.program.files.is_empty() {
+            return Err("Document contains no program files".to_string());
+        }
+        if document.program.files.len() <= document.program.active_file {
+            return Err("Document opens a program file that does not exist".to_string());
+        }
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn document_roundtrip() {
+        let document = Document {
+            version: DOCUMENT_VERSION,
+            program: ProgramSection {
+                files: vec![
+                    SourceFile::new("lib.simf", "fn f() {}"),
+                    SourceFile::new("main.simf", "fn main() {\n    f()\n}"),
+                ],
+                active_file: 1,
+            },
+            transaction: Some(TransactionSection {
+                fee: 1000,
+                ..Default::default()
+            }),
+            signed_data: Some(SignedDataSection {
+                signed_data_mode: SignedDataKind::SimfonyFunction,
+                ..Default::default()
+            }),
+            key_store: Some(KeyStoreSection {
+                random_seed: "1".to_string(),
+                key_count: 2,
+            }),
+            hash_store: None,
+        };
+        let json = document.to_json();
+        assert!(!json.contains("hash_store"));
+        assert_eq!(Ok(document.clone()), Document::from_json(&json));
+
+        let key_store = document.key_store.unwrap();
+        assert_eq!(
+            Ok(key_store.clone()),
+            KeyStoreSection::from_pairs(&key_store.pairs())
+        );
+        assert!(HashStoreSection::from_pairs(&key_store.pairs()).is_err());
+        let signed_data = document.signed_data.unwrap();
+        assert_eq!(
+            Ok(signed_data.clone()),
+            SignedDataSection::from_pairs(&signed_data.pairs())
+        );
+        assert!(json.contains(r#""signed_data_mode": "simfony_function""#));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn reject_documents() {
+        let minimal =
+            r#"{"version": 1, "program": {"files": [{"name": "main.simf", "text": ""}]}}"#;
+        let document = Document::from_json(minimal).unwrap();
+        assert_eq!(0, document.program.active_file);
+        assert_eq!(None, document.transaction);
+
+        let newer = r#"{"version": 2, "program": {"files": [{"name": "main.simf", "text": ""}]}}"#;
+        assert!(Document::from_json(newer).is_err());
+        let empty = r#"{"version": 1, "program": {"files": []}}"#;
+        assert!(Document::from_json(empty).is_err());
+        let out_of_bounds = r#"{"version": 1, "program": {"files": [{"name": "a", "text": ""}], "active_file": 1}}"#;
+        assert!(Document::from_json(out_of_bounds).is_err());
+        assert!(Document::from_json("fn main() {}").is_err());
+
+        // Incomplete sections are rejected instead of being reset on import
+        let partial = r#"{"version": 1, "program": {"files": [{"name": "a", "text": ""}]}, "transaction": {"fee": 1000}}"#;
+        let error = Document::from_json(partial).unwrap_err();
+        assert!(error.contains("missing field"), "{error}");
+
+        // Malformed values are rejected when the document is parsed
+        let key_store = |key_count: &str| {
+            format!(
+                r#"{{"version": 1, "program": {{"files": [{{"name": "a", "text": ""}}]}}, "key_store": {{"random_seed": "1", "key_count": {key_count}}}}}"#
+            )
+        };
+        assert!(Document::from_json(&key_store("2")).is_ok());
+        assert!(Document::from_json(&key_store(r#""two""#)).is_err());
+        assert!(Document::from_json(&key_store("-1")).is_err());
+        let signed_data = r#"{"version": 1, "program": {"files": [{"name": "a", "text": ""}]}, "signed_data": {"signed_data_mode": "everything", "signed_data_bytes": "", "signed_data_preimage": "", "signed_data_function": ""}}"#;
+        assert!(Document::from_json(signed_data).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use elements::hashes::hmac::{Hmac, HmacEngine};
+use elements::hashes::{sha256, Hash, HashEngine};
+use hex_conservative::{DisplayHex, FromHex};
+use simfony::elements;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::util::js_error;
+
+/// Prefix of encrypted values, which also serves as version tag.
+const PREFIX: &str = "enc2:";
+/// Prefix of values that were encrypted by an older version of the IDE.
+///
+/// These values were not bound to their storage key, and they cannot be decrypted anymore.
+const LEGACY_PREFIX: &str = "enc1:";
+/// Number of PBKDF2 iterations to derive a key from a passphrase.
+const PBKDF2_ITERATIONS: u32 = 600_000;
+/// Length of the salt that goes into key derivation.
+pub const SALT_LEN: usize = 16;
+/// Length of the nonce that goes into the encryption of each value.
+pub const NONCE_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+/// Plaintext of the verifier, which proves that a passphrase is correct.
+const VERIFIER_PLAINTEXT: &str = "passphrase verifier";
+/// Storage key that the verifier is bound to.
+const VERIFIER_STORAGE_KEY: &str = "encryption_verifier";
+
+/// Symmetric key that encrypts and authenticates secret values.
+///
+/// Values are encrypted with an HMAC-SHA256 key stream and authenticated with HMAC-SHA256
+/// (encrypt-then-MAC). The MAC covers the storage key of the value, so a value that is moved
+/// to another storage key fails to decrypt. The key is derived from a passphrase with
+/// PBKDF2-HMAC-SHA256, which the browser computes off the main thread.
+#[derive(Clone)]
+pub struct Key {
+    encryption_key: [u8; 32],
+    authentication_key: [u8; 32],
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+fn hmac(key: &[u8], chunks: &[&[u8]]) -> [u8; 32] {
+    let mut engine = HmacEngine::<sha256::Hash>::new(key);
+    for chunk in chunks {
+        engine.input(chunk);
+    }
+    Hmac::<sha256::Hash>::from_engine(engine).to_byte_array()
+}
+
+/// Compare two byte strings in constant time, so the timing leaks nothing about their contents.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Derive a master key from the given `passphrase` and `salt` using the browser's WebCrypto API.
+async fn pbkdf2(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let subtle = web_sys::window()
+        .and_then(|window| window.crypto().ok())
+        .ok_or("Browser provides no WebCrypto API".to_string())?
+        .subtle();
+    let usages = js_sys::Array::of1(&"deriveBits".into());
+    let passphrase = js_sys::Uint8Array::from(passphrase.as_bytes());
+    let base_key = subtle
+        .import_key_with_str("raw", &passphrase, "PBKDF2", false, &usages)
+        .map_err(js_error)?;
+    let base_key: web_sys::CryptoKey = JsFuture::from(base_key)
+        .await
+        .map_err(js_error)?
+        .unchecked_into();
+    let salt = js_sys::Uint8Array::from(salt);
+    let params = web_sys::Pbkdf2Params::new("PBKDF2", &"SHA-256".into(), PBKDF2_ITERATIONS, &salt);
+    let bits = subtle
+        .derive_bits_with_object(&params, &base_key, 256)
+        .map_err(js_error)?;
+    let bits = JsFuture::from(bits).await.map_err(js_error)?;
+    let mut master_key = [0; 32];
+    js_sys::Uint8Array::new(&bits).copy_to(&mut master_key);
+    Ok(master_key)
+}
+
+impl Key {
+    /// Derive a key from the given `passphrase` and `salt`.
+    ///
+    /// Return an error if the browser provides no WebCrypto API.
+    pub async fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, String> {
+        pbkdf2(passphrase, salt).await.map(Self::from_master_key)
+    }
+
+    /// Construct a key from the `master_key` that was derived from a passphrase.
+    pub fn from_master_key(master_key: [u8; 32]) -> Self {
+        Self {
+            encryption_key: hmac(&master_key, &[b"encryption"]),
+            authentication_key: hmac(&master_key, &[b"authentication"]),
+        }
+    }
+
+    fn tag(&self, storage_key: &str, nonce: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+        let storage_key_len = u32::try_from(storage_key.len())
+            .expect("storage key should be shorter than 4 GiB")
+            .to_be_bytes();
+        hmac(
+            &self.authentication_key,
+            &[&storage_key_len, storage_key.as_bytes(), nonce, ciphertext],
+        )
+    }
+
+    fn apply_key_stream(&self, nonce: &[u8], data: &mut [u8]) {
+        for (counter, block) in data.chunks_mut(32).enumerate() {
+            let counter = u32::try_from(counter).expect("data should be shorter than 128 GiB");
+            let key_stream = hmac(&self.encryption_key, &[nonce, &counter.to_be_bytes()]);
+            block
+                .iter_mut()
+                .zip(key_stream.iter())
+                .for_each(|(x, y)| *x ^= y);
+        }
+    }
+
+    /// Encrypt the `plaintext` using a fresh random `nonce`.
+    ///
+    /// The returned string can be written to the browser's local storage under `storage_key`.
+    pub fn encrypt(&self, storage_key: &str, plaintext: &str, nonce: [u8; NONCE_LEN]) -> String {
+        let mut ciphertext = plaintext.as_bytes().to_vec();
+        self.apply_key_stream(&nonce, &mut ciphertext);
+        let tag = self.tag(storage_key, &nonce, &ciphertext);
+        format!(
+            "{PREFIX}{}{}{}",
+            nonce.as_hex(),
+            ciphertext.as_hex(),
+            tag.as_hex()
+        )
+    }
+
+    /// Decrypt a string that was returned by [`Key::encrypt`] for the same `storage_key`.
+    ///
+    /// Return `None` if the string is ill-formatted, if it was encrypted under a different key
+    /// or if it was encrypted for a different storage key.
+    pub fn decrypt(&self, storage_key: &str, encrypted: &str) -> Option<String> {
+        let bytes = encrypted
+            .strip_prefix(PREFIX)
+            .and_then(|s| Vec::<u8>::from_hex(s).ok())?;
+        if bytes.len() < NONCE_LEN + TAG_LEN {
+            return None;
+        }
+        let (nonce, rest) = bytes.split_at(NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+        if !constant_time_eq(&self.tag(storage_key, nonce, ciphertext), tag) {
+            return None;
+        }
+        let mut plaintext = ciphertext.to_vec();
+        self.apply_key_stream(nonce, &mut plaintext);
+        String::from_utf8(plaintext).ok()
+    }
+
+    /// Return a verifier of the key using a fresh random `nonce`.
+    ///
+    /// The verifier is stored next to the salt, so a passphrase can be checked
+    /// before any secret value is encrypted.
+    pub fn verifier(&self, nonce: [u8; NONCE_LEN]) -> String {
+        self.encrypt(VERIFIER_STORAGE_KEY, VERIFIER_PLAINTEXT, nonce)
+    }
+
+    /// Check if the `verifier` was returned by [`Key::verifier`] of this key.
+    pub fn verify(&self, verifier: &str) -> bool {
+        self.decrypt(VERIFIER_STORAGE_KEY, verifier).as_deref() == Some(VERIFIER_PLAINTEXT)
+    }
+}
+
+/// Check if the given value was encrypted.
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX) || is_legacy(value)
+}
+
+/// Check if the given value was encrypted by an older version of the IDE.
+pub fn is_legacy(value: &str) -> bool {
+    value.starts_with(LEGACY_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn hmac_vectors() {
+        // RFC 4231, test cases 1 and 2
+        assert_eq!(
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            hmac(&[0x0b; 20], &[b"Hi There"]).to_lower_hex_string()
+        );
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            hmac(b"Jefe", &[b"what do ya want ", b"for nothing?"]).to_lower_hex_string()
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn constant_time_comparison() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"tag", b"tag"));
+        assert!(!constant_time_eq(b"tag", b"tab"));
+        assert!(!constant_time_eq(b"tag", b"tags"));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn encryption_roundtrip() {
+        let key = Key::from_master_key([0x42; 32]);
+        let plaintexts = [
+            "",
+            "0",
+            "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "more than thirty-two bytes of plaintext that span several key stream blocks",
+        ];
+
+        for (index, plaintext) in plaintexts.into_iter().enumerate() {
+            let encrypted = key.encrypt("Vault/random_seed", plaintext, [index as u8; NONCE_LEN]);
+            assert!(is_encrypted(&encrypted));
+            assert!(!is_legacy(&encrypted));
+            assert_eq!(
+                Some(plaintext),
+                key.decrypt("Vault/random_seed", &encrypted).as_deref()
+            );
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn wrong_passphrase() {
+        let key = Key::from_master_key([0x42; 32]);
+        let wrong_key = Key::from_master_key([0x43; 32]);
+        let encrypted = key.encrypt("Vault/random_seed", "secret", [0; NONCE_LEN]);
+
+        assert_eq!(None, wrong_key.decrypt("Vault/random_seed", &encrypted));
+        assert_eq!(None, key.decrypt("Vault/random_seed", "secret"));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn verify_passphrase() {
+        let key = Key::from_master_key([0x42; 32]);
+        let wrong_key = Key::from_master_key([0x43; 32]);
+        let verifier = key.verifier([0; NONCE_LEN]);
+
+        assert!(key.verify(&verifier));
+        assert!(!wrong_key.verify(&verifier));
+        assert!(!key.verify(&key.encrypt("encryption_verifier", "secret", [0; NONCE_LEN])));
+        assert!(!key.verify(&key.encrypt("Vault/random_seed", VERIFIER_PLAINTEXT, [0; NONCE_LEN])));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn swapped_values() {
+        let key = Key::from_master_key([0x42; 32]);
+        let signing_seed = key.encrypt("Vault/random_seed", "1", [0; NONCE_LEN]);
+        let preimages = key.encrypt("Vault/custom_preimages", "2", [1; NONCE_LEN]);
+
+        // Swapped within a workspace
+        assert_eq!(None, key.decrypt("Vault/random_seed", &preimages));
+        assert_eq!(None, key.decrypt("Vault/custom_preimages", &signing_seed));
+        // Moved to another workspace
+        assert_eq!(None, key.decrypt("Swap/random_seed", &signing_seed));
+        // Legacy values are recognized, but they cannot be decrypted
+        let legacy = signing_seed.replacen(PREFIX, LEGACY_PREFIX, 1);
+        assert!(is_encrypted(&legacy));
+        assert!(is_legacy(&legacy));
+        assert_eq!(None, key.decrypt("Vault/random_seed", &legacy));
+    }
+}
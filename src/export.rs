@@ -0,0 +1,155 @@
+use std::fmt::Write;
+
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+
+use crate::dag::Dag;
+
+/// Return how many parents refer to each node of the `dag`.
+fn parent_counts(dag: &Dag) -> Vec<usize> {
+    let mut counts = vec![0; dag.nodes().len()];
+    for node in dag.nodes() {
+        for &child in &node.children {
+            counts[child] += 1;
+        }
+    }
+    counts
+}
+
+/// Return a textual listing of the Simplicity program of the `dag`.
+///
+/// Each node is listed once on its own line, after its children, as its label `#id`,
+/// its combinator with the labels of its children, and its type.
+/// Nodes that are shared by several parents are marked as such.
+/// The root is listed last as `main`.
+pub fn listing(dag: &Dag) -> String {
+    let parent_counts = parent_counts(dag);
+    let mut listing = String::new();
+
+    for (id, node) in dag.nodes().iter().enumerate() {
+        let label = match id == dag.root() {
+            true => "main".to_string(),
+            false => format!("#{id}"),
+        };
+        let _ = write!(listing, "{label} := {}", node.name);
+        for child in &node.children {
+            let _ = write!(listing, " #{child}");
+        }
+        let _ = write!(listing, " : {} → {}", node.source_type, node.target_type);
+        if 1 < parent_counts[id] {
+            let _ = write!(listing, " -- shared by {} parents", parent_counts[id]);
+        }
+        listing.push('\n');
+    }
+
+    listing
+}
+
+/// Escape the given `text` for a quoted string of the DOT language.
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Return the Simplicity program of the `dag` as a Graphviz graph in the DOT language.
+///
+/// Shared nodes are drawn once with a double border, and they have an edge from each parent.
+/// Edges are labeled with the index of the child.
+pub fn dot(dag: &Dag) -> String {
+    let parent_counts = parent_counts(dag);
+    let mut dot = String::from("digraph simplicity {\n");
+    dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+
+    for (id, node) in dag.nodes().iter().enumerate() {
+        let label = escape_dot(&format!(
+            "#{id} {}\n{} → {}",
+            node.name, node.source_type, node.target_type
+        ))
+        .replace('\n', "\\n");
+        let shared = match 1 < parent_counts[id] {
+            true => ", peripheries=2",
+            false => "",
+        };
+        let _ = writeln!(dot, "    n{id} [label=\"{label}\"{shared}];");
+    }
+    for (id, node) in dag.nodes().iter().enumerate() {
+        for (index, child) in node.children.iter().enumerate() {
+            let _ = writeln!(dot, "    n{id} -> n{child} [label=\"{index}\"];");
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Standard base64 that is written with padding and read with or without padding.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Encode `bytes` as standard base64 with padding, which other Simplicity tools read.
+pub fn base64_encode(bytes: &[u8]) -> String {
+    BASE64.encode(bytes)
+}
+
+/// Decode standard base64 with or without padding.
+///
+/// Return `None` if the string is ill-formatted.
+pub fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    BASE64.decode(s).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples;
+    use crate::test_util::satisfied_and_tx_env;
+    use crate::util::{HashedData, SigningKeys};
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn base64_roundtrip() {
+        assert_eq!("", base64_encode(b""));
+        assert_eq!("Zg==", base64_encode(b"f"));
+        assert_eq!("Zm9vYg==", base64_encode(b"foob"));
+        assert_eq!("+/8=", base64_encode(&[0xfb, 0xff]));
+        for bytes in [&b""[..], b"f", b"fo", b"foo", &[0xfb, 0xff, 0x00, 0x10]] {
+            assert_eq!(Some(bytes.to_vec()), base64_decode(&base64_encode(bytes)));
+        }
+        assert_eq!(Some(b"foob".to_vec()), base64_decode("Zm9vYg"));
+        assert_eq!(None, base64_decode("-_8="));
+        assert_eq!(None, base64_decode("Z!=="));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn export_examples() {
+        let signing_keys = SigningKeys::default();
+        let hashed_data = HashedData::default();
+
+        for name in examples::keys() {
+            let example = examples::get(name).unwrap();
+            let (satisfied, _) = satisfied_and_tx_env(example, &signing_keys, &hashed_data);
+            let program = satisfied.redeem().clone();
+            let dag = Dag::new(&program, None);
+
+            let listing = listing(&dag);
+            let lines: Vec<&str> = listing.lines().collect();
+            assert_eq!(dag.nodes().len(), lines.len(), "{name}");
+            assert!(lines.last().unwrap().starts_with("main := "), "{name}");
+            assert!(lines[0].starts_with("#0 := "), "{name}");
+
+            let dot = dot(&dag);
+            let edge_count: usize = dag.nodes().iter().map(|node| node.children.len()).sum();
+            assert!(dot.starts_with("digraph simplicity {\n"), "{name}");
+            assert!(dot.ends_with("}\n"), "{name}");
+            assert_eq!(edge_count, dot.matches(" -> ").count(), "{name}");
+            assert_eq!(
+                dag.nodes().len(),
+                dot.matches("[label=\"#").count(),
+                "{name}"
+            );
+        }
+    }
+}
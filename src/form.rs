@@ -0,0 +1,437 @@
+use std::fmt;
+use std::ops::Range;
+
+use simfony::types::{TypeInner, UIntType};
+use simfony::ResolvedType;
+
+use crate::witness::{self, Module};
+
+/// Value of a witness or parameter, structured by its type so it can be edited in a form.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FormValue {
+    Boolean(bool),
+    /// Integer or hexadecimal literal, as the user entered it.
+    Literal(String),
+    /// Either value that keeps both sides, so switching sides does not lose the user's input.
+    Either {
+        is_right: bool,
+        left: Box<FormValue>,
+        right: Box<FormValue>,
+    },
+    /// Option value that keeps its inner value while it is `None`.
+    Option {
+        is_some: bool,
+        value: Box<FormValue>,
+    },
+    Tuple(Vec<FormValue>),
+    Array(Vec<FormValue>),
+    List(Vec<FormValue>),
+}
+
+fn is_byte_array(element: &ResolvedType, size: usize) -> bool {
+    matches!(element.as_inner(), TypeInner::UInt(UIntType::U8)) && 0 < size
+}
+
+impl FormValue {
+    /// Return a default value of the given type.
+    pub fn placeholder(ty: &ResolvedType) -> Self {
+        match ty.as_inner() {
+            TypeInner::Boolean => FormValue::Boolean(false),
+            TypeInner::UInt(..) => FormValue::Literal("0".to_string()),
+            TypeInner::Either(left, right) => FormValue::Either {
+                is_right: false,
+                left: Box::new(Self::placeholder(left)),
+                right: Box::new(Self::placeholder(right)),
+            },
+            TypeInner::Option(inner) => FormValue::Option {
+                is_some: false,
+                value: Box::new(Self::placeholder(inner)),
+            },
+            TypeInner::Tuple(elements) => FormValue::Tuple(
+                elements
+                    .iter()
+                    .map(|element| Self::placeholder(element))
+                    .collect(),
+            ),
+            TypeInner::Array(element, size) if is_byte_array(element, *size) => {
+                FormValue::Literal(format!("0x{}", "00".repeat(*size)))
+            }
+            TypeInner::Array(element, size) => {
+                FormValue::Array(vec![Self::placeholder(element); *size])
+            }
+            TypeInner::List(..) => FormValue::List(vec![]),
+        }
+    }
+
+    /// Parse a value of the given type from Simfony syntax.
+    pub fn parse(text: &str, ty: &ResolvedType) -> Result<Self, String> {
+        let mut parser = Parser { rest: text };
+        let value = parser.value(ty)?;
+        match parser.rest.trim().is_empty() {
+            true => Ok(value),
+            false => Err(format!("Unexpected `{}`", parser.rest.trim())),
+        }
+    }
+
+    /// Return the value at the given `path`.
+    ///
+    /// Each step of the path selects an element of a tuple, array or list.
+    /// For `Either` values, step 0 selects the left side and step 1 selects the right side.
+    /// For `Option` values, step 0 selects the inner value.
+    pub fn get(&self, path: &[usize]) -> Option<&Self> {
+        let (&step, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return Some(self),
+        };
+        let child = match self {
+            FormValue::Either { left, .. } if step == 0 => left.as_ref(),
+            FormValue::Either { right, .. } if step == 1 => right.as_ref(),
+            FormValue::Option { value, .. } if step == 0 => value.as_ref(),
+            FormValue::Tuple(elements) | FormValue::Array(elements) | FormValue::List(elements) => {
+                elements.get(step)?
+            }
+            _ => return None,
+        };
+        child.get(rest)
+    }
+
+    /// Return a mutable reference to the value at the given `path`.
+    ///
+    /// See [`FormValue::get`] for the meaning of the path.
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut Self> {
+        let (&step, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return Some(self),
+        };
+        let child = match self {
+            FormValue::Either { left, .. } if step == 0 => left.as_mut(),
+            FormValue::Either { right, .. } if step == 1 => right.as_mut(),
+            FormValue::Option { value, .. } if step == 0 => value.as_mut(),
+            FormValue::Tuple(elements) | FormValue::Array(elements) | FormValue::List(elements) => {
+                elements.get_mut(step)?
+            }
+            _ => return None,
+        };
+        child.get_mut(rest)
+    }
+}
+
+fn fmt_elements(f: &mut fmt::Formatter<'_>, elements: &[FormValue]) -> fmt::Result {
+    for (index, element) in elements.iter().enumerate() {
+        if 0 < index {
+            f.write_str(", ")?;
+        }
+        write!(f, "{element}")?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for FormValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormValue::Boolean(bit) => write!(f, "{bit}"),
+            FormValue::Literal(literal) => f.write_str(literal),
+            FormValue::Either {
+                is_right: false,
+                left,
+                ..
+            } => write!(f, "Left({left})"),
+            FormValue::Either {
+                is_right: true,
+                right,
+                ..
+            } => write!(f, "Right({right})"),
+            FormValue::Option { is_some: false, .. } => f.write_str("None"),
+            FormValue::Option {
+                is_some: true,
+                value,
+            } => write!(f, "Some({value})"),
+            FormValue::Tuple(elements) if elements.len() == 1 => write!(f, "({},)", elements[0]),
+            FormValue::Tuple(elements) => {
+                f.write_str("(")?;
+                fmt_elements(f, elements)?;
+                f.write_str(")")
+            }
+            FormValue::Array(elements) => {
+                f.write_str("[")?;
+                fmt_elements(f, elements)?;
+                f.write_str("]")
+            }
+            FormValue::List(elements) => {
+                f.write_str("list![")?;
+                fmt_elements(f, elements)?;
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+/// Parser of Simfony values, which is guided by the expected type.
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn eat(&mut self, token: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(format!(
+                "Expected `{token}`, found `{}`",
+                self.rest.chars().take(16).collect::<String>()
+            )),
+        }
+    }
+
+    fn literal(&mut self) -> Result<&'a str, String> {
+        self.rest = self.rest.trim_start();
+        let len = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        if len == 0 {
+            return Err("Expected a literal".to_string());
+        }
+        let (literal, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(literal)
+    }
+
+    fn wrapped(&mut self, ty: &'a ResolvedType) -> Result<FormValue, String> {
+        self.expect("(")?;
+        let value = self.value(ty)?;
+        self.expect(")")?;
+        Ok(value)
+    }
+
+    /// Parse comma-separated values until the `close` token.
+    fn sequence<F>(&mut self, close: &str, mut element_ty: F) -> Result<Vec<FormValue>, String>
+    where
+        F: FnMut(usize) -> Option<&'a ResolvedType>,
+    {
+        let mut elements = vec![];
+        while !self.eat(close) {
+            if !elements.is_empty() {
+                self.expect(",")?;
+                if self.eat(close) {
+                    break;
+                }
+            }
+            let ty = element_ty(elements.len()).ok_or("Too many elements")?;
+            elements.push(self.value(ty)?);
+        }
+        Ok(elements)
+    }
+
+    fn value(&mut self, ty: &'a ResolvedType) -> Result<FormValue, String> {
+        match ty.as_inner() {
+            TypeInner::Boolean => match self.literal()? {
+                "true" => Ok(FormValue::Boolean(true)),
+                "false" => Ok(FormValue::Boolean(false)),
+                literal => Err(format!("Expected `true` or `false`, found `{literal}`")),
+            },
+            TypeInner::UInt(..) => self
+                .literal()
+                .map(|literal| FormValue::Literal(literal.to_string())),
+            TypeInner::Either(left_ty, right_ty) => {
+                if self.eat("Left") {
+                    Ok(FormValue::Either {
+                        is_right: false,
+                        left: Box::new(self.wrapped(left_ty)?),
+                        right: Box::new(FormValue::placeholder(right_ty)),
+                    })
+                } else if self.eat("Right") {
+                    Ok(FormValue::Either {
+                        is_right: true,
+                        left: Box::new(FormValue::placeholder(left_ty)),
+                        right: Box::new(self.wrapped(right_ty)?),
+                    })
+                } else {
+                    Err("Expected `Left` or `Right`".to_string())
+                }
+            }
+            TypeInner::Option(inner_ty) => {
+                if self.eat("None") {
+                    Ok(FormValue::Option {
+                        is_some: false,
+                        value: Box::new(FormValue::placeholder(inner_ty)),
+                    })
+                } else if self.eat("Some") {
+                    Ok(FormValue::Option {
+                        is_some: true,
+                        value: Box::new(self.wrapped(inner_ty)?),
+                    })
+                } else {
+                    Err("Expected `None` or `Some`".to_string())
+                }
+            }
+            TypeInner::Tuple(element_tys) => {
+                self.expect("(")?;
+                let elements =
+                    self.sequence(")", |index| element_tys.get(index).map(|ty| &**ty))?;
+                match elements.len() == element_tys.len() {
+                    true => Ok(FormValue::Tuple(elements)),
+                    false => Err(format!("Expected {} tuple elements", element_tys.len())),
+                }
+            }
+            TypeInner::Array(element_ty, size) => {
+                if is_byte_array(element_ty, *size) && self.rest.trim_start().starts_with("0x") {
+                    let literal = self.literal()?;
+                    return match literal.len() == 2 + 2 * size {
+                        true => Ok(FormValue::Literal(literal.to_string())),
+                        false => Err(format!("Expected {size} bytes of hex")),
+                    };
+                }
+                self.expect("[")?;
+                let elements = self.sequence("]", |index| match index < *size {
+                    true => Some(&**element_ty),
+                    false => None,
+                })?;
+                match elements.len() == *size {
+                    true => Ok(FormValue::Array(elements)),
+                    false => Err(format!("Expected {size} array elements")),
+                }
+            }
+            TypeInner::List(element_ty, bound) => {
+                self.expect("list!")?;
+                self.expect("[")?;
+                let elements = self.sequence("]", |index| match index < bound.get() - 1 {
+                    true => Some(&**element_ty),
+                    false => None,
+                })?;
+                Ok(FormValue::List(elements))
+            }
+        }
+    }
+}
+
+/// Return the byte range of the value of the entry `name` in the given `module`
+/// of the program `text`.
+pub fn entry_value_range(text: &str, module: Module, name: &str) -> Option<Range<usize>> {
+    let body = witness::module_body(text, module)?;
+    let body_text = &text[body.clone()];
+    let (const_index, _) = body_text.match_indices("const").find(|(index, _)| {
+        let after_const = body_text[index + "const".len()..].trim_start();
+        after_const
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    })?;
+    let value_start = const_index + body_text[const_index..].find('=')? + 1;
+    let mut depth = 0;
+    let mut value_end = None;
+    for (index, c) in body_text[value_start..].char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ';' if depth == 0 => {
+                value_end = Some(value_start + index);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let value = &body_text[value_start..value_end?];
+    let start = body.start + value_start + (value.len() - value.trim_start().len());
+    let end = body.start + value_start + value.trim_end().len();
+    Some(start..end)
+}
+
+/// Return the form value of the entry `name` in the given `module` of the program `text`.
+///
+/// Return a placeholder if the entry is missing or cannot be parsed as the given type.
+pub fn entry_value(text: &str, module: Module, name: &str, ty: &ResolvedType) -> FormValue {
+    entry_value_range(text, module, name)
+        .and_then(|range| FormValue::parse(&text[range], ty).ok())
+        .unwrap_or_else(|| FormValue::placeholder(ty))
+}
+
+/// Write the form `value` into the entry `name` in the given `module` of the program `text`.
+///
+/// Create the entry if it does not exist.
+pub fn set_entry_value(
+    text: &mut String,
+    module: Module,
+    name: &str,
+    ty: &ResolvedType,
+    value: &FormValue,
+) {
+    match entry_value_range(text, module, name) {
+        Some(range) => text.replace_range(range, &value.to_string()),
+        None => {
+            let line = format!("    const {name}: {ty} = {value};\n");
+            witness::insert_into_module(text, module, &line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simfony::types::TypeConstructible;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn parse_display_roundtrip() {
+        let ty = ResolvedType::tuple([
+            ResolvedType::either(ResolvedType::u32(), ResolvedType::boolean()),
+            ResolvedType::option(ResolvedType::byte_array(2)),
+            ResolvedType::array(ResolvedType::u8(), 2),
+            ResolvedType::u256(),
+        ]);
+        let texts = [
+            "(Left(42), None, 0x0102, 0x00)",
+            "(Right(true), Some(0xffff), [1, 2], 1234)",
+        ];
+
+        for text in texts {
+            let value = FormValue::parse(text, &ty).expect("value should parse");
+            assert_eq!(text, value.to_string());
+        }
+
+        let placeholder = FormValue::placeholder(&ty);
+        assert_eq!("(Left(0), None, 0x0000, 0)", placeholder.to_string());
+        for text in ["(Left(42), None, 0x01, 0)", "(Left(42), None)", "Left(42)"] {
+            assert!(FormValue::parse(text, &ty).is_err());
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn edit_entry() {
+        let ty = ResolvedType::either(ResolvedType::u32(), ResolvedType::byte_array(2));
+        let mut text = r#"mod witness {
+    const A: Either<u32, [u8; 2]> = Left(1);
+}
+
+fn main() {}"#
+            .to_string();
+
+        let mut value = entry_value(&text, Module::Witness, "A", &ty);
+        *value.get_mut(&[0]).unwrap() = FormValue::Literal("2".to_string());
+        set_entry_value(&mut text, Module::Witness, "A", &ty, &value);
+        assert!(text.contains("const A: Either<u32, [u8; 2]> = Left(2);"));
+
+        if let FormValue::Either { is_right, .. } = &mut value {
+            *is_right = true;
+        }
+        set_entry_value(&mut text, Module::Witness, "A", &ty, &value);
+        assert!(text.contains("const A: Either<u32, [u8; 2]> = Right(0x0000);"));
+
+        set_entry_value(&mut text, Module::Witness, "B", &ty, &value);
+        assert!(text.contains("    const B: Either<u32, [u8; 2]> = Right(0x0000);\n}"));
+        assert_eq!(
+            value.to_string(),
+            entry_value(&text, Module::Witness, "B", &ty).to_string()
+        );
+    }
+}
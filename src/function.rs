@@ -1,18 +1,22 @@
+use std::collections::HashMap;
 use std::fmt;
+use std::ops::Range;
 use std::sync::Arc;
 
 use either::Either;
 use simfony::debug::{DebugSymbols, FallibleCall, FallibleCallName};
 use simfony::either;
+use simfony::parse::ParseFromStr;
 use simfony::simplicity::jet::elements::ElementsEnv;
-use simfony::SatisfiedProgram;
 use simfony::{elements, simplicity};
+use simfony::{CompiledProgram, SatisfiedProgram};
 use simplicity::node::Inner;
 use simplicity::types::Final;
-use simplicity::Value;
+use simplicity::{Imr, Value};
 
 use crate::jet;
 use crate::jet::JetFailed;
+use crate::syntax::{self, Token, TokenKind};
 use crate::util::Expression;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -61,6 +65,42 @@ enum Task {
     MakeRight(Arc<Final>),
     MakeProduct,
     ResetActiveSimfonyCall,
+    RecordOutput(Imr),
+}
+
+/// Values that a node saw while the program ran.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct NodeTrace {
+    /// Number of times the node was executed.
+    pub count: usize,
+    /// Input value of the first execution.
+    pub input: Option<Value>,
+    /// Output value of the first execution, if the execution finished.
+    pub output: Option<Value>,
+    /// For each child of the node, whether the node executed the child.
+    ///
+    /// This shows which branch a case took.
+    pub children: [bool; 2],
+}
+
+/// Record of the nodes that a run executed, keyed by their IMR.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Trace {
+    nodes: HashMap<Imr, NodeTrace>,
+    failed: Option<Imr>,
+}
+
+impl Trace {
+    /// Return how the node with the given `imr` was executed,
+    /// or `None` if it was not executed.
+    pub fn get(&self, imr: &Imr) -> Option<&NodeTrace> {
+        self.nodes.get(imr)
+    }
+
+    /// Return the IMR of the node where the run failed.
+    pub fn failed(&self) -> Option<Imr> {
+        self.failed
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -77,64 +117,120 @@ pub struct Runner {
     active_simfony_call: Option<FallibleCall>,
     /// Output from Simfony dbg! expressions, in order of occurrence.
     debug_output: Vec<String>,
+    /// Values of Simfony dbg! expressions, in order of occurrence.
+    debug_values: Vec<Value>,
+    /// Node that is currently executed.
+    current: Option<Imr>,
+    /// Record of the executed nodes.
+    trace: Trace,
 }
 
 impl Runner {
-    pub fn for_program(program: SatisfiedProgram) -> Self {
+    fn new(program: Arc<Expression>, debug_symbols: DebugSymbols) -> Self {
         Self {
-            tasks: vec![Task::Execute(program.redeem().clone())],
+            tasks: vec![Task::Execute(program)],
             input: vec![Value::unit()],
             output: vec![],
-            debug_symbols: program.debug_symbols().clone(),
+            debug_symbols,
             active_simfony_call: None,
             debug_output: vec![],
+            debug_values: vec![],
+            current: None,
+            trace: Trace::default(),
         }
     }
 
+    pub fn for_program(program: SatisfiedProgram) -> Self {
+        Self::new(program.redeem().clone(), program.debug_symbols().clone())
+    }
+
+    /// Create a runner for a Simplicity program without Simfony source,
+    /// which has no debug symbols.
+    pub fn for_redeem(program: Arc<Expression>) -> Self {
+        Self::new(program, DebugSymbols::default())
+    }
+
     pub fn debug_output(self) -> Vec<String> {
         self.debug_output
     }
 
+    /// Return the values of the Simfony dbg! expressions, in order of occurrence.
+    pub fn debug_values(&self) -> &[Value] {
+        &self.debug_values
+    }
+
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
     pub fn run(&mut self, env: &ElementsEnv<Arc<elements::Transaction>>) -> Result<(), ErrorKind> {
+        let result = self.run_tasks(env);
+        if result.is_err() {
+            self.trace.failed = self.current;
+        }
+        result
+    }
+
+    /// Push the task to execute the child at the given `index` of the `parent` node.
+    fn execute_child(&mut self, parent: Imr, index: usize, child: &Arc<Expression>) {
+        if let Some(trace) = self.trace.nodes.get_mut(&parent) {
+            trace.children[index] = true;
+        }
+        self.tasks.push(Task::Execute(Arc::clone(child)));
+    }
+
+    fn run_tasks(
+        &mut self,
+        env: &ElementsEnv<Arc<elements::Transaction>>,
+    ) -> Result<(), ErrorKind> {
         while let Some(task) = self.tasks.pop() {
             match task {
                 Task::Execute(expression) => {
                     let input = self.input.pop().unwrap();
+                    let imr = expression.imr();
+                    self.current = Some(imr);
+                    let trace = self.trace.nodes.entry(imr).or_default();
+                    trace.count += 1;
+                    if trace.count == 1 {
+                        trace.input = Some(input.shallow_clone());
+                        // The output is recorded after the node and its children are done
+                        self.tasks.push(Task::RecordOutput(imr));
+                    }
                     match expression.inner() {
                         Inner::Iden => self.output.push(input),
                         Inner::Unit => self.output.push(Value::unit()),
                         Inner::InjL(t) => {
                             let ty_r = expression.arrow().target.as_sum().unwrap().1;
                             self.tasks.push(Task::MakeLeft(Arc::new(ty_r.clone())));
-                            self.tasks.push(Task::Execute(Arc::clone(t)));
+                            self.execute_child(imr, 0, t);
                             self.input.push(input);
                         }
                         Inner::InjR(t) => {
                             let ty_l = expression.arrow().target.as_sum().unwrap().0;
                             self.tasks.push(Task::MakeRight(Arc::new(ty_l.clone())));
-                            self.tasks.push(Task::Execute(Arc::clone(t)));
+                            self.execute_child(imr, 0, t);
                             self.input.push(input);
                         }
                         Inner::Take(t) => {
                             let (a, _) = input.as_product().ok_or(ErrorKind::WrongType)?;
-                            self.tasks.push(Task::Execute(Arc::clone(t)));
+                            self.execute_child(imr, 0, t);
                             self.input.push(a.shallow_clone());
                         }
                         Inner::Drop(t) => {
                             let (_, b) = input.as_product().ok_or(ErrorKind::WrongType)?;
-                            self.tasks.push(Task::Execute(Arc::clone(t)));
+                            self.execute_child(imr, 0, t);
                             self.input.push(b.shallow_clone());
                         }
                         Inner::Comp(s, t) => {
-                            self.tasks.push(Task::Execute(Arc::clone(t)));
+                            self.execute_child(imr, 1, t);
                             self.tasks.push(Task::MoveLeftCompOutput);
-                            self.tasks.push(Task::Execute(Arc::clone(s)));
+                            self.execute_child(imr, 0, s);
                             self.input.push(input);
                         }
                         Inner::Pair(s, t) => {
                             self.tasks.push(Task::MakeProduct);
-                            self.tasks.push(Task::Execute(Arc::clone(t)));
-                            self.tasks.push(Task::Execute(Arc::clone(s)));
+                            self.execute_child(imr, 1, t);
+                            self.execute_child(imr, 0, s);
                             self.input.push(input.shallow_clone());
                             self.input.push(input);
                         }
@@ -159,6 +255,7 @@ impl Runner {
                                                 debug_value.value()
                                             );
                                             self.debug_output.push(s);
+                                            self.debug_values.push(c.shallow_clone());
                                         }
                                         None => {}
                                     }
@@ -168,7 +265,7 @@ impl Runner {
                             if let Some(a) = sum_a_b.as_left() {
                                 match expression.inner() {
                                     Inner::Case(s, _) | Inner::AssertL(s, _) => {
-                                        self.tasks.push(Task::Execute(Arc::clone(s)));
+                                        self.execute_child(imr, 0, s);
                                         self.input.push(Value::product(
                                             a.shallow_clone(),
                                             c.shallow_clone(),
@@ -182,7 +279,12 @@ impl Runner {
                             } else if let Some(b) = sum_a_b.as_right() {
                                 match expression.inner() {
                                     Inner::Case(_, t) | Inner::AssertR(_, t) => {
-                                        self.tasks.push(Task::Execute(Arc::clone(t)));
+                                        // The right branch is the only child of an assertr
+                                        let index = usize::from(matches!(
+                                            expression.inner(),
+                                            Inner::Case(..)
+                                        ));
+                                        self.execute_child(imr, index, t);
                                         self.input.push(Value::product(
                                             b.shallow_clone(),
                                             c.shallow_clone(),
@@ -199,9 +301,9 @@ impl Runner {
                         }
                         Inner::Disconnect(s, t) => {
                             self.tasks.push(Task::MakeProduct);
-                            self.tasks.push(Task::Execute(Arc::clone(t)));
+                            self.execute_child(imr, 1, t);
                             self.tasks.push(Task::MoveLeftDisconnectOutput);
-                            self.tasks.push(Task::Execute(Arc::clone(s)));
+                            self.execute_child(imr, 0, s);
                             let t_cmr = Value::u256(t.cmr().to_byte_array());
                             self.input.push(Value::product(t_cmr, input));
                         }
@@ -238,6 +340,12 @@ impl Runner {
                     self.output.push(Value::product(a, b));
                 }
                 Task::ResetActiveSimfonyCall => self.active_simfony_call = None,
+                Task::RecordOutput(imr) => {
+                    let output = self.output.last().map(Value::shallow_clone);
+                    if let Some(trace) = self.trace.nodes.get_mut(&imr) {
+                        trace.output = output;
+                    }
+                }
             }
         }
 
@@ -257,6 +365,120 @@ impl Runner {
     }
 }
 
+/// Name of the original main function
+/// while the program is evaluated with a different entry point.
+const ORIGINAL_MAIN: &str = "webide_original_main";
+
+/// Return the byte range of the name of the main function of the program `text`.
+///
+/// Comments and other identifiers called `main` are skipped.
+fn main_name_range(text: &str) -> Option<Range<usize>> {
+    let tokens: Vec<Token> = syntax::tokenize(text)
+        .into_iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+        .collect();
+    tokens
+        .windows(3)
+        .find(|window| window[0].text == "fn" && window[1].text == "main" && window[2].text == "(")
+        .map(|window| window[1].start..window[1].end())
+}
+
+/// Return the bits of the given `value`, from left to right.
+///
+/// Units have no bits, sums have the bit of their side followed by the bits of their content,
+/// and products have the bits of their left value followed by the bits of their right value.
+fn value_bits(value: Value, bits: &mut Vec<bool>) -> Option<()> {
+    if value.is_unit() {
+        return Some(());
+    }
+    if let Some((left, right)) = value.as_product() {
+        let (left, right) = (left.shallow_clone(), right.shallow_clone());
+        value_bits(left, bits)?;
+        return value_bits(right, bits);
+    }
+    if let Some(left) = value.as_left() {
+        bits.push(false);
+        return value_bits(left.shallow_clone(), bits);
+    }
+    let right = value.as_right()?;
+    bits.push(true);
+    value_bits(right.shallow_clone(), bits)
+}
+
+/// Return the big-endian bytes of the given `u256` value.
+fn u256_bytes(value: Value) -> Option<[u8; 32]> {
+    let mut bits = Vec::with_capacity(256);
+    value_bits(value, &mut bits)?;
+    if bits.len() != 256 {
+        return None;
+    }
+    let mut bytes = [0; 32];
+    for (index, bit) in bits.into_iter().enumerate() {
+        bytes[index / 8] |= u8::from(bit) << (7 - index % 8);
+    }
+    Some(bytes)
+}
+
+/// Nullary Simfony function that returns `u256`,
+/// compiled as the entry point of the program that defines it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct U256Function {
+    name: String,
+    program: Arc<Expression>,
+    debug_symbols: DebugSymbols,
+}
+
+impl U256Function {
+    /// Compile the function `function_name` of the Simfony program `program_text`.
+    ///
+    /// The main function of the program is replaced by a main function
+    /// that calls the given function and passes its output to `dbg!`.
+    /// The witness and parameter modules of the program are kept as they are.
+    pub fn compile(program_text: &str, function_name: &str) -> Result<Self, String> {
+        if function_name.is_empty()
+            || !function_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format!("`{function_name}` is not a valid function name"));
+        }
+        let mut text = program_text.to_string();
+        if let Some(range) = main_name_range(&text) {
+            text.replace_range(range, ORIGINAL_MAIN);
+        }
+        text.push_str(&format!(
+            "\n\nfn main() {{\n    let message: u256 = dbg!({function_name}());\n}}\n"
+        ));
+
+        let arguments =
+            simfony::Arguments::parse_from_str(&text).map_err(|error| error.to_string())?;
+        let compiled = CompiledProgram::new(text.as_str(), arguments)?;
+        let witness =
+            simfony::WitnessValues::parse_from_str(&text).map_err(|error| error.to_string())?;
+        let satisfied = compiled.satisfy(witness)?;
+        Ok(Self {
+            name: function_name.to_string(),
+            program: satisfied.redeem().clone(),
+            debug_symbols: satisfied.debug_symbols().clone(),
+        })
+    }
+
+    /// Evaluate the function in the environment `env` and return its output.
+    pub fn evaluate(
+        &self,
+        env: &ElementsEnv<Arc<elements::Transaction>>,
+    ) -> Result<[u8; 32], String> {
+        let mut runner = Runner::new(Arc::clone(&self.program), self.debug_symbols.clone());
+        runner.run(env).map_err(|error| error.to_string())?;
+        let output = runner
+            .debug_values()
+            .last()
+            .ok_or_else(|| format!("`{}` produced no output", self.name))?;
+        u256_bytes(output.shallow_clone())
+            .ok_or_else(|| format!("`{}` should return `u256`", self.name))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use simfony::elements::{hashes::Hash, secp256k1_zkp as secp256k1};
@@ -264,30 +486,16 @@ mod tests {
 
     use super::*;
     use crate::examples;
-    use crate::examples::Example;
+    use crate::test_util::satisfied_and_tx_env;
+    use crate::transaction::TxParams;
     use crate::util::{HashedData, SigningKeys};
 
-    fn satisfied_and_tx_env(
-        example: Example,
-        signing_keys: &SigningKeys,
-        hashed_data: &HashedData,
-    ) -> (SatisfiedProgram, ElementsEnv<Arc<elements::Transaction>>) {
-        let arguments = example.arguments(&signing_keys.public_keys, &hashed_data.hashes);
-        let compiled = CompiledProgram::new(example.template_text(), arguments)
-            .expect("example should compile");
-        let tx_env = example.params().tx_env(compiled.commit().cmr());
-        let sighash_all =
-            secp256k1::Message::from_digest(tx_env.c_tx_env().sighash_all().to_byte_array());
-        let witness = example.witness(
-            &signing_keys.secret_keys,
-            &hashed_data.preimages,
-            sighash_all,
-        );
-        let satisfied = compiled
-            .satisfy(witness)
-            .expect("example should be satisfied");
-
-        (satisfied, tx_env)
+    fn evaluate(
+        program_text: &str,
+        function_name: &str,
+        env: &ElementsEnv<Arc<elements::Transaction>>,
+    ) -> Result<[u8; 32], String> {
+        U256Function::compile(program_text, function_name)?.evaluate(env)
     }
 
     #[test]
@@ -311,6 +519,82 @@ mod tests {
         }
     }
 
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn evaluate_sighash_function() {
+        let signing_keys = SigningKeys::default();
+        let hashed_data = HashedData::default();
+        let example = examples::get("✍️️ P2PK").unwrap();
+        let (_, tx_env) = satisfied_and_tx_env(example, &signing_keys, &hashed_data);
+        let sighash_all =
+            secp256k1::Message::from_digest(tx_env.c_tx_env().sighash_all().to_byte_array());
+        let witness = example.witness(
+            &signing_keys.secret_keys,
+            &hashed_data.preimages,
+            sighash_all,
+        );
+        let arguments = example.arguments(&signing_keys.public_keys, &hashed_data.hashes);
+        let program_text = format!(
+            "{witness}\n\n{arguments}\n\nfn sighash() -> u256 {{\n    jet::sig_all_hash()\n}}\n\n{}",
+            example.template_text()
+        );
+
+        let message =
+            evaluate(&program_text, "sighash", &tx_env).expect("function should evaluate");
+        assert_eq!(tx_env.c_tx_env().sighash_all().to_byte_array(), message);
+        assert!(evaluate(&program_text, "sighash()", &tx_env).is_err());
+
+        // Only the definition of main is renamed, not comments that mention it
+        let commented_text = format!("// fn main() is the entry point\n{program_text}");
+        assert_eq!(Ok(message), evaluate(&commented_text, "sighash", &tx_env));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn evaluate_constant_function() {
+        let text = "fn one() -> u256 {\n    0x0000000000000000000000000000000000000000000000000000000000000001\n}\n\nfn not_u256() -> u32 {\n    1\n}\n\nfn main() {\n    assert!(false);\n}";
+        let env = TxParams::default().tx_env(simplicity::Cmr::unit());
+        let mut expected = [0; 32];
+        expected[31] = 1;
+        assert_eq!(Ok(expected), evaluate(text, "one", &env));
+        assert!(evaluate(text, "not_u256", &env).is_err());
+        assert!(evaluate(text, "missing", &env).is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn trace_run() {
+        let run = |a: u32| {
+            let text = format!(
+                "mod witness {{\n    const A: u32 = {a};\n}}\n\nfn main() {{\n    assert!(jet::eq_32(witness::A, 1));\n}}"
+            );
+            let arguments = simfony::Arguments::parse_from_str(&text).unwrap();
+            let compiled = CompiledProgram::new(text.as_str(), arguments).unwrap();
+            let env = TxParams::default().tx_env(compiled.commit().cmr());
+            let witness = simfony::WitnessValues::parse_from_str(&text).unwrap();
+            let satisfied = compiled.satisfy(witness).unwrap();
+            let program = satisfied.redeem().clone();
+            let mut runner = Runner::for_program(satisfied);
+            let result = runner.run(&env);
+            (program, result, runner.trace().clone())
+        };
+
+        let (program, result, trace) = run(1);
+        assert!(result.is_ok());
+        assert_eq!(None, trace.failed());
+        let root = trace.get(&program.imr()).unwrap();
+        assert_eq!(1, root.count);
+        assert_eq!(Some(Value::unit()), root.input);
+        assert_eq!(Some(Value::unit()), root.output);
+
+        let (program, result, trace) = run(2);
+        assert!(result.is_err());
+        let failed = trace.get(&trace.failed().unwrap()).unwrap();
+        assert!(failed.input.is_some());
+        assert_eq!(None, failed.output);
+        assert_eq!(None, trace.get(&program.imr()).unwrap().output);
+    }
+
     #[test]
     #[wasm_bindgen_test::wasm_bindgen_test]
     fn compare_with_rust_simplicity() {
@@ -0,0 +1,275 @@
+use std::fmt;
+use std::str::FromStr;
+
+use elements::hashes::{hash160, sha256, sha256d, Hash, HashEngine};
+use hex_conservative::{DisplayHex, FromHex};
+use simfony::elements;
+
+/// Hash function of the hash store.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum HashFunction {
+    /// SHA-256.
+    Sha256,
+    /// SHA-256 applied twice.
+    DoubleSha256,
+    /// RIPEMD-160 of SHA-256.
+    Hash160,
+    /// BIP-340 tagged SHA-256 with the given tag.
+    TaggedSha256(String),
+}
+
+impl fmt::Display for HashFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashFunction::Sha256 => f.write_str("SHA-256"),
+            HashFunction::DoubleSha256 => f.write_str("double SHA-256"),
+            HashFunction::Hash160 => f.write_str("HASH160"),
+            HashFunction::TaggedSha256(tag) => write!(f, "tagged SHA-256 ({tag})"),
+        }
+    }
+}
+
+/// Compute the bytes that a tagged hash prepends to its message.
+fn tag_prefix(tag: &str) -> [u8; 64] {
+    let tag_hash = sha256::Hash::hash(tag.as_bytes()).to_byte_array();
+    let mut prefix = [0; 64];
+    prefix[..32].copy_from_slice(&tag_hash);
+    prefix[32..].copy_from_slice(&tag_hash);
+    prefix
+}
+
+impl HashFunction {
+    /// Hash the given `preimage`.
+    ///
+    /// The bytes are returned in the order in which the hash function outputs them.
+    /// This is also the order in which Simfony jets output them.
+    pub fn hash(&self, preimage: &[u8]) -> Vec<u8> {
+        match self {
+            HashFunction::Sha256 => sha256::Hash::hash(preimage).to_byte_array().to_vec(),
+            HashFunction::DoubleSha256 => sha256d::Hash::hash(preimage).to_byte_array().to_vec(),
+            HashFunction::Hash160 => hash160::Hash::hash(preimage).to_byte_array().to_vec(),
+            HashFunction::TaggedSha256(..) => {
+                let mut engine = sha256::Hash::engine();
+                engine.input(&self.sha256_input_prefix());
+                engine.input(preimage);
+                sha256::Hash::from_engine(engine).to_byte_array().to_vec()
+            }
+        }
+    }
+
+    /// Return the bytes that the (first) SHA-256 computation processes before the preimage.
+    pub fn sha256_input_prefix(&self) -> Vec<u8> {
+        match self {
+            HashFunction::TaggedSha256(tag) => tag_prefix(tag).to_vec(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Return the given `bytes` as a Simfony value.
+///
+/// Byte strings whose length is a power of two up to 32 bytes become unsigned integers,
+/// such as `u256` for 32 bytes. Other byte strings become arrays of type `[u8; N]`.
+pub fn simfony_bytes(bytes: &[u8]) -> String {
+    match bytes.len() {
+        1 | 2 | 4 | 8 | 16 | 32 => format!("0x{}", bytes.as_hex()),
+        _ => {
+            let elements: Vec<String> = bytes.iter().map(|byte| format!("0x{byte:02x}")).collect();
+            format!("[{}]", elements.join(", "))
+        }
+    }
+}
+
+/// Preimage that the user entered into the hash store, with the function that hashes it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CustomPreimage {
+    pub preimage: Vec<u8>,
+    pub function: HashFunction,
+}
+
+impl CustomPreimage {
+    /// Hash the preimage.
+    pub fn hash(&self) -> Vec<u8> {
+        self.function.hash(&self.preimage)
+    }
+}
+
+/// Custom preimages are written as the hash function followed by the preimage in hex,
+/// such as `sha256 00ff` or `tagged:<tag in hex> 00ff`.
+impl fmt::Display for CustomPreimage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.function {
+            HashFunction::Sha256 => f.write_str("sha256")?,
+            HashFunction::DoubleSha256 => f.write_str("sha256d")?,
+            HashFunction::Hash160 => f.write_str("hash160")?,
+            HashFunction::TaggedSha256(tag) => write!(f, "tagged:{}", tag.as_bytes().as_hex())?,
+        }
+        write!(f, " {}", self.preimage.as_hex())
+    }
+}
+
+impl FromStr for CustomPreimage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (function, preimage) = s
+            .split_once(' ')
+            .ok_or("Custom preimage must consist of a hash function and a preimage")?;
+        let function = match function {
+            "sha256" => HashFunction::Sha256,
+            "sha256d" => HashFunction::DoubleSha256,
+            "hash160" => HashFunction::Hash160,
+            _ => {
+                let tag = function
+                    .strip_prefix("tagged:")
+                    .and_then(|tag| Vec::<u8>::from_hex(tag).ok())
+                    .and_then(|tag| String::from_utf8(tag).ok())
+                    .ok_or(format!("Unknown hash function: {function}"))?;
+                HashFunction::TaggedSha256(tag)
+            }
+        };
+        let preimage =
+            Vec::<u8>::from_hex(preimage).map_err(|_| "Preimage must be hex".to_string())?;
+        Ok(Self { preimage, function })
+    }
+}
+
+/// State of a streaming SHA-256 computation, as seen by the `sha_256_ctx_8_*` jets.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sha256Trace {
+    /// Midstate after each complete 64-byte block, in order.
+    pub midstates: Vec<sha256::Midstate>,
+    /// Bytes that have not yet been compressed into the midstate.
+    pub buffer: Vec<u8>,
+    /// Total number of bytes that have been added to the hash.
+    pub length: u64,
+}
+
+impl Sha256Trace {
+    /// Trace the SHA-256 computation over the given `data`.
+    pub fn new(data: &[u8]) -> Self {
+        let mut engine = sha256::Hash::engine();
+        let mut midstates = Vec::with_capacity(data.len() / 64);
+        let mut blocks = data.chunks_exact(64);
+        for block in blocks.by_ref() {
+            engine.input(block);
+            midstates.push(engine.midstate());
+        }
+        Self {
+            midstates,
+            buffer: blocks.remainder().to_vec(),
+            length: data.len() as u64,
+        }
+    }
+
+    /// Return the initial value of SHA-256 if no block has been compressed yet.
+    /// Otherwise, return the midstate after the last complete block.
+    pub fn current_midstate(&self) -> sha256::Midstate {
+        self.midstates
+            .last()
+            .copied()
+            .unwrap_or_else(|| sha256::Hash::engine().midstate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn hash_empty_preimage() {
+        let functions_hashes = [
+            (
+                HashFunction::Sha256,
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                HashFunction::DoubleSha256,
+                "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456",
+            ),
+            (
+                HashFunction::Hash160,
+                "b472a266d0bd89c13706a4132ccfb16f7c3b9fcb",
+            ),
+        ];
+
+        for (function, expected_hash) in functions_hashes {
+            assert_eq!(expected_hash, function.hash(&[]).as_hex().to_string());
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn tagged_hash() {
+        let function = HashFunction::TaggedSha256("TapLeaf".to_string());
+        let preimage = b"Simfony";
+        let mut expected = tag_prefix("TapLeaf").to_vec();
+        expected.extend_from_slice(preimage);
+
+        assert_eq!(
+            sha256::Hash::hash(&expected).to_byte_array().to_vec(),
+            function.hash(preimage)
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn resume_from_midstate() {
+        for len in [0, 1, 63, 64, 65, 130, 200] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let trace = Sha256Trace::new(&data);
+            assert_eq!(len / 64, trace.midstates.len());
+            assert_eq!(len % 64, trace.buffer.len());
+
+            let mut engine = sha256::HashEngine::from_midstate(
+                trace.current_midstate(),
+                (trace.length as usize) - trace.buffer.len(),
+            );
+            engine.input(&trace.buffer);
+            assert_eq!(sha256::Hash::hash(&data), sha256::Hash::from_engine(engine));
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn custom_preimage_roundtrip() {
+        let preimages = [
+            CustomPreimage {
+                preimage: vec![],
+                function: HashFunction::Sha256,
+            },
+            CustomPreimage {
+                preimage: vec![0x00, 0xff],
+                function: HashFunction::DoubleSha256,
+            },
+            CustomPreimage {
+                preimage: vec![0x01; 33],
+                function: HashFunction::Hash160,
+            },
+            CustomPreimage {
+                preimage: vec![0x02],
+                function: HashFunction::TaggedSha256("Tap Leaf: ✓".to_string()),
+            },
+        ];
+        for preimage in preimages {
+            let s = preimage.to_string();
+            assert!(!s.contains('\n'));
+            assert_eq!(Ok(preimage), s.parse::<CustomPreimage>());
+        }
+        for s in ["", "sha256", "md5 00", "sha256 0", "tagged:zz 00"] {
+            assert!(s.parse::<CustomPreimage>().is_err());
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn bytes_as_simfony_values() {
+        assert_eq!("0xab", simfony_bytes(&[0xab]));
+        assert_eq!("0x0001", simfony_bytes(&[0x00, 0x01]));
+        assert_eq!(format!("0x{}", "00".repeat(32)), simfony_bytes(&[0; 32]));
+        assert_eq!("[]", simfony_bytes(&[]));
+        assert_eq!("[0x01, 0x02, 0x03]", simfony_bytes(&[1, 2, 3]));
+        assert_eq!(20, simfony_bytes(&[0; 20]).matches("0x00").count());
+    }
+}
@@ -0,0 +1,464 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use simfony::simplicity::jet::{Elements, Jet};
+use simfony::types::TypeInner;
+use simfony::ResolvedType;
+
+use crate::completion;
+use crate::hash::CustomPreimage;
+use crate::syntax::{self, Token, TokenKind};
+use crate::witness::{self, Module, Symbol};
+
+/// Information about a name in the program text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hover {
+    /// Byte range of the name.
+    pub range: Range<usize>,
+    /// Name with its type, or signature.
+    pub signature: String,
+    /// Additional information, such as the cost of a jet.
+    pub detail: Option<String>,
+}
+
+/// Variable that a pattern binds to a type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Binding {
+    pub name: String,
+    /// Byte range of the name inside the pattern.
+    pub range: Range<usize>,
+    pub ty: String,
+}
+
+/// Return the byte offset of the subslice `part` inside `text`.
+fn offset_in(text: &str, part: &str) -> usize {
+    part.as_ptr() as usize - text.as_ptr() as usize
+}
+
+/// Return the tokens of `text` without whitespace and comments.
+fn significant_tokens(text: &str) -> Vec<Token<'_>> {
+    syntax::tokenize(text)
+        .into_iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+        .collect()
+}
+
+/// Return the byte index of the first character in `text` that satisfies `predicate`
+/// and that is not nested inside brackets.
+fn find_top_level(text: &str, predicate: impl Fn(char) -> bool) -> Option<usize> {
+    let mut depth = 0i32;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' | '>' => depth -= 1,
+            c if depth == 0 && predicate(c) => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split the contents of `text` between the brackets `open` and `close` at top-level commas.
+fn split_inner(text: &str, open: char, close: char) -> Option<Vec<&str>> {
+    text.strip_prefix(open)
+        .and_then(|text| text.strip_suffix(close))
+        .map(syntax::split_top_level)
+}
+
+/// Name prefix of the witness values that stand for types in [`resolve_types`].
+const TYPE_PROBE: &str = "WEBIDE_TYPE_";
+
+/// Resolve the type expressions `tys` of the program `text` with the Simfony compiler,
+/// which expands the type aliases of the program.
+///
+/// The compiler resolves the types of witness declarations only,
+/// so the types are declared in a separate program with the type aliases of `text`.
+/// Types that the compiler cannot resolve are `None`.
+fn resolve_types(text: &str, tys: &[&str]) -> Vec<Option<ResolvedType>> {
+    let aliases: String = completion::type_alias_definitions(text)
+        .into_iter()
+        .map(|(name, definition)| format!("type {name} = {definition};\n"))
+        .collect();
+    // Each type is the type of a witness value, which the compiler reports
+    let probe = |tys: &[&str]| -> Option<Vec<ResolvedType>> {
+        let statements: String = tys
+            .iter()
+            .enumerate()
+            .map(|(index, ty)| format!("    let _: {ty} = witness::{TYPE_PROBE}{index};\n"))
+            .collect();
+        let program = format!("{aliases}\nfn main() {{\n{statements}}}\n");
+        let declarations = witness::declarations(&program, &[]).ok()?;
+        (0..tys.len())
+            .map(|index| {
+                let name = format!("{TYPE_PROBE}{index}");
+                declarations
+                    .iter()
+                    .find(|(_, entry_name, _)| *entry_name == name)
+                    .map(|(.., ty)| ty.clone())
+            })
+            .collect()
+    };
+    match probe(tys) {
+        Some(resolved) => resolved.into_iter().map(Some).collect(),
+        // Resolve each type on its own, so one invalid type does not hide the others
+        None => tys
+            .iter()
+            .map(|ty| probe(&[ty]).and_then(|resolved| resolved.into_iter().next()))
+            .collect(),
+    }
+}
+
+/// Bind the variables of `pattern` to the corresponding components of the resolved type `ty`.
+///
+/// `pattern` is a subslice of `text`.
+/// Variables show their type as `written` in the program if the program spells out their component,
+/// and as the resolved type otherwise, such as for components of type aliases.
+fn bind_pattern(
+    text: &str,
+    pattern: &str,
+    written: Option<&str>,
+    ty: &ResolvedType,
+    bindings: &mut Vec<Binding>,
+) {
+    let pattern = pattern.trim();
+    let written = written.map(str::trim);
+    if let Some(patterns) = split_inner(pattern, '(', ')') {
+        if let TypeInner::Tuple(tys) = ty.as_inner() {
+            if tys.len() == patterns.len() {
+                let written = written
+                    .and_then(|written| split_inner(written, '(', ')'))
+                    .filter(|written| written.len() == patterns.len());
+                for (index, (pattern, ty)) in patterns.into_iter().zip(tys).enumerate() {
+                    let written = written.as_ref().map(|written| written[index]);
+                    bind_pattern(text, pattern, written, ty, bindings);
+                }
+            }
+        }
+    } else if let Some(patterns) = split_inner(pattern, '[', ']') {
+        if let TypeInner::Array(element_ty, _) = ty.as_inner() {
+            let written = written
+                .and_then(|written| written.strip_prefix('['))
+                .and_then(|written| written.strip_suffix(']'))
+                .and_then(|written| written.rsplit_once(';'))
+                .map(|(element, _)| element);
+            for pattern in patterns {
+                bind_pattern(text, pattern, written, element_ty, bindings);
+            }
+        }
+    } else {
+        let ty = written.map_or_else(|| ty.to_string(), str::to_string);
+        bind_name(text, pattern, ty, bindings);
+    }
+}
+
+/// Bind the variable `pattern` to the type `ty`, unless the pattern is not a single variable.
+///
+/// `pattern` is a subslice of `text`.
+fn bind_name(text: &str, pattern: &str, ty: String, bindings: &mut Vec<Binding>) {
+    let pattern = pattern.trim();
+    if pattern != "_"
+        && !pattern.is_empty()
+        && pattern
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        let start = offset_in(text, pattern);
+        bindings.push(Binding {
+            name: pattern.to_string(),
+            range: start..start + pattern.len(),
+            ty,
+        });
+    }
+}
+
+/// Return the variables that are bound inside the function that encloses byte offset `until`,
+/// in the order in which they are bound up to that offset.
+///
+/// Variables are bound by function parameters, `let` statements and match arms.
+/// Their types are resolved by the Simfony compiler, so variables inside type aliases have types.
+pub fn bindings(text: &str, until: usize) -> Vec<Binding> {
+    let tokens = significant_tokens(text);
+    let scope_start = tokens
+        .iter()
+        .rfind(|token| token.text == "fn" && token.start < until)
+        .map_or(0, |token| token.start);
+    // Patterns with the types that annotate them, as subslices of the text
+    let mut annotated: Vec<(&str, &str)> = vec![];
+
+    for (index, token) in tokens.iter().enumerate() {
+        if token.start < scope_start || until <= token.start {
+            continue;
+        }
+        match (token.kind, token.text) {
+            (TokenKind::Keyword, "let") => {
+                let statement = match tokens.get(index + 1) {
+                    Some(next) => &text[next.start..],
+                    None => continue,
+                };
+                let colon = find_top_level(statement, |c| c == ':');
+                let equals = find_top_level(statement, |c| c == '=' || c == ';');
+                if let (Some(colon), Some(equals)) = (colon, equals) {
+                    if colon < equals {
+                        annotated.push((&statement[..colon], &statement[colon + 1..equals]));
+                    }
+                }
+            }
+            (TokenKind::Keyword, "fn") | (TokenKind::Constant, "Left" | "Right" | "Some") => {
+                let open = match tokens.get(index + 1 + usize::from(token.text == "fn")) {
+                    Some(open) if open.text == "(" => open,
+                    _ => continue,
+                };
+                let close = match syntax::matching_bracket(&tokens, open.start) {
+                    Some(close) => close,
+                    None => continue,
+                };
+                annotated.extend(
+                    syntax::split_top_level(&text[open.end()..close])
+                        .into_iter()
+                        .filter_map(|param| param.split_once(':')),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let tys: Vec<&str> = annotated.iter().map(|(_, ty)| ty.trim()).collect();
+    let mut bindings = vec![];
+    for ((pattern, written), ty) in annotated.iter().zip(resolve_types(text, &tys)) {
+        match ty {
+            Some(ty) => bind_pattern(text, pattern, Some(written), &ty, &mut bindings),
+            // Without the resolved type, only single variables are bound
+            None => bind_name(text, pattern, written.trim().to_string(), &mut bindings),
+        }
+    }
+    bindings
+}
+
+/// Detail of the hover of a variable, which states that only names have hovers.
+const DECLARED_TYPE_DETAIL: &str = "Declared type. Other expressions show no type.";
+
+/// Return the module that the witness token `text` refers to, and the referred name.
+fn entry_of(text: &str) -> Option<(Module, &str)> {
+    match text.split_once("::")? {
+        ("witness", name) => Some((Module::Witness, name)),
+        ("param", name) => Some((Module::Param, name)),
+        _ => None,
+    }
+}
+
+/// Return the type or signature of the name at byte offset `offset` of the program `text`.
+///
+/// Jets also come with their cost.
+/// Only names have hovers: Simfony does not expose the types that it infers for other expressions,
+/// so variables show the types that the program declares for them.
+/// Symbols are typed after the `custom_preimages` of the hash store.
+pub fn hover(text: &str, offset: usize, custom_preimages: &[CustomPreimage]) -> Option<Hover> {
+    let tokens = syntax::tokenize(text);
+    let token = tokens
+        .iter()
+        .find(|token| token.start <= offset && offset < token.end())?;
+
+    let (signature, detail) = match token.kind {
+        TokenKind::Jet => {
+            let jet = Elements::from_str(token.text.strip_prefix("jet::")?).ok()?;
+            let params = simfony::jet::source_type(jet).iter().join(", ");
+            let output = simfony::jet::target_type(jet);
+            let signature = format!("{}: fn({params}) -> {output}", token.text);
+            (signature, Some(format!("Cost: {}", jet.cost())))
+        }
+        TokenKind::Function => {
+            let signature = completion::function_signatures(text)
+                .into_iter()
+                .find(|signature| signature.name == token.text)?;
+            (signature.to_string(), None)
+        }
+        TokenKind::Type => {
+            let definition = completion::type_alias_definitions(text)
+                .into_iter()
+                .find(|(name, _)| name == token.text);
+            match definition {
+                Some((name, definition)) => (format!("type {name} = {definition}"), None),
+                None => (
+                    format!("type {}", token.text),
+                    Some("Built-in type".to_string()),
+                ),
+            }
+        }
+        TokenKind::Witness => match entry_of(token.text) {
+            Some((module, name)) => {
+                let ty = witness::declarations(text, custom_preimages)
+                    .ok()?
+                    .into_iter()
+                    .find(|(entry_module, entry_name, _)| {
+                        *entry_module == module && entry_name == name
+                    })
+                    .map(|(.., ty)| ty)?;
+                (format!("{}: {ty}", token.text), None)
+            }
+            None => {
+                let symbol = Symbol::from_token(token.text).ok()?;
+                let signature = format!("{}: {}", token.text, symbol.ty(custom_preimages));
+                (
+                    signature,
+                    Some("Value from the key store or hash store".to_string()),
+                )
+            }
+        },
+        TokenKind::Identifier => {
+            let binding = bindings(text, token.end())
+                .into_iter()
+                .rev()
+                .find(|binding| binding.name == token.text)?;
+            let signature = format!("{}: {}", token.text, binding.ty);
+            (signature, Some(DECLARED_TYPE_DETAIL.to_string()))
+        }
+        _ => return None,
+    };
+
+    Some(Hover {
+        range: token.start..token.end(),
+        signature,
+        detail,
+    })
+}
+
+/// Return the byte range where the name at byte offset `offset` of the program `text` is defined.
+///
+/// Functions, type aliases, witness values, parameters and variables have definitions.
+pub fn definition(text: &str, offset: usize) -> Option<Range<usize>> {
+    let tokens = significant_tokens(text);
+    let token = tokens
+        .iter()
+        .find(|token| token.start <= offset && offset < token.end())?;
+    let defined_after = |keyword: &str, name: &str, range: Range<usize>| {
+        tokens
+            .windows(2)
+            .find(|pair| {
+                pair[0].kind == TokenKind::Keyword
+                    && pair[0].text == keyword
+                    && pair[1].text == name
+                    && range.contains(&pair[1].start)
+            })
+            .map(|pair| pair[1].start..pair[1].end())
+    };
+
+    match token.kind {
+        TokenKind::Function => defined_after("fn", token.text, 0..text.len()),
+        TokenKind::Type => defined_after("type", token.text, 0..text.len()),
+        TokenKind::Witness => {
+            let (module, name) = entry_of(token.text)?;
+            defined_after("const", name, witness::module_body(text, module)?)
+        }
+        TokenKind::Identifier => bindings(text, token.end())
+            .into_iter()
+            .rev()
+            .find(|binding| binding.name == token.text)
+            .map(|binding| binding.range),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::HashFunction;
+
+    const PROGRAM: &str = r#"mod witness {
+    const X: Either<u32, u8> = Left(1);
+}
+
+type Pair = (u32, u8);
+
+fn sum(pair: Pair, [a, b]: [u8; 2]) -> u32 {
+    let (x, y): Pair = pair;
+    let (carry, z): (bool, u32) = jet::add_32(x, jet::left_pad_low_8_32(y));
+    z
+}
+
+fn main() {
+    match witness::X {
+        Left(n: u32) => assert!(jet::eq_32(n, sum((n, 0), [0, 1]))),
+        Right(m: u8) => {},
+    }
+}"#;
+
+    fn hover_at(needle: &str) -> Option<String> {
+        let offset = PROGRAM.find(needle).expect("needle should exist");
+        hover(PROGRAM, offset, &[]).map(|hover| hover.signature)
+    }
+
+    fn definition_of(needle: &str) -> Option<&str> {
+        let offset = PROGRAM.find(needle).expect("needle should exist");
+        definition(PROGRAM, offset).map(|range| &PROGRAM[range])
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn hover_names() {
+        assert_eq!(Some("pair: Pair".to_string()), hover_at("pair;"));
+        assert_eq!(Some("z: u32".to_string()), hover_at("z\n}"));
+        assert_eq!(Some("carry: bool".to_string()), hover_at("carry"));
+        assert_eq!(Some("b: u8".to_string()), hover_at("b]"));
+        assert_eq!(Some("n: u32".to_string()), hover_at("n, sum"));
+        assert_eq!(
+            Some("fn sum(pair: Pair, [a, b]: [u8; 2]) -> u32".to_string()),
+            hover_at("sum((n")
+        );
+        assert_eq!(
+            Some("type Pair = (u32, u8)".to_string()),
+            hover_at("Pair = pair")
+        );
+        // Components of type aliases have their resolved types
+        assert_eq!(Some("x: u32".to_string()), hover_at("x, jet"));
+        assert_eq!(Some("y: u8".to_string()), hover_at("y));"));
+        // Other expressions have no hover, which the hovers of variables state
+        assert_eq!(None, hover_at("0, 1"));
+        let variable = hover(PROGRAM, PROGRAM.find("carry").unwrap(), &[]).unwrap();
+        assert_eq!(Some(DECLARED_TYPE_DETAIL.to_string()), variable.detail);
+        // Symbols have the types of their values
+        let text = "fn main() {\n    let pre: u256 = preimage::0;\n    let sig: Signature = sig::alice;\n}";
+        let symbol = |needle: &str| {
+            hover(text, text.find(needle).unwrap(), &[])
+                .unwrap()
+                .signature
+        };
+        assert_eq!("preimage::0: u256", symbol("preimage::"));
+        assert_eq!("sig::alice: Signature", symbol("sig::"));
+        // Custom preimages have the types of their lengths
+        let custom_preimages = [CustomPreimage {
+            preimage: vec![0; 20],
+            function: HashFunction::Hash160,
+        }];
+        let text = "fn main() {\n    let pre: [u8; 20] = preimage::26;\n}";
+        let custom = hover(text, text.find("preimage::").unwrap(), &custom_preimages).unwrap();
+        assert_eq!("preimage::26: [u8; 20]", custom.signature);
+
+        let jet = hover(PROGRAM, PROGRAM.find("jet::add_32").unwrap(), &[]).unwrap();
+        assert_eq!("jet::add_32: fn(u32, u32) -> (bool, u32)", jet.signature);
+        assert!(jet.detail.unwrap().starts_with("Cost: "));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn find_definitions() {
+        assert_eq!(Some("sum"), definition_of("sum((n"));
+        assert_eq!(
+            PROGRAM.find("sum(pair"),
+            definition(PROGRAM, PROGRAM.find("sum((n").unwrap()).map(|range| range.start)
+        );
+        assert_eq!(
+            PROGRAM.find("Pair = (u32"),
+            definition(PROGRAM, PROGRAM.find("Pair = pair").unwrap()).map(|range| range.start)
+        );
+        assert_eq!(
+            PROGRAM.find("X: Either"),
+            definition(PROGRAM, PROGRAM.find("witness::X {").unwrap()).map(|range| range.start)
+        );
+        assert_eq!(
+            PROGRAM.find("z): (bool"),
+            definition(PROGRAM, PROGRAM.find("z\n}").unwrap()).map(|range| range.start)
+        );
+        assert_eq!(None, definition_of("jet::eq_32"));
+    }
+}
@@ -1,9 +1,31 @@
+mod adaptor;
+mod breakdown;
+mod check;
+mod completion;
 mod components;
+mod dag;
+mod document;
+mod encryption;
 mod examples;
+mod export;
+mod form;
 mod function;
+mod hash;
 mod jet;
+mod lookup;
+mod problem;
+mod project;
+mod raw;
+mod share;
+mod source_map;
+mod syntax;
+#[cfg(test)]
+mod test_util;
 mod transaction;
 mod util;
+mod whitespace;
+mod witness;
+mod workspace;
 
 use components::App;
 use leptos::{mount_to_body, view};
@@ -0,0 +1,376 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::form;
+use crate::hash::CustomPreimage;
+use crate::witness::{self, Diagnostic, Resolved};
+
+/// Position in the program text.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Position {
+    /// Line index, starting at 0.
+    pub line: usize,
+    /// Character index inside the line, starting at 0.
+    pub col: usize,
+}
+
+impl Position {
+    pub const fn new(line: usize, col: usize) -> Self {
+        Self { line, col }
+    }
+
+    /// Return the byte offset of the position in the given `text`.
+    ///
+    /// Positions that lie outside the text are moved to the end of their line or to the end of the text.
+    pub fn offset(self, text: &str) -> usize {
+        let mut line_start = 0;
+        for _ in 0..self.line {
+            match text[line_start..].find('\n') {
+                Some(index) => line_start += index + 1,
+                None => return text.len(),
+            }
+        }
+        let line = text[line_start..].split('\n').next().unwrap_or("");
+        line_start
+            + line
+                .char_indices()
+                .nth(self.col)
+                .map(|(index, _)| index)
+                .unwrap_or(line.len())
+    }
+
+    /// Return the position of the given byte `offset` in the given `text`.
+    pub fn from_offset(text: &str, offset: usize) -> Self {
+        let before = &text[..offset.min(text.len())];
+        let line = before.matches('\n').count();
+        let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        Self::new(line, before[line_start..].chars().count())
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.col + 1)
+    }
+}
+
+/// Range of the program text between two positions.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// Return the byte range of the span in the given `text`.
+    ///
+    /// Empty spans are extended to cover one character, so they remain visible.
+    pub fn range(self, text: &str) -> Range<usize> {
+        let start = self.start.offset(text);
+        let end = self.end.offset(text).max(start);
+        match start == end {
+            true => {
+                start
+                    ..text[start..]
+                        .chars()
+                        .next()
+                        .map_or(end, |c| end + c.len_utf8())
+            }
+            false => start..end,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Problem of the program, with the span of program text where it occurs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Problem {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+impl Problem {
+    /// Read a problem from an error message of the Simfony compiler.
+    ///
+    /// Rich errors quote the offending lines of the program and underline the span with `^`:
+    ///
+    /// ```text
+    ///   |
+    /// 3 |     let a: u32 = witness::A;
+    ///   |                  ^^^^^^^^^^
+    /// Expected expression of type `u32`, found type `u16`
+    /// ```
+    ///
+    /// Errors without quotes have no span.
+    pub fn from_error(error: &str) -> Self {
+        let mut lines = vec![];
+        let mut underline = None;
+        let mut message_lines = vec![];
+
+        for line in error.lines() {
+            if underline.is_some() {
+                message_lines.push(line);
+                continue;
+            }
+            let (prefix, rest) = match line.split_once('|') {
+                Some(split) => split,
+                None => {
+                    message_lines.push(line);
+                    continue;
+                }
+            };
+            let prefix = prefix.trim();
+            if let Ok(line_number) = prefix.parse::<usize>() {
+                lines.push(line_number);
+            } else if prefix.is_empty() && rest.trim().is_empty() {
+                // Empty line of the quote
+            } else if prefix.is_empty() && rest.trim().chars().all(|c| c == '^') {
+                let caret_start = rest.find('^').expect("underline should not be empty");
+                underline = Some((caret_start.saturating_sub(1), rest.trim().len()));
+            } else {
+                message_lines.push(line);
+            }
+        }
+
+        let span = match (lines.first(), lines.last(), underline) {
+            (Some(&first), Some(&last), Some((col, len))) if 0 < first => match first == last {
+                true => Some(Span {
+                    start: Position::new(first - 1, col),
+                    end: Position::new(first - 1, col + len),
+                }),
+                false => Some(Span {
+                    start: Position::new(first - 1, 0),
+                    end: Position::new(last - 1, usize::MAX),
+                }),
+            },
+            _ => None,
+        };
+        let message = match span {
+            Some(..) => message_lines.join("\n").trim().to_string(),
+            None => error.trim().to_string(),
+        };
+
+        Self {
+            severity: Severity::Error,
+            message,
+            span,
+        }
+    }
+
+    /// Convert a diagnostic of the witness module or param module into a problem.
+    pub fn from_diagnostic(text: &str, diagnostic: &Diagnostic) -> Self {
+        let (severity, module, name) = match diagnostic {
+            Diagnostic::Missing { module, name, .. } => (Severity::Error, *module, name),
+            Diagnostic::Extra { module, name } => (Severity::Warning, *module, name),
+            Diagnostic::IllTyped { module, name, .. } => (Severity::Error, *module, name),
+        };
+        let span = form::entry_value_range(text, module, name).map(|range| Span {
+            start: Position::from_offset(text, range.start),
+            end: Position::from_offset(text, range.end),
+        });
+
+        Self {
+            severity,
+            message: diagnostic.to_string(),
+            span,
+        }
+    }
+}
+
+impl Span {
+    /// Move the span from the `resolved` text to the original `text`.
+    ///
+    /// Spans that start inside the value of a symbol start at the symbol.
+    pub fn unresolve(self, text: &str, resolved: &Resolved) -> Self {
+        let original = |position: Position| {
+            let offset = resolved.original_offset(position.offset(&resolved.text));
+            Position::from_offset(text, offset)
+        };
+        Self {
+            start: original(self.start),
+            end: original(self.end),
+        }
+    }
+}
+
+/// Collect the problems of the program `text`, given the errors of its compilation and satisfaction.
+///
+/// The compiler sees the `resolved` text, so the spans of its errors are moved to the original text.
+/// Problems with a span come first, ordered by position.
+/// Symbols are typed after the `custom_preimages` of the hash store.
+pub fn collect_problems<'a, I>(
+    text: &str,
+    resolved: &Resolved,
+    errors: I,
+    custom_preimages: &[CustomPreimage],
+) -> Vec<Problem>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut problems: Vec<Problem> = errors
+        .into_iter()
+        .filter(|error| !error.is_empty())
+        .map(Problem::from_error)
+        .map(|problem| Problem {
+            span: problem.span.map(|span| span.unresolve(text, resolved)),
+            ..problem
+        })
+        .collect();
+    if let Ok(diagnostics) = witness::diagnostics(text, custom_preimages) {
+        problems.extend(
+            diagnostics
+                .iter()
+                .map(|diagnostic| Problem::from_diagnostic(text, diagnostic)),
+        );
+    }
+    problems.sort_by(|a, b| {
+        let position = |problem: &Problem| {
+            problem
+                .span
+                .map_or(Position::new(usize::MAX, 0), |span| span.start)
+        };
+        position(a)
+            .cmp(&position(b))
+            .then_with(|| a.message.cmp(&b.message))
+    });
+    problems.dedup_by(|a, b| a.message == b.message);
+    problems
+}
+
+/// Segment of program text that is marked with the severity of a problem, if any.
+pub type Segment<'a> = (&'a str, Option<Severity>);
+
+/// Split the program `text` into segments that are covered by problems and segments that are not.
+///
+/// Errors take precedence over warnings where problems overlap.
+pub fn segments<'a>(text: &'a str, problems: &[Problem]) -> Vec<Segment<'a>> {
+    let mut marks: Vec<Option<Severity>> = vec![None; text.len()];
+    for problem in problems {
+        if let Some(span) = problem.span {
+            for mark in &mut marks[span.range(text)] {
+                if *mark != Some(Severity::Error) {
+                    *mark = Some(problem.severity);
+                }
+            }
+        }
+    }
+
+    let mut segments = vec![];
+    let mut segment_start = 0;
+    for (index, _) in text.char_indices().skip(1) {
+        if marks[index] != marks[segment_start] {
+            segments.push((&text[segment_start..index], marks[segment_start]));
+            segment_start = index;
+        }
+    }
+    if segment_start < text.len() {
+        segments.push((&text[segment_start..], marks[segment_start]));
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn parse_rich_error() {
+        let error = "  |
+3 |     let a: u32 = witness::A;
+  |                  ^^^^^^^^^^
+Expected expression of type `u32`, found type `u16`";
+        let problem = Problem::from_error(error);
+        assert_eq!(
+            "Expected expression of type `u32`, found type `u16`",
+            problem.message
+        );
+        assert_eq!(
+            Some(Span {
+                start: Position::new(2, 17),
+                end: Position::new(2, 27),
+            }),
+            problem.span
+        );
+
+        let text = "fn main() {\n\n    let a: u32 = witness::A;\n}";
+        assert_eq!("witness::A", &text[problem.span.unwrap().range(text)]);
+
+        let problem = Problem::from_error("Witness `A` is missing");
+        assert_eq!(None, problem.span);
+        assert_eq!("Witness `A` is missing", problem.message);
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn spans_after_symbols() {
+        let text = "fn main() {\n    let (a, b): (Signature, u8) = (sig::alice, witness::B);\n}";
+        let resolved = witness::resolve_mapped(text, |symbol| Ok(symbol.placeholder(&[]))).unwrap();
+        let line = resolved.text.lines().nth(1).unwrap();
+        let col = line.find("witness::B").unwrap();
+        let error = format!(
+            "  |\n2 | {line}\n  | {}{}\nWitness `B` is missing",
+            " ".repeat(col),
+            "^".repeat("witness::B".len())
+        );
+        let problems = collect_problems(text, &resolved, [error.as_str(), error.as_str()], &[]);
+        let missing: Vec<&Problem> = problems
+            .iter()
+            .filter(|problem| problem.message == "Witness `B` is missing")
+            .collect();
+        assert_eq!(1, missing.len());
+        let span = missing[0].span.expect("error should have a span");
+        assert_eq!("witness::B", &text[span.range(text)]);
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn position_offset_roundtrip() {
+        let text = "ab\n\ncdé\nf";
+        for offset in [0, 1, 2, 3, 4, 5, 6, 8, 9, 10] {
+            let position = Position::from_offset(text, offset);
+            assert_eq!(offset, position.offset(text));
+        }
+        assert_eq!(text.len(), Position::new(7, 0).offset(text));
+        assert_eq!(2, Position::new(0, 7).offset(text));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn mark_segments() {
+        let text = "let a = b;";
+        let problems = [
+            Problem {
+                severity: Severity::Warning,
+                message: "".to_string(),
+                span: Some(Span {
+                    start: Position::new(0, 4),
+                    end: Position::new(0, 9),
+                }),
+            },
+            Problem {
+                severity: Severity::Error,
+                message: "".to_string(),
+                span: Some(Span {
+                    start: Position::new(0, 8),
+                    end: Position::new(0, 8),
+                }),
+            },
+        ];
+        assert_eq!(
+            vec![
+                ("let ", None),
+                ("a = ", Some(Severity::Warning)),
+                ("b", Some(Severity::Error)),
+                (";", None),
+            ],
+            segments(text, &problems)
+        );
+    }
+}
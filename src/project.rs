@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+
+use crate::problem::Position;
+use crate::syntax::{self, TokenKind};
+
+/// Source file of a program that is made of several files.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+}
+
+impl SourceFile {
+    pub fn new<S: Into<String>, T: Into<String>>(name: S, text: T) -> Self {
+        Self {
+            name: name.into(),
+            text: text.into(),
+        }
+    }
+}
+
+/// Name of the file of a program that consists of a single file.
+pub const DEFAULT_FILE_NAME: &str = "main.simf";
+
+/// Separator between files in the combined program text.
+const SEPARATOR: &str = "\n\n";
+
+/// Check if the program `text` defines a main function, which makes it an entry point.
+pub fn defines_main(text: &str) -> bool {
+    let tokens: Vec<_> = syntax::tokenize(text)
+        .into_iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+        .collect();
+    tokens
+        .windows(2)
+        .any(|pair| pair[0].text == "fn" && pair[1].text == "main")
+}
+
+/// Prefix of the comment lines that list the files that an entry point includes.
+const INCLUDE_PREFIX: &str = "// include:";
+
+/// Return the names of the files that the program `text` includes.
+///
+/// An entry point includes library files with comment lines
+/// like `// include: alice_witness.simf, alice_params.simf`.
+pub fn includes(text: &str) -> Vec<&str> {
+    text.lines()
+        .filter_map(|line| line.trim().strip_prefix(INCLUDE_PREFIX))
+        .flat_map(|names| names.split(','))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Check if the file at `index` is a library that the `entry` file uses.
+///
+/// A library that some entry point includes belongs to the entry points that include it.
+/// Every other library is shared by all entry points.
+/// Without an entry point, every library is used.
+fn uses_library(files: &[SourceFile], entry: Option<usize>, index: usize) -> bool {
+    let name = files[index].name.as_str();
+    let is_included_by =
+        |file: &SourceFile| defines_main(&file.text) && includes(&file.text).contains(&name);
+    !defines_main(&files[index].text)
+        && match entry {
+            Some(entry) => is_included_by(&files[entry]) || !files.iter().any(is_included_by),
+            None => true,
+        }
+}
+
+/// Return the index of the entry point among the `files`.
+///
+/// The entry point is the `active` file if it defines a main function.
+/// Otherwise, it is the first entry point that includes the `active` file,
+/// or the first file that defines a main function if no entry point includes it.
+pub fn entry_point(files: &[SourceFile], active: usize) -> Option<usize> {
+    let entries = || (0..files.len()).filter(|&index| defines_main(&files[index].text));
+    let active_name = files.get(active).map(|file| file.name.as_str());
+    match files.get(active) {
+        Some(file) if defines_main(&file.text) => Some(active),
+        _ => entries()
+            .find(|&index| {
+                active_name.is_some_and(|name| includes(&files[index].text).contains(&name))
+            })
+            .or_else(|| entries().next()),
+    }
+}
+
+/// Check if `name` can be used as the name of a new file among the existing `files`.
+pub fn is_valid_file_name(files: &[SourceFile], name: &str) -> bool {
+    !name.trim().is_empty() && !name.contains('\n') && files.iter().all(|file| file.name != name)
+}
+
+/// Return `name` if it can be used as the name of a new file among the existing `files`,
+/// and otherwise `name` with the lowest number before its extension that makes it usable.
+pub fn unused_file_name(files: &[SourceFile], name: &str) -> String {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{extension}")),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|number| match number {
+            1 => name.to_string(),
+            _ => format!("{stem}{number}{extension}"),
+        })
+        .find(|name| is_valid_file_name(files, name))
+        .expect("there should be an unused file name")
+}
+
+/// Program text that combines several source files.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Combined {
+    pub text: String,
+    /// Index of each combined file, with the line of the combined text where the file starts.
+    pub file_lines: Vec<(usize, usize)>,
+}
+
+impl Combined {
+    /// Combine the libraries of the entry point in their given order,
+    /// followed by the entry point.
+    ///
+    /// See [`entry_point`] for which entry point is combined
+    /// and [`includes`] for how libraries are assigned to entry points.
+    pub fn new(files: &[SourceFile], active: usize) -> Self {
+        let entry = entry_point(files, active);
+        let included = (0..files.len())
+            .filter(|&index| uses_library(files, entry, index))
+            .chain(entry);
+        let mut text = String::new();
+        let mut file_lines = vec![];
+
+        for index in included {
+            if !file_lines.is_empty() {
+                text.push_str(SEPARATOR);
+            }
+            file_lines.push((index, text.matches('\n').count()));
+            text.push_str(&files[index].text);
+        }
+
+        Self { text, file_lines }
+    }
+
+    /// Translate a position of the combined text into the index of a file
+    /// and a position inside that file.
+    pub fn locate(&self, position: Position) -> Option<(usize, Position)> {
+        self.file_lines
+            .iter()
+            .rev()
+            .find(|(_, start_line)| *start_line <= position.line)
+            .map(|(index, start_line)| {
+                (
+                    *index,
+                    Position::new(position.line - start_line, position.col),
+                )
+            })
+    }
+
+    /// Translate a position inside the file with the given `index` into a position of the combined text.
+    ///
+    /// Return `None` if the file is not combined.
+    pub fn position_in(&self, index: usize, position: Position) -> Option<Position> {
+        self.file_lines
+            .iter()
+            .find(|(file, _)| *file == index)
+            .map(|(_, start_line)| Position::new(position.line + start_line, position.col))
+    }
+}
+
+/// Encode the `files` and the index of the `active` file as a single string.
+///
+/// The string starts with the active index on its own line.
+/// Each file follows as its name on one line, the byte length of its text on the next line
+/// and then its text.
+pub fn encode(files: &[SourceFile], active: usize) -> String {
+    let mut encoded = format!("{active}\n");
+    for file in files {
+        encoded.push_str(&format!(
+            "{}\n{}\n{}",
+            file.name,
+            file.text.len(),
+            file.text
+        ));
+    }
+    encoded
+}
+
+/// Decode files and the index of the active file from a string that [`encode`] returned.
+///
+/// Return `None` if the string is ill-formatted.
+pub fn decode(encoded: &str) -> Option<(Vec<SourceFile>, usize)> {
+    let (active, mut rest) = encoded.split_once('\n')?;
+    let active = active.parse::<usize>().ok()?;
+    let mut files = vec![];
+
+    while !rest.is_empty() {
+        let (name, after_name) = rest.split_once('\n')?;
+        let (len, after_len) = after_name.split_once('\n')?;
+        let len = len.parse::<usize>().ok()?;
+        let text = after_len.get(..len)?;
+        files.push(SourceFile::new(name, text));
+        rest = &after_len[len..];
+    }
+
+    match active < files.len() {
+        true => Some((files, active)),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<SourceFile> {
+        vec![
+            SourceFile::new("lib.simf", "fn not(bit: bool) -> bool {\n    <u1>::into(jet::complement_1(<bool>::into(bit)))\n}"),
+            SourceFile::new("alice.simf", "fn main() {\n    assert!(not(false))\n}"),
+            SourceFile::new("witness.simf", "mod witness {}"),
+            SourceFile::new("bob.simf", "// Bob\nfn main() {\n    assert!(not(witness::X))\n}"),
+        ]
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn combine_files() {
+        let files = files();
+        assert_eq!(Some(1), entry_point(&files, 0));
+        assert_eq!(Some(3), entry_point(&files, 3));
+
+        let combined = Combined::new(&files, 3);
+        let expected = format!(
+            "{}\n\n{}\n\n{}",
+            files[0].text, files[2].text, files[3].text
+        );
+        assert_eq!(expected, combined.text);
+        assert_eq!(vec![(0, 0), (2, 4), (3, 6)], combined.file_lines);
+        assert_eq!(
+            Some((3, Position::new(2, 4))),
+            combined.locate(Position::new(8, 4))
+        );
+        assert_eq!(
+            Some((0, Position::new(1, 0))),
+            combined.locate(Position::new(1, 0))
+        );
+
+        assert_eq!(
+            Some(Position::new(8, 4)),
+            combined.position_in(3, Position::new(2, 4))
+        );
+        assert_eq!(None, combined.position_in(1, Position::new(0, 0)));
+
+        let single = [SourceFile::new(DEFAULT_FILE_NAME, "fn main() {}")];
+        assert_eq!("fn main() {}", Combined::new(&single, 0).text);
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn combine_included_files() {
+        let files = vec![
+            SourceFile::new("lib.simf", "fn id(x: u8) -> u8 {\n    x\n}"),
+            SourceFile::new("alice.simf", "// include: alice_witness.simf\nfn main() {}"),
+            SourceFile::new("alice_witness.simf", "mod witness { const A: u8 = 1; }"),
+            SourceFile::new(
+                "bob.simf",
+                "// include: bob_witness.simf, lib.simf\nfn main() {}",
+            ),
+            SourceFile::new("bob_witness.simf", "mod witness { const B: u8 = 2; }"),
+        ];
+        assert_eq!(
+            vec!["bob_witness.simf", "lib.simf"],
+            includes(&files[3].text)
+        );
+
+        // Included files are associated with the entry points that include them
+        assert_eq!(Some(1), entry_point(&files, 2));
+        assert_eq!(Some(3), entry_point(&files, 4));
+        assert_eq!(Some(3), entry_point(&files, 0));
+
+        // Each variant gets its own witness module, and only Bob includes the library
+        let alice = Combined::new(&files, 2);
+        assert_eq!(
+            vec![2, 1],
+            alice
+                .file_lines
+                .iter()
+                .map(|(index, _)| *index)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, alice.text.matches("mod witness").count());
+        let bob = Combined::new(&files, 3);
+        assert_eq!(
+            vec![0, 4, 3],
+            bob.file_lines
+                .iter()
+                .map(|(index, _)| *index)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, bob.text.matches("mod witness").count());
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn name_files() {
+        let files = files();
+        assert!(!is_valid_file_name(&files, "lib.simf"));
+        assert!(!is_valid_file_name(&files, " "));
+        assert_eq!("lib2.simf", unused_file_name(&files, "lib.simf"));
+        assert_eq!("carol.simf", unused_file_name(&files, "carol.simf"));
+        assert_eq!("file.simf", unused_file_name(&files, "file.simf"));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn encode_decode_roundtrip() {
+        let files = files();
+        let encoded = encode(&files, 2);
+        assert_eq!(Some((files, 2)), decode(&encoded));
+
+        assert_eq!(None, decode(""));
+        assert_eq!(None, decode("1\nmain.simf\n2\n{}"));
+        assert_eq!(None, decode("0\nmain.simf\n3\n{}"));
+    }
+}
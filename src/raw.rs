@@ -0,0 +1,401 @@
+use std::sync::Arc;
+
+use elements::secp256k1_zkp as secp256k1;
+use elements::taproot::ControlBlock;
+use hex_conservative::FromHex;
+use simfony::{elements, simplicity};
+use simplicity::jet::elements::{ElementsEnv, ElementsUtxo};
+use simplicity::jet::Elements;
+use simplicity::{BitIter, Cmr, RedeemNode};
+
+use crate::export;
+use crate::util::{self, Expression};
+
+/// Text encoding of bytes.
+///
+/// Many hex strings are also valid base64, so the encoding is chosen by the user
+/// instead of being guessed from the text.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+impl Encoding {
+    /// Decode bytes from the given string. Whitespace is ignored.
+    pub fn decode(self, s: &str) -> Result<Vec<u8>, String> {
+        let s: String = s.split_whitespace().collect();
+        match self {
+            Self::Hex => {
+                Vec::<u8>::from_hex(&s).map_err(|error| format!("Bytes must be hex: {error}"))
+            }
+            Self::Base64 => export::base64_decode(&s).ok_or("Bytes must be base64".to_string()),
+        }
+    }
+}
+
+/// Elements network on which a transaction is executed.
+///
+/// Simplicity programs commit to the genesis block hash of the network.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Network {
+    #[default]
+    LiquidTestnet,
+    Liquid,
+}
+
+impl Network {
+    /// Return the genesis block hash of the network.
+    pub fn genesis(self) -> elements::BlockHash {
+        match self {
+            Self::LiquidTestnet => util::liquid_testnet_genesis(),
+            Self::Liquid => util::liquid_genesis(),
+        }
+    }
+}
+
+/// Decode a Simplicity program from its encoded `program` and `witness` data,
+/// which are given in the same `encoding`.
+pub fn decode_program(
+    program: &str,
+    witness: &str,
+    encoding: Encoding,
+) -> Result<Arc<Expression>, String> {
+    let program_bytes = encoding
+        .decode(program)
+        .map_err(|error| format!("Program: {error}"))?;
+    let witness_bytes = encoding
+        .decode(witness)
+        .map_err(|error| format!("Witness: {error}"))?;
+    RedeemNode::<Elements>::decode(
+        BitIter::from(program_bytes.into_iter()),
+        BitIter::from(witness_bytes.into_iter()),
+    )
+    .map_err(|error| error.to_string())
+}
+
+/// First byte of the annex of a Taproot witness stack.
+const ANNEX_TAG: u8 = 0x50;
+
+/// Decode a serialized transaction output that is given in the `encoding`.
+///
+/// The output may also be given as the serialized transaction that contains it,
+/// in which case it is the output that the `outpoint` spends.
+fn decode_utxo(
+    s: &str,
+    outpoint: elements::OutPoint,
+    encoding: Encoding,
+) -> Result<ElementsUtxo, String> {
+    let bytes = encoding.decode(s)?;
+    let output = match elements::encode::deserialize::<elements::Transaction>(&bytes) {
+        Ok(tx) if tx.txid() != outpoint.txid => {
+            return Err(format!("Transaction {} is not spent", tx.txid()))
+        }
+        Ok(tx) => tx
+            .output
+            .get(outpoint.vout as usize)
+            .cloned()
+            .ok_or(format!(
+                "Transaction {} has no output {}",
+                tx.txid(),
+                outpoint.vout
+            ))?,
+        Err(..) => elements::encode::deserialize::<elements::TxOut>(&bytes)
+            .map_err(|error| format!("Output is ill-formatted: {error}"))?,
+    };
+    Ok(ElementsUtxo {
+        script_pubkey: output.script_pubkey,
+        asset: output.asset,
+        value: output.value,
+    })
+}
+
+/// Simplicity spend of a transaction input, which is decoded from the witness stack of the input.
+#[derive(Debug)]
+pub struct Spend {
+    /// Simplicity program of the spend, including its witness data.
+    pub program: Arc<Expression>,
+    /// CMR that is committed to by the witness stack.
+    pub cmr: Cmr,
+    pub control_block: ControlBlock,
+    /// Script pubkey of the output that is spent.
+    pub script_pubkey: elements::Script,
+    /// Environment of the transaction in which the program is executed.
+    pub env: ElementsEnv<Arc<elements::Transaction>>,
+}
+
+impl Spend {
+    /// Decode the Simplicity spend of input `index` of the transaction `tx`.
+    ///
+    /// The transaction is given in the `encoding`.
+    /// The outputs that the transaction spends are given one per line, in the order of the inputs,
+    /// and in the same encoding.
+    /// The transaction is executed on the given `network`.
+    ///
+    /// The witness stack of the input consists of the Simplicity witness data,
+    /// the Simplicity program, the CMR, the control block and an optional annex,
+    /// like [`crate::transaction::TxParams::transaction`] builds it.
+    pub fn decode(
+        tx: &str,
+        utxos: &str,
+        index: u32,
+        encoding: Encoding,
+        network: Network,
+    ) -> Result<Self, String> {
+        let tx_bytes = encoding
+            .decode(tx)
+            .map_err(|error| format!("Transaction: {error}"))?;
+        let tx = elements::encode::deserialize::<elements::Transaction>(&tx_bytes)
+            .map_err(|error| format!("Transaction is ill-formatted: {error}"))?;
+        let input = tx
+            .input
+            .get(index as usize)
+            .ok_or(format!("Transaction has no input {index}"))?;
+
+        let utxo_lines: Vec<&str> = utxos
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        if utxo_lines.len() != tx.input.len() {
+            return Err(format!(
+                "Transaction spends {} outputs, but {} outputs are given",
+                tx.input.len(),
+                utxo_lines.len()
+            ));
+        }
+        let utxos = utxo_lines
+            .into_iter()
+            .zip(&tx.input)
+            .enumerate()
+            .map(|(i, (line, input))| {
+                decode_utxo(line, input.previous_output, encoding)
+                    .map_err(|error| format!("UTXO {i}: {error}"))
+            })
+            .collect::<Result<Vec<ElementsUtxo>, String>>()?;
+
+        let mut stack = input.witness.script_witness.clone();
+        let annex = match stack.last() {
+            Some(last) if 1 < stack.len() && last.first() == Some(&ANNEX_TAG) => stack.pop(),
+            _ => None,
+        };
+        let [witness_bytes, program_bytes, cmr_bytes, control_block_bytes] =
+            match <[Vec<u8>; 4]>::try_from(stack) {
+                Ok(stack) => stack,
+                Err(stack) => {
+                    return Err(format!(
+                        "Witness stack of a Simplicity spend must have 4 elements, but it has {}",
+                        stack.len()
+                    ))
+                }
+            };
+        let program = RedeemNode::<Elements>::decode(
+            BitIter::from(program_bytes.into_iter()),
+            BitIter::from(witness_bytes.into_iter()),
+        )
+        .map_err(|error| format!("Program: {error}"))?;
+        let cmr = <[u8; 32]>::try_from(cmr_bytes.as_slice())
+            .map(Cmr::from_byte_array)
+            .map_err(|_| "CMR must be 32 bytes long".to_string())?;
+        if cmr != program.cmr() {
+            return Err(format!(
+                "Witness stack commits to CMR {cmr}, but the program has CMR {}",
+                program.cmr()
+            ));
+        }
+        let control_block = ControlBlock::from_slice(&control_block_bytes)
+            .map_err(|error| format!("Control block: {error}"))?;
+
+        let script_pubkey = utxos[index as usize].script_pubkey.clone();
+        let env = ElementsEnv::new(
+            Arc::new(tx),
+            utxos,
+            index,
+            cmr,
+            control_block.clone(),
+            annex,
+            network.genesis(),
+        );
+        Ok(Self {
+            program,
+            cmr,
+            control_block,
+            script_pubkey,
+            env,
+        })
+    }
+
+    /// Check that the control block proves that the script pubkey of the spent output
+    /// commits to the CMR of the program.
+    pub fn verify_control_block(&self) -> Result<(), String> {
+        let script_pubkey = &self.script_pubkey;
+        if !script_pubkey.is_v1_p2tr() {
+            return Err("Spent output is not a Taproot output".to_string());
+        }
+        let output_key = secp256k1::XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..])
+            .map_err(|error| format!("Spent output has an invalid Taproot key: {error}"))?;
+        if self.control_block.leaf_version != simplicity::leaf_version() {
+            return Err("Control block has no Simplicity leaf version".to_string());
+        }
+        let script = elements::Script::from(self.cmr.as_ref().to_vec());
+        match self.control_block.verify_taproot_commitment(
+            secp256k1::SECP256K1,
+            &output_key,
+            &script,
+        ) {
+            true => Ok(()),
+            false => Err(
+                "Control block does not match the script pubkey of the spent output".to_string(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_conservative::DisplayHex;
+    use simfony::elements::confidential;
+
+    use super::*;
+    use crate::examples::{self, Example};
+    use crate::function::Runner;
+    use crate::test_util::{satisfied_and_tx_env, satisfy_example};
+    use crate::transaction::TxParams;
+    use crate::util::{HashedData, SigningKeys};
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn decode_bytes_in_chosen_encoding() {
+        assert_eq!(Ok(vec![0xde, 0xad]), Encoding::Hex.decode("dead"));
+        assert_eq!(Ok(vec![0xde, 0xad]), Encoding::Hex.decode(" de\nad "));
+        assert_eq!(Ok(vec![0x75, 0xe6, 0x9d]), Encoding::Base64.decode("dead"));
+        assert_eq!(Ok(b"foob".to_vec()), Encoding::Base64.decode("Zm9vYg=="));
+        assert!(Encoding::Hex.decode("Zm9vYg==").is_err());
+        assert_eq!(Ok(vec![]), Encoding::Hex.decode(""));
+        assert!(Encoding::Base64.decode("not bytes!").is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn network_genesis() {
+        assert_eq!(
+            "a771da8e52ee6ad581ed1e9a99825e5b3b7992225534eaa2ae23244fe26ab1c1",
+            Network::LiquidTestnet.genesis().to_string()
+        );
+        assert_eq!(
+            "1466275836220db2944ca059a3a10ef6fd2ea684b0688d2c379296888a206003",
+            Network::Liquid.genesis().to_string()
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn decode_and_run_examples() {
+        let signing_keys = SigningKeys::default();
+        let hashed_data = HashedData::default();
+
+        for name in examples::keys() {
+            let example = examples::get(name).unwrap();
+            let (satisfied, tx_env) = satisfied_and_tx_env(example, &signing_keys, &hashed_data);
+            let pruned = satisfied.redeem().prune(&tx_env).unwrap();
+            let (program_bytes, witness_bytes) = pruned.encode_to_vec();
+
+            let decoded = decode_program(
+                &program_bytes.to_lower_hex_string(),
+                &witness_bytes.to_lower_hex_string(),
+                Encoding::Hex,
+            )
+            .unwrap();
+            assert_eq!(pruned.cmr(), decoded.cmr(), "{name}");
+            assert_eq!(pruned.imr(), decoded.imr(), "{name}");
+            let mut runner = Runner::for_redeem(decoded);
+            assert!(runner.run(&tx_env).is_ok(), "{name}");
+        }
+    }
+
+    /// Return the parameters of a transaction that spends the output of a funding transaction,
+    /// which pays to the program with the given `cmr`,
+    /// together with the spent output and the funding transaction.
+    fn spend_params(
+        example: Example,
+        cmr: Cmr,
+    ) -> (TxParams, elements::TxOut, elements::Transaction) {
+        let mut params = example.params();
+        let utxo = elements::TxOut {
+            asset: confidential::Asset::Explicit(util::liquid_testnet_bitcoin_asset()),
+            value: confidential::Value::Explicit(params.value_in),
+            nonce: confidential::Nonce::Null,
+            script_pubkey: util::liquid_testnet_address(cmr).script_pubkey(),
+            witness: elements::TxOutWitness::empty(),
+        };
+        let funding_tx = elements::Transaction {
+            version: 2,
+            lock_time: elements::LockTime::from_consensus(0),
+            input: vec![],
+            output: vec![utxo.clone()],
+        };
+        params.txid = funding_tx.txid();
+        params.vout = 0;
+        (params, utxo, funding_tx)
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn decode_and_verify_spends() {
+        let signing_keys = SigningKeys::default();
+        let hashed_data = HashedData::default();
+
+        for name in examples::keys() {
+            let example = examples::get(name).unwrap();
+            let (satisfied, tx_env) =
+                satisfy_example(example, &signing_keys, &hashed_data, |cmr| {
+                    spend_params(example, cmr).0
+                });
+            let cmr = satisfied.redeem().cmr();
+            let (params, utxo, funding_tx) = spend_params(example, cmr);
+            let pruned = satisfied.redeem().prune(&tx_env).unwrap();
+            let tx =
+                elements::encode::serialize(&params.transaction(&pruned)).to_lower_hex_string();
+
+            // The spent output is given by itself or as the transaction that contains it
+            for spent in [
+                elements::encode::serialize(&utxo),
+                elements::encode::serialize(&funding_tx),
+            ] {
+                let spend = Spend::decode(
+                    &tx,
+                    &spent.to_lower_hex_string(),
+                    0,
+                    Encoding::Hex,
+                    Network::LiquidTestnet,
+                )
+                .unwrap();
+                assert_eq!(cmr, spend.cmr, "{name}");
+                assert_eq!(pruned.cmr(), spend.program.cmr(), "{name}");
+                assert_eq!(Ok(()), spend.verify_control_block(), "{name}");
+                let mut runner = Runner::for_redeem(spend.program);
+                assert!(runner.run(&spend.env).is_ok(), "{name}");
+            }
+
+            let faucet_output = elements::TxOut {
+                script_pubkey: util::liquid_testnet_faucet_script_pubkey(),
+                ..utxo
+            };
+            let faucet_output = elements::encode::serialize(&faucet_output).to_lower_hex_string();
+            let decode = |tx: &str, utxos: &str, index: u32, encoding: Encoding| {
+                Spend::decode(tx, utxos, index, encoding, Network::LiquidTestnet)
+            };
+            let spend = decode(&tx, &faucet_output, 0, Encoding::Hex).unwrap();
+            assert!(spend.verify_control_block().is_err(), "{name}");
+            assert!(decode(&tx, "", 0, Encoding::Hex).is_err(), "{name}");
+            assert!(
+                decode(&tx, &faucet_output, 1, Encoding::Hex).is_err(),
+                "{name}"
+            );
+            assert!(
+                decode(&tx, &faucet_output, 0, Encoding::Base64).is_err(),
+                "{name}"
+            );
+        }
+    }
+}
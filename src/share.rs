@@ -0,0 +1,80 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use miniz_oxide::{deflate, inflate};
+
+/// Version of the format of shared states.
+const SHARE_VERSION: u8 = 2;
+
+/// Level of DEFLATE compression, from 0 (none) to 10 (best).
+const COMPRESSION_LEVEL: u8 = 10;
+/// Maximum byte length of a decompressed state,
+/// so a crafted link cannot exhaust the memory of the visitor.
+const MAX_STATE_LEN: usize = 1 << 20;
+
+/// Encode the key-value pairs of an IDE state for the URL of a share link.
+///
+/// Each pair is written as the key on one line, the byte length of the value on the next line
+/// and then the value. The result is compressed with DEFLATE and encoded as URL-safe base64.
+pub fn encode_state<'a, I>(values: I) -> String
+where
+    I: IntoIterator<Item = (&'a str, String)>,
+{
+    let mut serialized = vec![SHARE_VERSION];
+    for (key, value) in values {
+        serialized.extend_from_slice(format!("{key}\n{}\n{value}", value.len()).as_bytes());
+    }
+    URL_SAFE_NO_PAD.encode(deflate::compress_to_vec(&serialized, COMPRESSION_LEVEL))
+}
+
+/// Decode the key-value pairs that [`encode_state`] returned.
+///
+/// Return `None` if the string is ill-formatted.
+pub fn decode_state(s: &str) -> Option<Vec<(String, String)>> {
+    let compressed = URL_SAFE_NO_PAD.decode(s).ok()?;
+    let serialized = inflate::decompress_to_vec_with_limit(&compressed, MAX_STATE_LEN).ok()?;
+    let (&version, serialized) = serialized.split_first()?;
+    if version != SHARE_VERSION {
+        return None;
+    }
+    let mut rest = std::str::from_utf8(serialized).ok()?;
+    let mut values = vec![];
+
+    while !rest.is_empty() {
+        let (key, after_key) = rest.split_once('\n')?;
+        let (len, after_len) = after_key.split_once('\n')?;
+        let len = len.parse::<usize>().ok()?;
+        let value = after_len.get(..len)?;
+        values.push((key.to_string(), value.to_string()));
+        rest = &after_len[len..];
+    }
+
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn share_state_roundtrip() {
+        let values = [
+            ("program", "fn main() {\n    assert!(true)\n}".to_string()),
+            ("txid", "00".repeat(32)),
+            ("recipient", String::new()),
+        ];
+        let encoded = encode_state(values.clone());
+        assert!(!encoded.contains(['+', '/', '=']));
+        let decoded = decode_state(&encoded).unwrap();
+        let expected: Vec<_> = values
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        assert_eq!(expected, decoded);
+        assert_eq!(None, decode_state("AA"));
+        assert_eq!(None, decode_state("not base64!"));
+
+        let program = "fn main() {}\n".repeat(100);
+        assert!(encode_state([("program", program.clone())]).len() < program.len() / 10);
+    }
+}
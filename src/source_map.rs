@@ -0,0 +1,422 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use simfony::simplicity;
+use simplicity::Cmr;
+
+use crate::dag::Dag;
+use crate::syntax::{self, Token, TokenKind};
+
+/// Top-level statement or final expression of a function body.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Statement {
+    /// Name of the function whose body contains the statement.
+    pub function: String,
+    /// Byte range of the statement, without the trailing semicolon.
+    pub span: Range<usize>,
+    /// Byte range of the expression that the statement evaluates.
+    ///
+    /// This is the right-hand side of a `let` statement.
+    pub expression: Range<usize>,
+}
+
+/// Function definition of a Simfony program.
+struct Function<'a> {
+    name: &'a str,
+    /// Tokens of the body, without the enclosing braces.
+    body: &'a [Token<'a>],
+}
+
+/// Return the function definitions of the given `tokens`, which exclude whitespace and comments.
+fn functions<'a>(tokens: &'a [Token<'a>]) -> Vec<Function<'a>> {
+    let mut functions = vec![];
+    let mut index = 0;
+
+    while index + 2 < tokens.len() {
+        let is_definition = tokens[index].text == "fn"
+            && tokens[index + 1].kind == TokenKind::Function
+            && tokens[index + 2].text == "(";
+        if !is_definition {
+            index += 1;
+            continue;
+        }
+        let body = syntax::matching_bracket(tokens, tokens[index + 2].start)
+            .and_then(|params_end| {
+                tokens
+                    .iter()
+                    .position(|token| params_end < token.start && token.text == "{")
+            })
+            .and_then(|open| {
+                let close = syntax::matching_bracket(tokens, tokens[open].start)?;
+                let close = tokens.iter().position(|token| token.start == close)?;
+                Some((open, close))
+            });
+        let (open, close) = match body {
+            Some(body) => body,
+            None => break,
+        };
+        functions.push(Function {
+            name: tokens[index + 1].text,
+            body: &tokens[open + 1..close],
+        });
+        index = close + 1;
+    }
+
+    functions
+}
+
+/// Split a function body into its top-level statements, as ranges of token indices.
+///
+/// The ranges exclude the semicolons that end the statements.
+fn split_statements(body: &[Token]) -> Vec<Range<usize>> {
+    let mut statements = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (index, token) in body.iter().enumerate() {
+        match (token.kind, token.text) {
+            (TokenKind::Bracket, "(" | "[" | "{") => depth += 1,
+            (TokenKind::Bracket, _) => depth = depth.saturating_sub(1),
+            (_, ";") if depth == 0 => {
+                if start < index {
+                    statements.push(start..index);
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < body.len() {
+        statements.push(start..body.len());
+    }
+
+    statements
+}
+
+/// Return the top-level statements of all function bodies of the program `text`,
+/// in the order in which they appear.
+pub fn statements(text: &str) -> Vec<Statement> {
+    let tokens: Vec<_> = syntax::tokenize(text)
+        .into_iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+        .collect();
+    let mut statements = vec![];
+
+    for function in functions(&tokens) {
+        for range in split_statements(function.body) {
+            let tokens = &function.body[range];
+            let span = tokens[0].start..tokens[tokens.len() - 1].end();
+            // The expression of a let statement follows the equals sign
+            let expression_start = match tokens[0].text {
+                "let" => match tokens.iter().position(|token| token.text == "=") {
+                    Some(index) if index + 1 < tokens.len() => tokens[index + 1].start,
+                    _ => continue,
+                },
+                _ => span.start,
+            };
+            statements.push(Statement {
+                function: function.name.to_string(),
+                span: span.clone(),
+                expression: expression_start..span.end,
+            });
+        }
+    }
+
+    statements
+}
+
+/// Return how deep each function of the program `text` is nested in the call graph.
+///
+/// The depth of a function is the length of the longest chain of calls that reaches it.
+fn call_depths(text: &str) -> HashMap<String, usize> {
+    let tokens: Vec<_> = syntax::tokenize(text)
+        .into_iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+        .collect();
+    let functions = functions(&tokens);
+    let names: HashSet<&str> = functions.iter().map(|function| function.name).collect();
+    // Functions are called by name or passed by name to loops such as `for_while::<f>`
+    let calls: Vec<(&str, &str)> = functions
+        .iter()
+        .flat_map(|function| {
+            function
+                .body
+                .iter()
+                .filter(|token| matches!(token.kind, TokenKind::Function | TokenKind::Identifier))
+                .filter(|token| names.contains(token.text))
+                .map(|token| (function.name, token.text))
+        })
+        .collect();
+
+    let mut depths: HashMap<String, usize> =
+        names.iter().map(|name| (name.to_string(), 0)).collect();
+    // Recursion is forbidden, so the depths settle after one round per function
+    for _ in 0..names.len() {
+        for (caller, callee) in &calls {
+            let depth = depths[*caller] + 1;
+            let callee_depth = depths
+                .get_mut(*callee)
+                .expect("callee should be a function");
+            *callee_depth = (*callee_depth).max(depth);
+        }
+    }
+    depths
+}
+
+/// Return the program `text` where the given `expression` is wrapped in a block
+/// that declares an unused variable.
+///
+/// The variable changes the environment of the expression,
+/// which changes the Simplicity nodes that the expression compiles to.
+fn mark_expression(text: &str, expression: &Range<usize>) -> String {
+    format!(
+        "{}{{ let webide_mark: u1 = 0; {} }}{}",
+        &text[..expression.start],
+        &text[expression.clone()],
+        &text[expression.end..]
+    )
+}
+
+/// Return the variants of the program `text` where one statement is marked,
+/// in the order of [`statements`].
+///
+/// The nodes that a variant does not share with the program are produced by its statement.
+pub fn marked_texts(text: &str) -> Vec<String> {
+    statements(text)
+        .iter()
+        .map(|statement| mark_expression(text, &statement.expression))
+        .collect()
+}
+
+/// Map from the Simplicity nodes of a program to the Simfony statements that produce them.
+///
+/// The map is approximate, because the compiler does not report where nodes come from:
+///
+/// - Only top-level statements of function bodies are mapped.
+///   Nodes of nested blocks belong to the statement that contains the block.
+/// - Nodes that combine several statements of the same function, such as the root,
+///   belong to no statement.
+/// - Nodes that several statements compile to identically are attributed through their parents.
+///
+/// Building the map compiles the program once per statement,
+/// so the map should be built once per program text and off the main thread.
+#[derive(Clone, Debug)]
+pub struct SourceMap {
+    statements: Vec<Statement>,
+    /// Index of the statement that produces the node with the given CMR.
+    owners: HashMap<Cmr, usize>,
+}
+
+impl SourceMap {
+    /// Create the source map of the program `text`.
+    ///
+    /// The `compile` function returns the CMRs of all nodes that a program text compiles to.
+    /// It compiles the program and each of its [`marked_texts`].
+    pub fn new<F>(text: &str, compile: F) -> Result<Self, String>
+    where
+        F: Fn(&str) -> Result<HashSet<Cmr>, String>,
+    {
+        let original = compile(text)?;
+        let changed = marked_texts(text)
+            .iter()
+            .map(|marked| match compile(marked) {
+                Ok(marked_cmrs) => original.difference(&marked_cmrs).copied().collect(),
+                Err(..) => vec![],
+            })
+            .collect();
+        Ok(Self::from_changes(text, changed))
+    }
+
+    /// Create the source map of the program `text` from the CMRs of the nodes
+    /// that change when a statement is marked, in the order of [`marked_texts`].
+    ///
+    /// A node that changes for several statements belongs to the statement
+    /// that is least deeply nested in the call graph, so a function call belongs to the caller.
+    /// If several statements of that depth change the node, then the node combines them
+    /// and belongs to none of them.
+    pub fn from_changes(text: &str, changed: Vec<Vec<Cmr>>) -> Self {
+        let statements = statements(text);
+        let depths = call_depths(text);
+        let depth = |index: usize| depths[&statements[index].function];
+
+        // Statements whose marking changes the node with the given CMR
+        let mut changed_by: HashMap<Cmr, Vec<usize>> = HashMap::new();
+        for (index, cmrs) in changed.into_iter().enumerate().take(statements.len()) {
+            for cmr in cmrs {
+                changed_by.entry(cmr).or_default().push(index);
+            }
+        }
+
+        let owners = changed_by
+            .into_iter()
+            .filter_map(|(cmr, indices)| {
+                let min_depth = indices.iter().map(|&index| depth(index)).min()?;
+                let mut least_nested = indices
+                    .into_iter()
+                    .filter(|&index| depth(index) == min_depth);
+                match (least_nested.next(), least_nested.next()) {
+                    (Some(index), None) => Some((cmr, index)),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        Self { statements, owners }
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    /// Return the index of the statement that changes each node of the `dag`,
+    /// if there is one.
+    pub fn node_owners(&self, dag: &Dag) -> Vec<Option<usize>> {
+        dag.nodes()
+            .iter()
+            .map(|node| self.owners.get(&node.cmr).copied())
+            .collect()
+    }
+
+    /// Return the index of the statement that produces each node of the `dag`.
+    ///
+    /// Nodes that no statement changes, such as constants, belong to the statement of their parent.
+    pub fn node_statements(&self, dag: &Dag) -> Vec<Option<usize>> {
+        let mut owners = self.node_owners(dag);
+        // Parents come after their children, so parents are visited first in reverse order
+        for id in (0..owners.len()).rev() {
+            if let Some(owner) = owners[id] {
+                for &child in &dag.nodes()[id].children {
+                    owners[child].get_or_insert(owner);
+                }
+            }
+        }
+        owners
+    }
+
+    /// Return the indices of the statements that overlap the given byte `range`.
+    ///
+    /// An empty range selects the statements that contain it.
+    pub fn statements_at(&self, range: Range<usize>) -> Vec<usize> {
+        self.statements
+            .iter()
+            .enumerate()
+            .filter(|(_, statement)| match range.is_empty() {
+                true => statement.span.start <= range.start && range.start <= statement.span.end,
+                false => range.start < statement.span.end && statement.span.start < range.end,
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simfony::parse::ParseFromStr;
+    use simfony::{Arguments, CompiledProgram, WitnessValues};
+
+    use super::*;
+    use crate::check;
+
+    const CHECKSIG: &str = r#"mod witness {
+    const SIG: Signature = 0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000;
+}
+
+fn checksig(pk: Pubkey, sig: Signature) {
+    let msg: u256 = jet::sig_all_hash();
+    jet::bip_0340_verify((pk, msg), sig)
+}
+
+fn main() {
+    let pk: Pubkey = 0x79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798;
+    checksig(pk, witness::SIG)
+}"#;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn split_statements() {
+        let text = "fn f(x: u8) -> u8 {\n    let (a, b): (u8, u8) = (x, x);\n    match a { Left(y) => y, Right(z) => { let w: u8 = z; w } };\n    // done\n    b\n}\n\nfn main() { assert!(true); }";
+        let found = statements(text);
+        let spans: Vec<&str> = found.iter().map(|s| &text[s.span.clone()]).collect();
+        let expressions: Vec<&str> = found.iter().map(|s| &text[s.expression.clone()]).collect();
+        assert_eq!(
+            vec![
+                "let (a, b): (u8, u8) = (x, x)",
+                "match a { Left(y) => y, Right(z) => { let w: u8 = z; w } }",
+                "b",
+                "assert!(true)",
+            ],
+            spans
+        );
+        assert_eq!(
+            vec![
+                "(x, x)",
+                "match a { Left(y) => y, Right(z) => { let w: u8 = z; w } }",
+                "b",
+                "assert!(true)",
+            ],
+            expressions
+        );
+        assert_eq!(
+            vec!["f", "f", "f", "main"],
+            found
+                .iter()
+                .map(|s| s.function.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn call_depth() {
+        let depths = call_depths(CHECKSIG);
+        assert_eq!(0, depths["main"]);
+        assert_eq!(1, depths["checksig"]);
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn map_checksig() {
+        let compile = |text: &str| -> Result<HashSet<Cmr>, String> {
+            let arguments = Arguments::parse_from_str(text).map_err(|error| error.to_string())?;
+            let compiled = CompiledProgram::new(text, arguments)?;
+            Ok(check::commit_cmrs(&compiled.commit()))
+        };
+        let source_map = SourceMap::new(CHECKSIG, compile).unwrap();
+        assert_eq!(4, source_map.statements().len());
+
+        let arguments = Arguments::parse_from_str(CHECKSIG).unwrap();
+        let compiled = CompiledProgram::new(CHECKSIG, arguments).unwrap();
+        let witness = WitnessValues::parse_from_str(CHECKSIG).unwrap();
+        let satisfied = compiled.satisfy(witness).unwrap();
+        let dag = Dag::new(satisfied.redeem(), None);
+        let owners = source_map.node_statements(&dag);
+        // The root combines both statements of main, so it belongs to neither
+        assert_eq!(None, owners[dag.root()]);
+        for index in 0..4 {
+            assert!(owners.contains(&Some(index)), "statement {index}");
+        }
+
+        let call = CHECKSIG.find("checksig(pk").unwrap();
+        assert_eq!(vec![3], source_map.statements_at(call..call));
+        assert_eq!(
+            vec![0, 1],
+            source_map
+                .statements_at(CHECKSIG.find("let msg").unwrap()..CHECKSIG.find("sig)").unwrap())
+        );
+
+        // The check worker returns the same changes as hex
+        let response = check::check(&check::Request {
+            generation: 1,
+            text: CHECKSIG.to_string(),
+            variants: marked_texts(CHECKSIG),
+        });
+        let changed = response
+            .changed
+            .unwrap()
+            .iter()
+            .map(|cmrs| cmrs.iter().map(|cmr| cmr.parse().unwrap()).collect())
+            .collect();
+        let from_worker = SourceMap::from_changes(CHECKSIG, changed);
+        assert_eq!(owners, from_worker.node_statements(&dag));
+    }
+}
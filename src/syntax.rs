@@ -0,0 +1,454 @@
+use std::fmt;
+
+/// Kind of a Simfony token.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    /// `fn`, `let`, `match`, etc.
+    Keyword,
+    /// Built-in type or type alias.
+    Type,
+    /// Built-in macro such as `assert!`.
+    Macro,
+    /// Jet such as `jet::add_32`.
+    Jet,
+    /// Witness or parameter name such as `witness::SIG` or `param::PK`,
+    /// or a symbol such as `sig::alice`.
+    Witness,
+    /// Name of a function that is called or defined.
+    Function,
+    /// Other identifier.
+    Identifier,
+    /// `true`, `false`, `None`, `Some`, `Left` or `Right`.
+    Constant,
+    /// Decimal, hexadecimal or binary literal.
+    Number,
+    Comment,
+    /// `(`, `)`, `[`, `]`, `{` or `}`.
+    Bracket,
+    /// Operator or separator.
+    Punctuation,
+    Whitespace,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TokenKind::Keyword => "keyword",
+            TokenKind::Type => "type",
+            TokenKind::Macro => "macro",
+            TokenKind::Jet => "jet",
+            TokenKind::Witness => "witness",
+            TokenKind::Function => "function",
+            TokenKind::Identifier => "identifier",
+            TokenKind::Constant => "constant",
+            TokenKind::Number => "number",
+            TokenKind::Comment => "comment",
+            TokenKind::Bracket => "bracket",
+            TokenKind::Punctuation => "punctuation",
+            TokenKind::Whitespace => "whitespace",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Token of a Simfony program.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset of the token in the program text.
+    pub start: usize,
+}
+
+impl Token<'_> {
+    /// Return the byte offset after the token.
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+}
+
+/// Words that Simfony does not accept as names.
+///
+/// A test checks the list against the Simfony parser.
+pub const KEYWORDS: [&str; 6] = ["fn", "let", "match", "mod", "const", "type"];
+pub const CONSTANTS: [&str; 6] = ["true", "false", "None", "Some", "Left", "Right"];
+pub const BUILTIN_TYPES: [&str; 13] = [
+    "bool", "u1", "u2", "u4", "u8", "u16", "u32", "u64", "u128", "u256", "Either", "Option", "List",
+];
+/// Type aliases that are built into Simfony.
+///
+/// A test checks the list against the Simfony parser.
+pub const BUILTIN_ALIASES: [&str; 28] = [
+    "Ctx8",
+    "Pubkey",
+    "Message64",
+    "Message",
+    "Signature",
+    "Scalar",
+    "Fe",
+    "Ge",
+    "Gej",
+    "Point",
+    "Height",
+    "Time",
+    "Distance",
+    "Duration",
+    "Lock",
+    "Outpoint",
+    "Confidential1",
+    "ExplicitAsset",
+    "Asset1",
+    "ExplicitAmount",
+    "Amount1",
+    "ExplicitNonce",
+    "Nonce",
+    "TokenAmount1",
+    "ConfidentialAsset",
+    "ConfidentialAmount",
+    "ConfidentialNonce",
+    "ConfidentialTokenAmount",
+];
+/// Namespaces whose members are lexed as one token.
+const NAMESPACES: [(&str, TokenKind); 5] = [
+    ("jet", TokenKind::Jet),
+    ("witness", TokenKind::Witness),
+    ("param", TokenKind::Witness),
+    ("sig", TokenKind::Witness),
+    ("preimage", TokenKind::Witness),
+];
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Return the length of the prefix of `text` whose characters satisfy `predicate`.
+fn prefix_len(text: &str, predicate: impl Fn(char) -> bool) -> usize {
+    text.find(|c: char| !predicate(c)).unwrap_or(text.len())
+}
+
+/// Split the program `text` into tokens.
+///
+/// The lexer never fails: the concatenation of all tokens is the original text.
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let type_aliases = type_aliases(text);
+    let mut tokens = vec![];
+    let mut start = 0;
+
+    while start < text.len() {
+        let rest = &text[start..];
+        let c = rest.chars().next().expect("rest is not empty");
+        let (kind, len) = if c.is_whitespace() {
+            (TokenKind::Whitespace, prefix_len(rest, char::is_whitespace))
+        } else if rest.starts_with("//") {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let len = comment.find("*/").map_or(rest.len(), |index| index + 4);
+            (TokenKind::Comment, len)
+        } else if c.is_ascii_digit() {
+            (TokenKind::Number, prefix_len(rest, is_identifier_char))
+        } else if is_identifier_char(c) {
+            let len = prefix_len(rest, is_identifier_char);
+            let word = &rest[..len];
+            let after_word = &rest[len..];
+            let namespace = NAMESPACES
+                .iter()
+                .find(|(namespace, _)| *namespace == word && after_word.starts_with("::"));
+            if let Some((_, kind)) = namespace {
+                let member_len = prefix_len(&after_word[2..], is_identifier_char);
+                (*kind, len + 2 + member_len)
+            } else if after_word.starts_with('!') {
+                (TokenKind::Macro, len + 1)
+            } else if CONSTANTS.contains(&word) {
+                (TokenKind::Constant, len)
+            } else if BUILTIN_TYPES.contains(&word)
+                || type_aliases.contains(&word)
+                || BUILTIN_ALIASES.contains(&word)
+            {
+                (TokenKind::Type, len)
+            } else if KEYWORDS.contains(&word) {
+                (TokenKind::Keyword, len)
+            } else if after_word.trim_start().starts_with('(')
+                || tokens
+                    .iter()
+                    .rev()
+                    .find(|token: &&Token| token.kind != TokenKind::Whitespace)
+                    .is_some_and(|token| token.text == "fn")
+            {
+                (TokenKind::Function, len)
+            } else {
+                (TokenKind::Identifier, len)
+            }
+        } else if "()[]{}".contains(c) {
+            (TokenKind::Bracket, 1)
+        } else if rest.starts_with("->") || rest.starts_with("=>") || rest.starts_with("::") {
+            (TokenKind::Punctuation, 2)
+        } else {
+            (TokenKind::Punctuation, c.len_utf8())
+        };
+
+        tokens.push(Token {
+            kind,
+            text: &rest[..len],
+            start,
+        });
+        start += len;
+    }
+
+    tokens
+}
+
+/// Return the names of the type aliases that the program `text` defines.
+pub fn type_aliases(text: &str) -> Vec<&str> {
+    text.match_indices("type ")
+        .filter(|(index, _)| {
+            !text[..*index]
+                .chars()
+                .next_back()
+                .is_some_and(is_identifier_char)
+        })
+        .filter_map(|(index, _)| {
+            let rest = text[index + "type ".len()..].trim_start();
+            let len = prefix_len(rest, is_identifier_char);
+            match len {
+                0 => None,
+                _ => Some(&rest[..len]),
+            }
+        })
+        .collect()
+}
+
+/// Split `text` at the commas that are not nested inside brackets.
+///
+/// The parts are trimmed and empty parts are skipped.
+pub fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0i32;
+    let mut part_start = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&text[part_start..index]);
+                part_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[part_start..]);
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+fn closing_bracket(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        _ => None,
+    }
+}
+
+fn opening_bracket(close: char) -> Option<char> {
+    match close {
+        ')' => Some('('),
+        ']' => Some('['),
+        '}' => Some('{'),
+        _ => None,
+    }
+}
+
+/// Return the byte offset of the bracket that matches the bracket at byte offset `offset`.
+///
+/// Brackets inside comments are ignored.
+pub fn matching_bracket(tokens: &[Token], offset: usize) -> Option<usize> {
+    let index = tokens
+        .iter()
+        .position(|token| token.start == offset && token.kind == TokenKind::Bracket)?;
+    let bracket = tokens[index].text.chars().next()?;
+    let brackets = tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Bracket);
+
+    if let Some(close) = closing_bracket(bracket) {
+        let mut depth = 0;
+        for token in brackets.filter(|token| offset < token.start) {
+            match token.text.chars().next()? {
+                c if c == bracket => depth += 1,
+                c if c == close && depth == 0 => return Some(token.start),
+                c if c == close => depth -= 1,
+                _ => {}
+            }
+        }
+    } else if let Some(open) = opening_bracket(bracket) {
+        let mut depth = 0;
+        for token in brackets.rev().filter(|token| token.start < offset) {
+            match token.text.chars().next()? {
+                c if c == bracket => depth += 1,
+                c if c == open && depth == 0 => return Some(token.start),
+                c if c == open => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// Return the pair of matching brackets next to the cursor at byte offset `cursor`.
+///
+/// The bracket right before the cursor takes precedence over the bracket right after it.
+pub fn bracket_pair_at(tokens: &[Token], cursor: usize) -> Option<(usize, usize)> {
+    let before = tokens
+        .iter()
+        .find(|token| token.kind == TokenKind::Bracket && token.end() == cursor);
+    let after = tokens
+        .iter()
+        .find(|token| token.kind == TokenKind::Bracket && token.start == cursor);
+    before
+        .into_iter()
+        .chain(after)
+        .find_map(|token| matching_bracket(tokens, token.start).map(|other| (token.start, other)))
+}
+
+/// Number of spaces per indentation level.
+pub const INDENT: &str = "    ";
+
+/// Return the text that is inserted when the user presses Enter at byte offset `cursor`,
+/// and the byte offset of the cursor inside the inserted text.
+///
+/// The new line keeps the indentation of the current line.
+/// After an opening bracket, the new line is indented one more level.
+/// If the cursor sits between two matching brackets, the closing bracket moves onto its own line.
+pub fn newline_insertion(text: &str, cursor: usize) -> (String, usize) {
+    let line_start = text[..cursor].rfind('\n').map_or(0, |index| index + 1);
+    let indent_len = prefix_len(&text[line_start..cursor], |c| c == ' ' || c == '\t');
+    let indent = &text[line_start..line_start + indent_len];
+    let before = text[..cursor].trim_end_matches(' ').chars().next_back();
+    let after = text[cursor..].chars().next();
+
+    match before.and_then(closing_bracket) {
+        Some(close) if after == Some(close) => {
+            let inner = format!("\n{indent}{INDENT}");
+            let insertion = format!("{inner}\n{indent}");
+            (insertion, inner.len())
+        }
+        Some(..) => {
+            let insertion = format!("\n{indent}{INDENT}");
+            let len = insertion.len();
+            (insertion, len)
+        }
+        None => {
+            let insertion = format!("\n{indent}");
+            let len = insertion.len();
+            (insertion, len)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simfony::TemplateProgram;
+
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn tokenize_program() {
+        let text = r#"type Pair = (u32, Signature);
+
+/* block
+   comment */
+fn add(a: u32, b: u32) -> u32 {
+    let (_, sum): (bool, u32) = jet::add_32(a, b); // line comment
+    assert!(jet::le_32(sum, 0xff));
+    match witness::X {
+        Left(x: Pair) => sig::alice,
+        Right(y: bool) => param::Y,
+    }
+}"#;
+        let tokens = tokenize(text);
+        assert_eq!(
+            text,
+            tokens.iter().map(|token| token.text).collect::<String>()
+        );
+
+        let kind_of = |text: &str| {
+            tokens
+                .iter()
+                .find(|token| token.text == text)
+                .map(|token| token.kind)
+        };
+        assert_eq!(Some(TokenKind::Keyword), kind_of("type"));
+        assert_eq!(Some(TokenKind::Type), kind_of("Pair"));
+        assert_eq!(Some(TokenKind::Type), kind_of("Signature"));
+        assert_eq!(Some(TokenKind::Comment), kind_of("/* block\n   comment */"));
+        assert_eq!(Some(TokenKind::Comment), kind_of("// line comment"));
+        assert_eq!(Some(TokenKind::Function), kind_of("add"));
+        assert_eq!(Some(TokenKind::Jet), kind_of("jet::add_32"));
+        assert_eq!(Some(TokenKind::Macro), kind_of("assert!"));
+        assert_eq!(Some(TokenKind::Number), kind_of("0xff"));
+        assert_eq!(Some(TokenKind::Witness), kind_of("witness::X"));
+        assert_eq!(Some(TokenKind::Witness), kind_of("sig::alice"));
+        assert_eq!(Some(TokenKind::Witness), kind_of("param::Y"));
+        assert_eq!(Some(TokenKind::Constant), kind_of("Left"));
+        assert_eq!(Some(TokenKind::Identifier), kind_of("sum"));
+        assert_eq!(Some(TokenKind::Punctuation), kind_of("=>"));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn check_builtins_with_simfony() {
+        let accepts = |program: String| TemplateProgram::new(program.as_str()).is_ok();
+        let is_name = |word: &str| accepts(format!("fn main() {{\n    let {word}: u8 = 0;\n}}"));
+        let is_type = |word: &str| accepts(format!("type WebideAlias = {word};\n\nfn main() {{}}"));
+
+        for keyword in KEYWORDS {
+            assert!(!is_name(keyword), "`{keyword}` should be a keyword");
+        }
+        for word in ["amount", "sum", "alice", "Fn"] {
+            assert!(is_name(word), "`{word}` should not be a keyword");
+        }
+        for alias in BUILTIN_ALIASES {
+            assert!(is_type(alias), "`{alias}` should be a built-in alias");
+        }
+        for word in ["Pair", "Alice", "Left", "signature"] {
+            assert!(!is_type(word), "`{word}` should not be a built-in alias");
+        }
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn match_brackets() {
+        let text = "f(a, [b], /* ( */ {c})";
+        let tokens = tokenize(text);
+        assert_eq!(Some(21), matching_bracket(&tokens, 1));
+        assert_eq!(Some(1), matching_bracket(&tokens, 21));
+        assert_eq!(Some(7), matching_bracket(&tokens, 5));
+        assert_eq!(None, matching_bracket(&tokens, 0));
+        assert_eq!(Some((21, 1)), bracket_pair_at(&tokens, 22));
+        assert_eq!(Some((18, 20)), bracket_pair_at(&tokens, 18));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn auto_indent() {
+        let text = "fn main() {}";
+        assert_eq!(
+            ("\n    \n".to_string(), 5),
+            newline_insertion(text, text.len() - 1)
+        );
+        let text = "    let a = (";
+        assert_eq!(
+            ("\n        ".to_string(), 9),
+            newline_insertion(text, text.len())
+        );
+        let text = "    let a = 1;";
+        assert_eq!(
+            ("\n    ".to_string(), 5),
+            newline_insertion(text, text.len())
+        );
+    }
+}
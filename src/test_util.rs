@@ -0,0 +1,55 @@
+//! Fixtures that the tests of several modules share.
+
+use std::sync::Arc;
+
+use simfony::elements::hashes::Hash;
+use simfony::elements::secp256k1_zkp as secp256k1;
+use simfony::simplicity::jet::elements::ElementsEnv;
+use simfony::simplicity::Cmr;
+use simfony::{elements, CompiledProgram, SatisfiedProgram};
+
+use crate::examples::Example;
+use crate::transaction::TxParams;
+use crate::util::{HashedData, SigningKeys};
+
+/// Compile the `example` and satisfy it on the transaction with the parameters
+/// that `params` returns for the CMR of the program.
+///
+/// Return the satisfied program with its transaction environment.
+pub fn satisfy_example<F>(
+    example: Example,
+    signing_keys: &SigningKeys,
+    hashed_data: &HashedData,
+    params: F,
+) -> (SatisfiedProgram, ElementsEnv<Arc<elements::Transaction>>)
+where
+    F: FnOnce(Cmr) -> TxParams,
+{
+    let arguments = example.arguments(&signing_keys.public_keys, &hashed_data.hashes);
+    let compiled =
+        CompiledProgram::new(example.template_text(), arguments).expect("example should compile");
+    let tx_env = params(compiled.commit().cmr()).tx_env(compiled.commit().cmr());
+    let sighash_all =
+        secp256k1::Message::from_digest(tx_env.c_tx_env().sighash_all().to_byte_array());
+    let witness = example.witness(
+        &signing_keys.secret_keys,
+        &hashed_data.preimages,
+        sighash_all,
+    );
+    let satisfied = compiled
+        .satisfy(witness)
+        .expect("example should be satisfied");
+
+    (satisfied, tx_env)
+}
+
+/// Compile the `example` and satisfy it on its own transaction.
+///
+/// Return the satisfied program with its transaction environment.
+pub fn satisfied_and_tx_env(
+    example: Example,
+    signing_keys: &SigningKeys,
+    hashed_data: &HashedData,
+) -> (SatisfiedProgram, ElementsEnv<Arc<elements::Transaction>>) {
+    satisfy_example(example, signing_keys, hashed_data, |_| example.params())
+}
@@ -107,4 +107,12 @@ impl TxParams {
         };
         tx
     }
+
+    /// Return the weight of the witness stack that spends the `pruned` program.
+    ///
+    /// Witness data costs one weight unit per byte, including the length prefixes.
+    pub fn witness_weight(&self, pruned: &RedeemNode<Elements>) -> usize {
+        let tx = self.transaction(pruned);
+        elements::encode::serialize(&tx.input[0].witness.script_witness).len()
+    }
 }
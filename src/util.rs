@@ -10,6 +10,7 @@ use simplicity::dag::{DagLike, MaxSharing, NoSharing};
 use simplicity::jet::Elements;
 use simplicity::node::Inner;
 use simplicity::{node, RedeemNode};
+use wasm_bindgen::JsValue;
 
 #[derive(Clone, Debug)]
 pub struct SigningKeys {
@@ -30,6 +31,13 @@ impl SigningKeys {
             public_keys,
         }
     }
+
+    /// Sign the `message` with the secret key at the given `index`.
+    ///
+    /// Signing is deterministic, so the same message always has the same signature.
+    pub fn sign(&self, index: usize, message: secp256k1::Message) -> secp256k1::schnorr::Signature {
+        secp256k1::SECP256K1.sign_schnorr_no_aux_rand(&message, &self.secret_keys[index])
+    }
 }
 
 impl Default for SigningKeys {
@@ -68,6 +76,44 @@ impl Default for HashedData {
     }
 }
 
+/// Describe the JavaScript `error` to the user.
+pub fn js_error(error: JsValue) -> String {
+    format!("{error:?}")
+}
+
+/// Return the name of the key with the given index.
+pub fn key_name(index: usize) -> &'static str {
+    match index {
+        0 => "Alice",
+        1 => "Bob",
+        2 => "Charlie",
+        3 => "David",
+        4 => "Eve",
+        5 => "Frank",
+        6 => "Grace",
+        7 => "Heidi",
+        8 => "Ivan",
+        9 => "Judy",
+        10 => "Kevin",
+        11 => "Luther",
+        12 => "Mallory",
+        13 => "Niaj",
+        14 => "Olivia",
+        15 => "Peggy",
+        16 => "Quentin",
+        17 => "Rupert",
+        18 => "Sybil",
+        19 => "Trent",
+        20 => "Ursula",
+        21 => "Victor",
+        22 => "Wendy",
+        23 => "Xavier",
+        24 => "Yvonne",
+        25 => "Zoe",
+        _ => "Unnamed",
+    }
+}
+
 /// A counter in the range `1..26`.
 #[derive(Copy, Clone, Debug)]
 pub struct Counter26(usize);
@@ -205,6 +251,14 @@ pub fn liquid_testnet_genesis() -> elements::BlockHash {
     ])
 }
 
+pub fn liquid_genesis() -> elements::BlockHash {
+    elements::BlockHash::from_byte_array([
+        0x03, 0x60, 0x20, 0x8a, 0x88, 0x96, 0x92, 0x37, 0x2c, 0x8d, 0x68, 0xb0, 0x84, 0xa6, 0x2e,
+        0xfd, 0xf6, 0x0e, 0xa1, 0xa3, 0x59, 0xa0, 0x4c, 0x94, 0xb2, 0x0d, 0x22, 0x36, 0x58, 0x27,
+        0x66, 0x14,
+    ])
+}
+
 pub fn liquid_testnet_faucet_script_pubkey() -> elements::Script {
     "tlq1qqd0qxdqsag3t63gfzq4xr25fcjvsujun6ycx9jtd9jufarrrwtseyf05kf0qz62u09wpnj064cycfvtlxuz4xj4j48wxpsrs2"
         .parse::<elements::Address>()
@@ -0,0 +1,307 @@
+use std::ops::Range;
+
+use simfony::parse::{self, ParseFromStr};
+
+use crate::syntax::{self, Token, TokenKind};
+use crate::witness::{self, Symbol};
+
+/// Normalize the whitespace of the Simfony program `text`.
+///
+/// Statements, match arms and blocks are put on their own lines and indented by nesting depth.
+/// Spacing between tokens is normalized. Comments and single blank lines are kept.
+/// The witness module and the param module are moved to the top of the program, in this order.
+///
+/// This is not a pretty printer: tokens are printed as written,
+/// and line breaks inside statements and expressions are kept, so long lines are not wrapped.
+/// Printing from the syntax tree of the Simfony parser would drop comments.
+///
+/// Programs that cannot be parsed are left alone.
+pub fn normalize(text: &str) -> Result<String, String> {
+    // Parsing does not depend on the types of symbols, so custom preimages are left out
+    let resolved = witness::resolve(text, |symbol| Ok(symbol.placeholder(&[])))?;
+    parse::Program::parse_from_str(&resolved).map_err(|error| error.to_string())?;
+    Ok(normalize_unchecked(text))
+}
+
+fn normalize_unchecked(text: &str) -> String {
+    print(&reorder_items(text))
+}
+
+/// Return the byte ranges of the top-level items of the program `text`.
+///
+/// Comments above an item belong to that item.
+/// Comments on the same line as the end of an item belong to the item that ends.
+fn items(text: &str) -> Vec<Range<usize>> {
+    let mut items: Vec<Range<usize>> = vec![];
+    let mut item_start = None;
+    let mut depth = 0i32;
+
+    for token in syntax::tokenize(text) {
+        match token.kind {
+            TokenKind::Whitespace => continue,
+            TokenKind::Comment if item_start.is_none() => {
+                if let Some(last) = items.last_mut() {
+                    if !text[last.end..token.start].contains('\n') {
+                        last.end = token.end();
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        let start = *item_start.get_or_insert(token.start);
+        match (token.kind, token.text) {
+            (TokenKind::Bracket, "{" | "(" | "[") => depth += 1,
+            (TokenKind::Bracket, "}" | ")" | "]") => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 && matches!(token.text, "}" | ";") {
+            items.push(start..token.end());
+            item_start = None;
+        }
+    }
+    if let Some(start) = item_start {
+        items.push(start..text.len());
+    }
+
+    items
+}
+
+/// Return the rank of the item in the canonical order of items.
+fn item_rank(item: &str) -> usize {
+    let mut head = syntax::tokenize(item)
+        .into_iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+        .map(|token| token.text);
+    match (head.next(), head.next()) {
+        (Some("mod"), Some("witness")) => 0,
+        (Some("mod"), Some("param")) => 1,
+        _ => 2,
+    }
+}
+
+/// Move the witness module and the param module to the top of the program `text`,
+/// and separate all top-level items by a blank line.
+fn reorder_items(text: &str) -> String {
+    let mut items: Vec<&str> = items(text).into_iter().map(|range| &text[range]).collect();
+    items.sort_by_key(|item| item_rank(item));
+    items.join("\n\n")
+}
+
+/// Return true if there is a space between the adjacent tokens `prev` and `next`.
+fn needs_space(prev: &Token, next: &Token) -> bool {
+    match (prev.text, next.text) {
+        ("{", "}") => false,
+        (_, ")" | "]" | "," | ";" | ":" | "::" | "<" | ">") => false,
+        ("(" | "[" | "<" | "::", _) => false,
+        // Call with generic arguments, such as `unwrap_left::<u8>(x)`
+        (">", "(") => false,
+        (_, "(") => !matches!(
+            prev.kind,
+            TokenKind::Function
+                | TokenKind::Jet
+                | TokenKind::Macro
+                | TokenKind::Constant
+                | TokenKind::Identifier
+                | TokenKind::Type
+        ),
+        _ => true,
+    }
+}
+
+/// Return the opening bracket if `token` opens a nesting level.
+///
+/// Angle brackets of generic types are treated like brackets.
+fn opening(token: &Token) -> Option<char> {
+    match (token.kind, token.text) {
+        (TokenKind::Bracket | TokenKind::Punctuation, "(" | "[" | "{" | "<") => {
+            token.text.chars().next()
+        }
+        _ => None,
+    }
+}
+
+/// Return the matching opening bracket if `token` closes a nesting level.
+fn closing(token: &Token) -> Option<char> {
+    match (token.kind, token.text) {
+        (TokenKind::Bracket, ")") => Some('('),
+        (TokenKind::Bracket, "]") => Some('['),
+        (TokenKind::Bracket, "}") => Some('{'),
+        (TokenKind::Punctuation, ">") => Some('<'),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    lines: Vec<String>,
+    line: String,
+    /// Open brackets, with the index of the line where they were opened.
+    open: Vec<(char, usize)>,
+}
+
+impl Printer {
+    fn break_line(&mut self, blank: bool) {
+        let line = std::mem::take(&mut self.line);
+        self.lines.push(line.trim_end().to_string());
+        if blank {
+            self.lines.push(String::new());
+        }
+    }
+
+    /// Return the indentation level of a new line.
+    ///
+    /// Brackets that are opened on the same line add only one level.
+    fn indentation(&self) -> usize {
+        let mut levels = self.open.iter().map(|(_, line)| line).collect::<Vec<_>>();
+        levels.dedup();
+        levels.len()
+    }
+
+    /// Return the innermost open bracket.
+    fn innermost(&self) -> Option<char> {
+        self.open.last().map(|(bracket, _)| *bracket)
+    }
+
+    fn push(&mut self, token: &Token) {
+        if let Some(open) = closing(token) {
+            if self.innermost() == Some(open) {
+                self.open.pop();
+            }
+        }
+        if self.line.is_empty() {
+            self.line = syntax::INDENT.repeat(self.indentation());
+        }
+        match token.kind {
+            TokenKind::Comment => self.line.push_str(token.text.trim_end()),
+            _ => self.line.push_str(token.text),
+        }
+        if let Some(open) = opening(token) {
+            self.open.push((open, self.lines.len()));
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if !self.line.is_empty() {
+            self.break_line(false);
+        }
+        self.lines.join("\n")
+    }
+}
+
+/// Print the program `text` with normalized line breaks, indentation and spacing.
+fn print(text: &str) -> String {
+    let mut printer = Printer::default();
+    let mut prev: Option<Token> = None;
+    // Number of line breaks in the original text before the current token
+    let mut newlines = 0;
+    // Whether the current token must start a new line
+    let mut force_break = false;
+
+    for token in syntax::tokenize(text) {
+        if token.kind == TokenKind::Whitespace {
+            newlines += token.text.matches('\n').count();
+            continue;
+        }
+        if let Some(prev) = prev {
+            let is_empty_block = prev.text == "{" && token.text == "}";
+            let is_trailing_comment = token.kind == TokenKind::Comment && newlines == 0;
+            let break_before = !is_empty_block
+                && !is_trailing_comment
+                && (force_break || 0 < newlines || token.text == "}");
+            if break_before {
+                let blank = 1 < newlines && prev.text != "{" && token.text != "}";
+                printer.break_line(blank);
+            } else if needs_space(&prev, &token) {
+                printer.line.push(' ');
+            }
+        }
+        printer.push(&token);
+
+        force_break = match (token.kind, token.text) {
+            (TokenKind::Bracket, "{") => true,
+            (TokenKind::Punctuation, ";") => matches!(printer.innermost(), None | Some('{')),
+            (TokenKind::Punctuation, ",") => printer.innermost() == Some('{'),
+            (TokenKind::Comment, comment) => comment.starts_with("//"),
+            _ => false,
+        };
+        prev = Some(token);
+        newlines = 0;
+    }
+
+    printer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples;
+    use crate::util::{HashedData, SigningKeys};
+    use simfony::CompiledProgram;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn normalize_text() {
+        let text = r#"fn   checksig(pk:Pubkey,sig : Signature){ jet::bip_0340_verify( (pk, jet::sig_all_hash()) ,sig) }
+// Main function
+
+
+fn main() {
+  let out: Either<u256,Ctx8> = for_while :: <f>(ctx, ()); // loop
+  match witness::X { Left(x: u8) => {}, Right(y: [u8;2]) => assert!(jet::eq_8(x,
+  y)), }
+}
+mod param { const A: u8 = 1; }
+mod witness {
+    const X: Either<u8, [u8; 2]> = Left(1);
+}"#;
+        let expected = r#"mod witness {
+    const X: Either<u8, [u8; 2]> = Left(1);
+}
+
+mod param {
+    const A: u8 = 1;
+}
+
+fn checksig(pk: Pubkey, sig: Signature) {
+    jet::bip_0340_verify((pk, jet::sig_all_hash()), sig)
+}
+
+// Main function
+
+fn main() {
+    let out: Either<u256, Ctx8> = for_while::<f>(ctx, ()); // loop
+    match witness::X {
+        Left(x: u8) => {},
+        Right(y: [u8; 2]) => assert!(jet::eq_8(x,
+            y)),
+    }
+}"#;
+        let formatted = normalize_unchecked(text);
+        assert_eq!(expected, formatted);
+        assert_eq!(formatted, normalize_unchecked(&formatted));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn normalize_examples() {
+        let signing_keys = SigningKeys::default();
+        let hashed_data = HashedData::default();
+
+        for name in examples::keys() {
+            let example = examples::get(name).unwrap();
+            let text = example.template_text();
+            let formatted = normalize(text).unwrap();
+            assert_eq!(formatted, normalize(&formatted).unwrap(), "{name}");
+
+            let cmr = |text: &str| {
+                let arguments = example.arguments(&signing_keys.public_keys, &hashed_data.hashes);
+                CompiledProgram::new(text, arguments)
+                    .unwrap()
+                    .commit()
+                    .cmr()
+            };
+            assert_eq!(cmr(text), cmr(&formatted), "{name}");
+        }
+    }
+}
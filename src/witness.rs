@@ -0,0 +1,634 @@
+use std::fmt;
+use std::ops::Range;
+
+use simfony::parse::ParseFromStr;
+use simfony::{ResolvedType, TemplateProgram};
+
+use crate::form::FormValue;
+use crate::hash::{self, CustomPreimage};
+use crate::util::key_name;
+
+/// Symbolic reference to a value in the key store or hash store.
+///
+/// Symbols are written as `sig::alice` (signature of the key "Alice")
+/// or `preimage::0` (preimage 0 of the hash store).
+/// They can be used in place of witness values.
+///
+/// Preimages 0 to 25 are the random preimages of the hash store.
+/// The custom preimages of the hash store follow from index 26 on.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Symbol {
+    /// Signature of the key with the given index over the current signed data.
+    Signature(usize),
+    /// Preimage with the given index.
+    Preimage(usize),
+}
+
+const SIGNATURE_NAMESPACE: &str = "sig";
+const PREIMAGE_NAMESPACE: &str = "preimage";
+/// Number of random preimages of the hash store, which come before the custom preimages.
+const RANDOM_PREIMAGES: usize = 26;
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl Symbol {
+    /// Parse the symbol `name` inside the given `namespace`.
+    fn parse(namespace: &str, name: &str) -> Result<Self, String> {
+        match namespace {
+            SIGNATURE_NAMESPACE => (0..26)
+                .find(|&index| key_name(index).eq_ignore_ascii_case(name))
+                .map(Symbol::Signature)
+                .ok_or_else(|| format!("`{namespace}::{name}`: There is no key called `{name}`")),
+            PREIMAGE_NAMESPACE => name
+                .parse::<usize>()
+                .ok()
+                .map(Symbol::Preimage)
+                .ok_or_else(|| format!("`{namespace}::{name}`: Preimage index must be a number")),
+            _ => unreachable!("namespace should be known"),
+        }
+    }
+
+    /// Parse the symbol token `text`, such as `sig::alice` or `preimage::0`.
+    ///
+    /// Return an error if the text is no symbol or if the symbol does not exist.
+    pub fn from_token(text: &str) -> Result<Self, String> {
+        match text.split_once("::") {
+            Some((namespace @ (SIGNATURE_NAMESPACE | PREIMAGE_NAMESPACE), name)) => {
+                Self::parse(namespace, name)
+            }
+            _ => Err(format!("`{text}` is no symbol")),
+        }
+    }
+
+    /// Return the length in bytes of the preimage with the given `index`.
+    ///
+    /// Random preimages are 32 bytes long, and so are custom preimages
+    /// that are missing from `custom_preimages`.
+    fn preimage_len(index: usize, custom_preimages: &[CustomPreimage]) -> usize {
+        index
+            .checked_sub(RANDOM_PREIMAGES)
+            .and_then(|custom_index| custom_preimages.get(custom_index))
+            .map_or(32, |custom| custom.preimage.len())
+    }
+
+    /// Return the type of the symbol's value, which is the type of its placeholder.
+    ///
+    /// Custom preimages take their length from `custom_preimages`.
+    pub fn ty(self, custom_preimages: &[CustomPreimage]) -> String {
+        match self {
+            Symbol::Signature(..) => "Signature".to_string(),
+            Symbol::Preimage(index) => match Self::preimage_len(index, custom_preimages) {
+                1 => "u8".to_string(),
+                2 => "u16".to_string(),
+                4 => "u32".to_string(),
+                8 => "u64".to_string(),
+                16 => "u128".to_string(),
+                32 => "u256".to_string(),
+                len => format!("[u8; {len}]"),
+            },
+        }
+    }
+
+    /// Return a value of the same type as the symbol's value.
+    ///
+    /// The placeholder does not change the CMR of the program.
+    /// Custom preimages take their length from `custom_preimages`.
+    pub fn placeholder(self, custom_preimages: &[CustomPreimage]) -> String {
+        match self {
+            Symbol::Signature(..) => format!("0x{}", "00".repeat(64)),
+            Symbol::Preimage(index) => {
+                hash::simfony_bytes(&vec![0; Self::preimage_len(index, custom_preimages)])
+            }
+        }
+    }
+}
+
+/// Replace all symbols in the program `text` by the values that `value` returns.
+///
+/// Return an error if the text contains a symbol that does not exist
+/// or if `value` returns an error.
+pub fn resolve<F>(text: &str, value: F) -> Result<String, String>
+where
+    F: FnMut(Symbol) -> Result<String, String>,
+{
+    resolve_mapped(text, value).map(|resolved| resolved.text)
+}
+
+/// Program text whose symbols were replaced by their values.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Resolved {
+    /// Resolved program text.
+    pub text: String,
+    /// Byte ranges of the replaced symbols in the original text
+    /// and of their values in the resolved text, in order of occurrence.
+    replacements: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl Resolved {
+    /// Return the given program `text`, which contains no symbols.
+    pub fn unchanged(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            replacements: vec![],
+        }
+    }
+
+    /// Return the byte offset in the original text
+    /// that corresponds to the given byte `offset` of the resolved text.
+    ///
+    /// Offsets inside a value are moved to the start of its symbol.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let mut last = None;
+        for (symbol, value) in &self.replacements {
+            if offset < value.start {
+                break;
+            }
+            if offset < value.end {
+                return symbol.start;
+            }
+            last = Some((symbol, value));
+        }
+        match last {
+            Some((symbol, value)) => symbol.end + (offset - value.end),
+            None => offset,
+        }
+    }
+}
+
+/// Replace all symbols in the program `text` by the values that `value` returns,
+/// and remember where the values were inserted.
+///
+/// Return an error if the text contains a symbol that does not exist
+/// or if `value` returns an error.
+pub fn resolve_mapped<F>(text: &str, mut value: F) -> Result<Resolved, String>
+where
+    F: FnMut(Symbol) -> Result<String, String>,
+{
+    let mut resolved = String::with_capacity(text.len());
+    let mut replacements = vec![];
+    let mut rest = text;
+
+    while let Some((start, namespace)) = find_namespace(rest) {
+        let after_namespace = &rest[start + namespace.len() + 2..];
+        let name_len = after_namespace
+            .find(|c| !is_identifier_char(c))
+            .unwrap_or(after_namespace.len());
+        let symbol = Symbol::parse(namespace, &after_namespace[..name_len])?;
+        resolved.push_str(&rest[..start]);
+        let symbol_start = text.len() - rest.len() + start;
+        let symbol_end = text.len() - after_namespace.len() + name_len;
+        let value_start = resolved.len();
+        resolved.push_str(&value(symbol)?);
+        replacements.push((symbol_start..symbol_end, value_start..resolved.len()));
+        rest = &after_namespace[name_len..];
+    }
+
+    resolved.push_str(rest);
+    Ok(Resolved {
+        text: resolved,
+        replacements,
+    })
+}
+
+/// Find the first occurrence of `namespace::` that is not part of a longer identifier.
+fn find_namespace(text: &str) -> Option<(usize, &'static str)> {
+    [SIGNATURE_NAMESPACE, PREIMAGE_NAMESPACE]
+        .into_iter()
+        .filter_map(|namespace| {
+            let pattern = format!("{namespace}::");
+            text.match_indices(pattern.as_str())
+                .find(|(index, _)| {
+                    !text[..*index]
+                        .chars()
+                        .next_back()
+                        .is_some_and(is_identifier_char)
+                })
+                .map(|(index, _)| (index, namespace))
+        })
+        .min_by_key(|(index, _)| *index)
+}
+
+/// Module of a Simfony program that assigns values to names.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Module {
+    /// Witness values, which are provided when the program is spent.
+    Witness,
+    /// Parameters, which are provided when the program is committed.
+    Param,
+}
+
+impl Module {
+    pub const ALL: [Self; 2] = [Self::Witness, Self::Param];
+
+    fn name(self) -> &'static str {
+        match self {
+            Module::Witness => "witness",
+            Module::Param => "param",
+        }
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mod {}", self.name())
+    }
+}
+
+/// Problem with an entry of the witness module or the param module.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Diagnostic {
+    /// The program uses a name that the module does not assign.
+    Missing {
+        module: Module,
+        name: String,
+        ty: ResolvedType,
+    },
+    /// The module assigns a name that the program does not use.
+    Extra { module: Module, name: String },
+    /// The module assigns a value of a different type than the program expects.
+    IllTyped {
+        module: Module,
+        name: String,
+        expected: ResolvedType,
+        found: ResolvedType,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::Missing { module, name, ty } => {
+                write!(f, "`{module}` is missing `{name}` of type `{ty}`")
+            }
+            Diagnostic::Extra { module, name } => {
+                write!(f, "`{module}` assigns `{name}`, which the program does not use")
+            }
+            Diagnostic::IllTyped {
+                module,
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{module}` assigns `{name}` of type `{found}`, but the program expects `{expected}`"
+            ),
+        }
+    }
+}
+
+/// Name and type of a value that the program uses or that a module assigns.
+type Entry = (String, ResolvedType);
+
+/// Return the names and types that the program uses inside the given module.
+///
+/// The entries are sorted by name.
+fn declared_entries(program: &TemplateProgram, module: Module) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = match module {
+        Module::Witness => program
+            .witness_types()
+            .iter()
+            .map(|(name, ty)| (name.to_string(), ty.clone()))
+            .collect(),
+        Module::Param => program
+            .parameters()
+            .iter()
+            .map(|(name, ty)| (name.to_string(), ty.clone()))
+            .collect(),
+    };
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+/// Return the names and types that the given module of the program `text` assigns.
+///
+/// The entries are sorted by name. A module that does not exist assigns nothing.
+fn assigned_entries(text: &str, module: Module) -> Result<Vec<Entry>, String> {
+    if !text.contains(&module.to_string()) {
+        return Ok(vec![]);
+    }
+    let mut entries: Vec<Entry> = match module {
+        Module::Witness => simfony::WitnessValues::parse_from_str(text)
+            .map_err(|error| error.to_string())?
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.ty().clone()))
+            .collect(),
+        Module::Param => simfony::Arguments::parse_from_str(text)
+            .map_err(|error| error.to_string())?
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.ty().clone()))
+            .collect(),
+    };
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(entries)
+}
+
+/// Return the module, name and type of each witness value and parameter
+/// that the program `text` uses.
+///
+/// Symbols are typed after the `custom_preimages` of the hash store.
+/// Return an error if the program cannot be parsed.
+pub fn declarations(
+    text: &str,
+    custom_preimages: &[CustomPreimage],
+) -> Result<Vec<(Module, String, ResolvedType)>, String> {
+    let text = resolve(text, |symbol| Ok(symbol.placeholder(custom_preimages)))?;
+    let program = TemplateProgram::new(text.as_str())?;
+    Ok(Module::ALL
+        .into_iter()
+        .flat_map(|module| {
+            declared_entries(&program, module)
+                .into_iter()
+                .map(move |(name, ty)| (module, name, ty))
+        })
+        .collect())
+}
+
+/// Return the names and types that the given module of the program `text` assigns.
+///
+/// Symbols are typed after the `custom_preimages` of the hash store.
+/// Return an error if the module cannot be parsed.
+pub fn assignments(
+    text: &str,
+    module: Module,
+    custom_preimages: &[CustomPreimage],
+) -> Result<Vec<(String, ResolvedType)>, String> {
+    let text = resolve(text, |symbol| Ok(symbol.placeholder(custom_preimages)))?;
+    assigned_entries(&text, module)
+}
+
+/// Compare the names and types that the program `text` uses
+/// with the names and types that its modules assign.
+///
+/// Symbols are typed after the `custom_preimages` of the hash store.
+/// Return an error if the program cannot be parsed.
+pub fn diagnostics(
+    text: &str,
+    custom_preimages: &[CustomPreimage],
+) -> Result<Vec<Diagnostic>, String> {
+    let text = resolve(text, |symbol| Ok(symbol.placeholder(custom_preimages)))?;
+    let program = TemplateProgram::new(text.as_str())?;
+    let mut diagnostics = vec![];
+
+    for module in Module::ALL {
+        let declared = declared_entries(&program, module);
+        let assigned = assigned_entries(&text, module)?;
+
+        for (name, ty) in &declared {
+            match assigned
+                .iter()
+                .find(|(assigned_name, _)| assigned_name == name)
+            {
+                None => diagnostics.push(Diagnostic::Missing {
+                    module,
+                    name: name.clone(),
+                    ty: ty.clone(),
+                }),
+                Some((_, found)) if found != ty => diagnostics.push(Diagnostic::IllTyped {
+                    module,
+                    name: name.clone(),
+                    expected: ty.clone(),
+                    found: found.clone(),
+                }),
+                Some(..) => {}
+            }
+        }
+        for (name, _) in &assigned {
+            if !declared
+                .iter()
+                .any(|(declared_name, _)| declared_name == name)
+            {
+                diagnostics.push(Diagnostic::Extra {
+                    module,
+                    name: name.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// Return the byte range of the body of the given `module` of the program `text`,
+/// which lies between the curly braces.
+pub fn module_body(text: &str, module: Module) -> Option<Range<usize>> {
+    let module_start = text.find(&module.to_string())?;
+    let body_start = module_start + text[module_start..].find('{')? + 1;
+    let mut depth = 0;
+    for (index, c) in text[body_start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(body_start..body_start + index),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Insert the given `lines` at the end of the given `module` of the program `text`.
+///
+/// Create the module if it does not exist.
+pub fn insert_into_module(text: &mut String, module: Module, lines: &str) {
+    let body = match module_body(text, module) {
+        Some(body) => body,
+        None if text.contains(&module.to_string()) => return,
+        None => {
+            let body = match lines.is_empty() {
+                true => "{}".to_string(),
+                false => format!("{{\n{lines}}}"),
+            };
+            text.insert_str(0, &format!("{module} {body}\n\n"));
+            return;
+        }
+    };
+    if lines.is_empty() {
+        return;
+    }
+    match text[..body.end].ends_with('\n') {
+        true => text.insert_str(body.end, lines),
+        false => text.insert_str(body.end, &format!("\n{lines}")),
+    }
+}
+
+/// Return a line with a typed placeholder for each name of the given `module`
+/// that the `diagnostics` report as missing.
+pub fn stub_lines(diagnostics: &[Diagnostic], module: Module) -> String {
+    diagnostics
+        .iter()
+        .filter_map(|diagnostic| match diagnostic {
+            Diagnostic::Missing {
+                module: missing_module,
+                name,
+                ty,
+            } if *missing_module == module => Some(format!(
+                "    const {name}: {ty} = {};\n",
+                FormValue::placeholder(ty)
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Return the stub lines to insert into the param module and the witness module
+/// of the program `text`, so that every name that the program uses is assigned.
+///
+/// A module is listed if it lacks a name or if it does not exist, so it can be created.
+/// The param module comes first, so the witness module ends up on top
+/// if both modules are created by [`insert_into_module`].
+///
+/// Return an error if the program cannot be parsed.
+pub fn stubs(
+    text: &str,
+    custom_preimages: &[CustomPreimage],
+) -> Result<Vec<(Module, String)>, String> {
+    let diagnostics = diagnostics(text, custom_preimages)?;
+    Ok([Module::Param, Module::Witness]
+        .into_iter()
+        .map(|module| (module, stub_lines(&diagnostics, module)))
+        .filter(|(module, lines)| !lines.is_empty() || !text.contains(&module.to_string()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn resolve_symbols() {
+        let text = r#"mod witness {
+    const ALICE_SIGNATURE: Signature = sig::alice;
+    const BOB_SIGNATURE: Signature = sig::Bob;
+    const PREIMAGE: u256 = preimage::25;
+    const OTHER: u32 = my_sig::alice;
+}"#;
+        let resolved = resolve(text, |symbol| match symbol {
+            Symbol::Signature(index) => Ok(format!("sig{index}")),
+            Symbol::Preimage(index) => Ok(format!("pre{index}")),
+        })
+        .unwrap();
+        let expected = r#"mod witness {
+    const ALICE_SIGNATURE: Signature = sig0;
+    const BOB_SIGNATURE: Signature = sig1;
+    const PREIMAGE: u256 = pre25;
+    const OTHER: u32 = my_sig::alice;
+}"#;
+        assert_eq!(expected, resolved);
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn map_resolved_offsets() {
+        let text = "a = sig::alice; b = preimage::1;";
+        let resolved = resolve_mapped(text, |symbol| Ok(symbol.placeholder(&[]))).unwrap();
+        let b = resolved.text.find('b').unwrap();
+        assert_eq!(text.find('b').unwrap(), resolved.original_offset(b));
+        let semicolon = resolved.text.rfind(';').unwrap();
+        assert_eq!(
+            text.rfind(';').unwrap(),
+            resolved.original_offset(semicolon)
+        );
+        assert_eq!(0, resolved.original_offset(0));
+        assert_eq!(4, resolved.original_offset(4));
+        // Offsets inside a value belong to its symbol
+        assert_eq!(4, resolved.original_offset(10));
+        assert_eq!(text.len(), resolved.original_offset(resolved.text.len()));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn unknown_symbols() {
+        for text in ["sig::nobody", "sig::", "preimage::", "preimage::alice"] {
+            assert!(resolve(text, |symbol| Ok(symbol.placeholder(&[]))).is_err());
+        }
+        // Custom preimages exist only if the hash store has them
+        let custom_preimages = 1;
+        let value = |symbol| match symbol {
+            Symbol::Preimage(index) if 26 + custom_preimages <= index => {
+                Err(format!("There is no preimage {index}"))
+            }
+            symbol => Ok(symbol.placeholder(&[])),
+        };
+        assert!(resolve("preimage::26", value).is_ok());
+        assert!(resolve("preimage::27", value).is_err());
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn generate_stubs() {
+        let text = r#"mod witness {
+    const A: u16 = 1;
+    const D: bool = false;
+}
+
+fn main() {
+    let a: u32 = witness::A;
+    let b: Signature = witness::B;
+    let c: (bool, Option<u8>) = param::C;
+}"#;
+        let problems = diagnostics(text, &[]).expect("program should parse");
+        assert_eq!(4, problems.len());
+        assert!(problems.iter().any(|diagnostic| matches!(diagnostic,
+            Diagnostic::IllTyped { module: Module::Witness, name, .. } if name == "A"
+        )));
+        assert!(problems.iter().any(|diagnostic| matches!(diagnostic,
+            Diagnostic::Missing { module: Module::Witness, name, .. } if name == "B"
+        )));
+        assert!(problems.iter().any(|diagnostic| matches!(diagnostic,
+            Diagnostic::Missing { module: Module::Param, name, .. } if name == "C"
+        )));
+        assert!(problems.iter().any(|diagnostic| matches!(diagnostic,
+            Diagnostic::Extra { module: Module::Witness, name } if name == "D"
+        )));
+
+        let mut stubbed = text.to_string();
+        for (module, lines) in stubs(text, &[]).expect("program should parse") {
+            insert_into_module(&mut stubbed, module, &lines);
+        }
+        assert!(
+            stubbed.starts_with("mod param {\n    const C: (bool, Option<u8>) = (false, None);\n}")
+        );
+        assert!(stubbed.contains(&format!(
+            "    const D: bool = false;\n    const B: [u8; 64] = 0x{};\n}}",
+            "00".repeat(64)
+        )));
+        let problems = diagnostics(&stubbed, &[]).expect("program should parse");
+        assert_eq!(2, problems.len());
+        assert_eq!(
+            Vec::<(Module, String)>::new(),
+            stubs(&stubbed, &[]).unwrap()
+        );
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn custom_preimage_placeholders() {
+        let custom_preimages = [CustomPreimage {
+            preimage: vec![0xff; 20],
+            function: hash::HashFunction::Hash160,
+        }];
+        let placeholder = Symbol::Preimage(26).placeholder(&custom_preimages);
+        assert_eq!(20, placeholder.matches("0x00").count());
+        assert_eq!("[u8; 20]", Symbol::Preimage(26).ty(&custom_preimages));
+        // Random preimages and unknown custom preimages are 32 bytes long
+        assert_eq!("u256", Symbol::Preimage(0).ty(&custom_preimages));
+        assert_eq!("u256", Symbol::Preimage(27).ty(&custom_preimages));
+
+        let text = r#"mod witness {
+    const PREIMAGE: [u8; 20] = preimage::26;
+}
+
+fn main() {
+    let preimage: [u8; 20] = witness::PREIMAGE;
+}"#;
+        let problems = diagnostics(text, &custom_preimages).expect("program should parse");
+        assert_eq!(Vec::<Diagnostic>::new(), problems);
+        let problems = diagnostics(text, &[]).expect("program should parse");
+        assert!(matches!(
+            problems.as_slice(),
+            [Diagnostic::IllTyped { name, .. }] if name == "PREIMAGE"
+        ));
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// Version of the storage schema.
+///
+/// Increase the version when the stored values change, and add a step to [`migrate`]
+/// that converts values of the previous version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Storage key of the list of workspaces.
+pub const WORKSPACES_KEY: &str = "workspaces";
+
+/// Name of the workspace that is created if there is none.
+pub const DEFAULT_WORKSPACE: &str = "Default";
+
+/// Store of string values under string keys, such as the browser's local storage.
+pub trait Store {
+    fn get(&self, key: &str) -> Option<String>;
+
+    fn set(&mut self, key: &str, value: &str);
+
+    fn remove(&mut self, key: &str);
+
+    /// Return all keys that have a value.
+    fn keys(&self) -> Vec<String>;
+}
+
+impl Store for HashMap<String, String> {
+    fn get(&self, key: &str) -> Option<String> {
+        HashMap::get(self, key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        self.insert(key.to_string(), value.to_string());
+    }
+
+    fn remove(&mut self, key: &str) {
+        HashMap::remove(self, key);
+    }
+
+    fn keys(&self) -> Vec<String> {
+        HashMap::keys(self).cloned().collect()
+    }
+}
+
+/// Return the storage key of the value with the given `key` inside the workspace called `name`.
+pub fn storage_key(name: &str, key: &str) -> String {
+    format!("workspace/{name}/{key}")
+}
+
+/// List of named workspaces, each of which holds a separate copy of the IDE state.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Workspaces {
+    /// Names of the workspaces, in order of creation.
+    names: Vec<String>,
+    /// Index of the workspace that is open.
+    active: usize,
+}
+
+impl Default for Workspaces {
+    fn default() -> Self {
+        Self {
+            names: vec![DEFAULT_WORKSPACE.to_string()],
+            active: 0,
+        }
+    }
+}
+
+impl Workspaces {
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Return the name of the workspace that is open.
+    pub fn active(&self) -> &str {
+        &self.names[self.active]
+    }
+
+    /// Check if `name` can be used as the name of a new workspace.
+    pub fn is_valid_name(&self, name: &str) -> bool {
+        !name.trim().is_empty()
+            && !name.contains(['\n', '/'])
+            && self.names.iter().all(|existing| existing != name)
+    }
+
+    /// Add an empty workspace with the given `name`.
+    pub fn create(&mut self, name: &str) -> Result<(), String> {
+        if !self.is_valid_name(name) {
+            return Err(format!("Invalid workspace name: `{name}`"));
+        }
+        self.names.push(name.to_string());
+        Ok(())
+    }
+
+    /// Open the workspace with the given `name`.
+    pub fn open(&mut self, name: &str) -> Result<(), String> {
+        self.active = self
+            .names
+            .iter()
+            .position(|existing| existing == name)
+            .ok_or(format!("Unknown workspace: `{name}`"))?;
+        Ok(())
+    }
+
+    /// Remove the workspace with the given `name`.
+    ///
+    /// If the workspace is open, then the first remaining workspace is opened.
+    /// The last remaining workspace cannot be removed.
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        let index = self
+            .names
+            .iter()
+            .position(|existing| existing == name)
+            .ok_or(format!("Unknown workspace: `{name}`"))?;
+        if self.names.len() == 1 {
+            return Err("The last workspace cannot be deleted".to_string());
+        }
+        self.names.remove(index);
+        if index == self.active {
+            self.active = 0;
+        } else if index < self.active {
+            self.active -= 1;
+        }
+        Ok(())
+    }
+
+    /// Return `base` if it is unused as workspace name,
+    /// and otherwise `base` followed by the lowest number that makes it unused.
+    pub fn unused_name(&self, base: &str) -> String {
+        (1..)
+            .map(|number| match number {
+                1 => base.to_string(),
+                _ => format!("{base} {number}"),
+            })
+            .find(|name| self.is_valid_name(name))
+            .expect("there should be an unused name")
+    }
+
+    /// Return an unused name for a copy of the workspace with the given `name`.
+    pub fn copy_name(&self, name: &str) -> String {
+        self.unused_name(&format!("{name} (copy)"))
+    }
+
+    /// Parse the list of workspaces that [`fmt::Display`] produced.
+    ///
+    /// The first line is the schema version, the second line is the name of the open workspace,
+    /// and each following line is the name of a workspace.
+    pub fn parse(s: &str) -> Option<(u32, Self)> {
+        let mut lines = s.lines();
+        let version = lines.next()?.parse::<u32>().ok()?;
+        let active = lines.next()?;
+        let names: Vec<String> = lines.map(str::to_string).collect();
+        let active = names.iter().position(|name| name == active)?;
+        Some((version, Self { names, active }))
+    }
+
+    /// Rebuild the list of workspaces from the workspace values in the `store`,
+    /// for when the stored list is lost.
+    ///
+    /// The workspaces are sorted by name and the first one is open.
+    /// Return `None` if the store holds no workspace values.
+    pub fn recover<S: Store>(store: &S) -> Option<Self> {
+        let mut names: Vec<String> = store
+            .keys()
+            .iter()
+            .filter_map(|key| key.strip_prefix("workspace/")?.split_once('/'))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        names.sort();
+        names.dedup();
+        match names.is_empty() {
+            true => None,
+            false => Some(Self { names, active: 0 }),
+        }
+    }
+}
+
+impl fmt::Display for Workspaces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{SCHEMA_VERSION}\n{}", self.active())?;
+        for name in &self.names {
+            write!(f, "\n{name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Copy the values with the given `keys` from the workspace `from` to the workspace `to`.
+pub fn copy_values<S: Store>(store: &mut S, keys: &[&str], from: &str, to: &str) {
+    for key in keys {
+        match store.get(&storage_key(from, key)) {
+            Some(value) => store.set(&storage_key(to, key), &value),
+            None => store.remove(&storage_key(to, key)),
+        }
+    }
+}
+
+/// Create a workspace from the given `values` with their keys and open it.
+///
+/// The name of the workspace starts with `base`. Values whose key is not one of `keys` are ignored.
+/// Each value is passed through `encode` with its key and its storage key before it is stored,
+/// and it is left out if `encode` returns `None`.
+///
+/// Return the name of the workspace.
+pub fn import<S, F>(
+    store: &mut S,
+    workspaces: &mut Workspaces,
+    base: &str,
+    keys: &[&str],
+    values: &[(String, String)],
+    mut encode: F,
+) -> Result<String, String>
+where
+    S: Store,
+    F: FnMut(&str, &str, String) -> Option<String>,
+{
+    let name = workspaces.unused_name(base);
+    workspaces.create(&name)?;
+    for (key, value) in values {
+        if !keys.contains(&key.as_str()) {
+            continue;
+        }
+        let storage_key = storage_key(&name, key);
+        if let Some(value) = encode(key, &storage_key, value.clone()) {
+            store.set(&storage_key, &value);
+        }
+    }
+    workspaces.open(&name)?;
+    store.set(WORKSPACES_KEY, &workspaces.to_string());
+    Ok(name)
+}
+
+/// Remove the values with the given `keys` of the workspace called `name`.
+pub fn remove_values<S: Store>(store: &mut S, keys: &[&str], name: &str) {
+    for key in keys {
+        store.remove(&storage_key(name, key));
+    }
+}
+
+/// Load the list of workspaces from the `store`,
+/// after converting values that were stored with an older schema.
+///
+/// The values with the given `keys` make up the state of a workspace.
+pub fn migrate<S: Store>(store: &mut S, keys: &[&str]) -> Workspaces {
+    let parsed = store.get(WORKSPACES_KEY).map(|s| Workspaces::parse(&s));
+    let (mut version, mut workspaces) = match parsed {
+        Some(Some(parsed)) => parsed,
+        // The list is corrupt, but the values of its workspaces are still there
+        Some(None) => match Workspaces::recover(store) {
+            Some(workspaces) => (SCHEMA_VERSION, workspaces),
+            None => (0, Workspaces::default()),
+        },
+        // Before version 1, there was a single workspace whose values were stored without prefix
+        None => (0, Workspaces::default()),
+    };
+
+    while version < SCHEMA_VERSION {
+        match version {
+            0 => {
+                for key in keys {
+                    if let Some(value) = store.get(key) {
+                        store.set(&storage_key(DEFAULT_WORKSPACE, key), &value);
+                        store.remove(key);
+                    }
+                }
+                workspaces = Workspaces::default();
+            }
+            _ => unreachable!("there should be a migration for each version"),
+        }
+        version += 1;
+    }
+
+    store.set(WORKSPACES_KEY, &workspaces.to_string());
+    workspaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn manage_workspaces() {
+        let mut workspaces = Workspaces::default();
+        workspaces.create("Vault").unwrap();
+        workspaces.create("Swap").unwrap();
+        assert!(workspaces.create("Vault").is_err());
+        assert!(workspaces.create("a/b").is_err());
+        assert_eq!("Vault (copy)", workspaces.copy_name("Vault"));
+        workspaces.create("Vault (copy)").unwrap();
+        assert_eq!("Vault (copy) 2", workspaces.copy_name("Vault"));
+        workspaces.remove("Vault (copy)").unwrap();
+
+        workspaces.open("Swap").unwrap();
+        assert_eq!("Swap", workspaces.active());
+        workspaces.remove("Vault").unwrap();
+        assert_eq!("Swap", workspaces.active());
+        workspaces.remove("Swap").unwrap();
+        assert_eq!(DEFAULT_WORKSPACE, workspaces.active());
+        assert!(workspaces.remove(DEFAULT_WORKSPACE).is_err());
+
+        workspaces.create("Vault").unwrap();
+        workspaces.open("Vault").unwrap();
+        let s = workspaces.to_string();
+        assert_eq!(Some((SCHEMA_VERSION, workspaces)), Workspaces::parse(&s));
+        assert_eq!(None, Workspaces::parse("1\nUnknown\nDefault"));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn migrate_legacy_values() {
+        let mut store = HashMap::new();
+        store.set("program", "fn main() {}");
+        store.set("txid", "00");
+        store.set("encryption_salt", "ff");
+
+        let workspaces = migrate(&mut store, &["program", "txid", "fee"]);
+        assert_eq!(Workspaces::default(), workspaces);
+        assert_eq!(None, Store::get(&store, "program"));
+        assert_eq!(
+            Some("fn main() {}".to_string()),
+            Store::get(&store, &storage_key(DEFAULT_WORKSPACE, "program"))
+        );
+        assert_eq!(
+            None,
+            Store::get(&store, &storage_key(DEFAULT_WORKSPACE, "fee"))
+        );
+        // Values outside of workspaces stay where they are
+        assert_eq!(
+            Some("ff".to_string()),
+            Store::get(&store, "encryption_salt")
+        );
+
+        // Migration is idempotent
+        let migrated = store.clone();
+        migrate(&mut store, &["program", "txid", "fee"]);
+        assert_eq!(migrated, store);
+
+        copy_values(&mut store, &["program", "txid"], DEFAULT_WORKSPACE, "Copy");
+        assert_eq!(
+            Some("00".to_string()),
+            Store::get(&store, &storage_key("Copy", "txid"))
+        );
+        remove_values(&mut store, &["program", "txid"], "Copy");
+        assert_eq!(None, Store::get(&store, &storage_key("Copy", "txid")));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn recover_corrupt_list() {
+        let mut store = HashMap::new();
+        store.set(&storage_key("Vault", "program"), "fn main() {}");
+        store.set(&storage_key("Swap", "program"), "fn main() {}");
+        store.set(&storage_key("Swap", "txid"), "00");
+        store.set(WORKSPACES_KEY, "garbage");
+
+        let workspaces = migrate(&mut store, &["program", "txid"]);
+        assert_eq!(["Swap", "Vault"], workspaces.names());
+        assert_eq!("Swap", workspaces.active());
+        assert_eq!(
+            Some(workspaces.to_string()),
+            Store::get(&store, WORKSPACES_KEY)
+        );
+        assert_eq!(
+            Some("00".to_string()),
+            Store::get(&store, &storage_key("Swap", "txid"))
+        );
+
+        let mut empty = HashMap::new();
+        empty.set(WORKSPACES_KEY, "garbage");
+        assert_eq!(Workspaces::default(), migrate(&mut empty, &["program"]));
+    }
+
+    #[test]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn import_values() {
+        let mut store = HashMap::new();
+        store.set(&storage_key(DEFAULT_WORKSPACE, "program"), "fn main() {}");
+        let mut workspaces = migrate(&mut store, &["program", "random_seed"]);
+        workspaces.create("Shared").unwrap();
+
+        let values = [
+            ("program", "fn main() { assert!(true) }"),
+            ("random_seed", "42"),
+            ("unknown", "value"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+        let name = import(
+            &mut store,
+            &mut workspaces,
+            "Shared",
+            &["program", "random_seed"],
+            &values,
+            |key, storage_key, value| match key {
+                "random_seed" => Some(format!("{storage_key}:{value}")),
+                _ => Some(value),
+            },
+        )
+        .unwrap();
+
+        assert_eq!("Shared 2", name);
+        assert_eq!("Shared 2", workspaces.active());
+        assert_eq!(
+            Some(workspaces.to_string()),
+            Store::get(&store, WORKSPACES_KEY)
+        );
+        assert_eq!(
+            Some("fn main() { assert!(true) }".to_string()),
+            Store::get(&store, &storage_key(&name, "program"))
+        );
+        assert_eq!(
+            Some(format!("{}:42", storage_key(&name, "random_seed"))),
+            Store::get(&store, &storage_key(&name, "random_seed"))
+        );
+        assert_eq!(None, Store::get(&store, &storage_key(&name, "unknown")));
+        // The values of the other workspaces stay as they are
+        assert_eq!(
+            Some("fn main() {}".to_string()),
+            Store::get(&store, &storage_key(DEFAULT_WORKSPACE, "program"))
+        );
+    }
+}